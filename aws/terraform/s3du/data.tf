@@ -70,6 +70,8 @@ data aws_iam_policy_document s3 {
 
     actions = [
       "s3:GetBucketLocation",
+      "s3:GetBucketVersioning",
+      "s3:GetLifecycleConfiguration",
       "s3:ListAllMyBuckets",
       "s3:ListBucket",
       "s3:ListBucketMultipartUploads",
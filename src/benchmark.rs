@@ -0,0 +1,143 @@
+// benchmark: Sizes the bundled test fixtures to check that a build works
+#![forbid(unsafe_code)]
+#![deny(missing_docs)]
+use anyhow::{
+    anyhow,
+    Result,
+};
+use crate::common::{
+    Bucket,
+    BucketSizer,
+    ObjectVersions,
+};
+use crate::s3;
+use std::io::Write;
+use std::time::Instant;
+
+/// Number of times that each scenario is sized.
+const ITERATIONS: u32 = 100;
+
+/// A listing of the current objects in a bucket.
+const LIST_OBJECTS: &str = include_str!("../test-data/s3-list-objects.xml");
+
+/// A listing of the object versions in a bucket.
+const LIST_OBJECT_VERSIONS: &str =
+    include_str!("../test-data/s3-list-object-versions.xml");
+
+/// A listing of the in-progress multipart uploads in a bucket.
+const LIST_MULTIPART_UPLOADS: &str =
+    include_str!("../test-data/s3-list-multipart-uploads.xml");
+
+/// A listing of the parts of a multipart upload.
+const LIST_PARTS: &str = include_str!("../test-data/s3-list-parts.xml");
+
+/// The scenarios that are sized, as their name, the object versions sized,
+/// the expected size in bytes and the responses to replay for each sizing.
+const SCENARIOS: &[(&str, ObjectVersions, u64, &[&str])] = &[
+    (
+        "current",
+        ObjectVersions::Current,
+        33_792,
+        &[
+            LIST_OBJECTS,
+        ],
+    ),
+    (
+        "non-current",
+        ObjectVersions::NonCurrent,
+        166_498,
+        &[
+            LIST_OBJECT_VERSIONS,
+        ],
+    ),
+    (
+        "multipart",
+        ObjectVersions::Multipart,
+        204_800,
+        &[
+            LIST_MULTIPART_UPLOADS,
+            LIST_PARTS,
+        ],
+    ),
+    (
+        "all",
+        ObjectVersions::All,
+        805_532,
+        &[
+            LIST_MULTIPART_UPLOADS,
+            LIST_PARTS,
+            LIST_OBJECT_VERSIONS,
+        ],
+    ),
+];
+
+/// Size each scenario against S3 `Client`s replaying the bundled fixtures,
+/// writing the time taken to `out`.
+///
+/// No requests are made to AWS. The timings include creating each `Client`,
+/// so they're only a rough baseline for comparing machines and builds.
+pub async fn run(out: &mut dyn Write) -> Result<()> {
+    let bucket = Bucket {
+        name:          "test-bucket".into(),
+        region:        None,
+        storage_types: None,
+        is_versioned:  None,
+        created:       None,
+    };
+
+    for (name, versions, expected, fixtures) in SCENARIOS {
+        let start = Instant::now();
+
+        for _ in 0..ITERATIONS {
+            let responses = fixtures.iter()
+                .map(|fixture| (200, (*fixture).to_string()))
+                .collect();
+
+            let client = s3::Client::replay(responses, *versions);
+            let size   = client.bucket_size(&bucket).await?;
+
+            if size.bytes != *expected {
+                return Err(anyhow!(
+                    "Benchmark '{}' sized {} bytes, expected {}",
+                    name,
+                    size.bytes,
+                    expected,
+                ));
+            }
+        }
+
+        let elapsed = start.elapsed();
+
+        writeln!(
+            out,
+            "{}: {} iterations in {:.3}s ({:.1}µs each)",
+            name,
+            ITERATIONS,
+            elapsed.as_secs_f64(),
+            elapsed.as_secs_f64() * 1_000_000.0 / f64::from(ITERATIONS),
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_run() {
+        let mut out = Vec::new();
+
+        run(&mut out).await.unwrap();
+
+        let out = String::from_utf8(out).unwrap();
+
+        let names: Vec<&str> = out.lines()
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, _)| name)
+            .collect();
+
+        assert_eq!(names, vec!["current", "non-current", "multipart", "all"]);
+    }
+}
@@ -0,0 +1,341 @@
+// cache: Caches bucket sizes in a local file between runs
+#![forbid(unsafe_code)]
+#![deny(missing_docs)]
+use anyhow::{
+    Context,
+    Result,
+};
+use aws_smithy_types::date_time::{
+    DateTime,
+    Format,
+};
+use crate::common::BucketSize;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use std::fs::{
+    self,
+    File,
+};
+use std::io::{
+    BufWriter,
+    ErrorKind,
+    Write,
+};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{
+    Duration,
+    SystemTime,
+};
+use tracing::{
+    debug,
+    warn,
+};
+
+/// A bucket size recorded in the cache file.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+struct CacheEntry {
+    /// Name of the bucket.
+    bucket: String,
+
+    /// The mode that the bucket was sized in, such as `s3`.
+    mode: String,
+
+    /// The object versions that were sized, such as `current`.
+    ///
+    /// This is only known in S3 mode and is omitted otherwise.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    object_versions: Option<String>,
+
+    /// Size of the bucket in bytes.
+    bytes: u64,
+
+    /// Time that the bucket was sized, in RFC 3339 format.
+    timestamp: String,
+}
+
+impl CacheEntry {
+    /// Returns how long ago the bucket was sized, or `None` if the timestamp
+    /// can't be parsed.
+    ///
+    /// A timestamp in the future, due to clock skew, is treated as brand new.
+    fn age(&self) -> Option<Duration> {
+        let sized = DateTime::from_str(&self.timestamp, Format::DateTime)
+            .ok()?;
+
+        let sized = SystemTime::try_from(sized).ok()?;
+
+        let age = SystemTime::now()
+            .duration_since(sized)
+            .unwrap_or_default();
+
+        Some(age)
+    }
+}
+
+/// Bucket sizes cached in a local JSON file between runs.
+///
+/// Entries are keyed by bucket name, the mode and, in S3 mode, the object
+/// versions that were sized, so that sizes from different queries don't
+/// collide. Entries for other queries are kept when the cache is saved.
+pub struct SizeCache {
+    /// Path of the cache file.
+    path: PathBuf,
+
+    /// Cached sizes younger than this are reused, if given.
+    ///
+    /// If this isn't given, sizes are only recorded, never reused.
+    max_age: Option<Duration>,
+
+    /// The mode that buckets are being sized in.
+    mode: String,
+
+    /// The object versions that are being sized, if known.
+    object_versions: Option<String>,
+
+    /// Every entry in the cache, including those for other queries.
+    entries: Mutex<Vec<CacheEntry>>,
+}
+
+impl SizeCache {
+    /// Return the `SizeCache` in the file at `path`, for buckets sized in
+    /// `mode` with the given `object_versions`.
+    ///
+    /// A missing cache file is treated as an empty cache. So is a cache file
+    /// that can't be read, with a warning, as it will be replaced once the
+    /// buckets have been sized.
+    pub fn load(
+        path: PathBuf,
+        max_age: Option<Duration>,
+        mode: &str,
+        object_versions: Option<String>,
+    ) -> Self {
+        let entries = match fs::read_to_string(&path) {
+            Ok(data) => {
+                serde_json::from_str(&data).unwrap_or_else(|e| {
+                    warn!(
+                        "Ignoring invalid cache '{}': {}",
+                        path.display(),
+                        e,
+                    );
+
+                    Vec::new()
+                })
+            },
+            Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(e) => {
+                warn!("Ignoring unreadable cache '{}': {}", path.display(), e);
+
+                Vec::new()
+            },
+        };
+
+        debug!("load: Loaded {} cache entries", entries.len());
+
+        Self {
+            path,
+            max_age,
+            object_versions,
+            entries: Mutex::new(entries),
+            mode:    mode.to_string(),
+        }
+    }
+
+    /// Returns a `bool` indicating if `entry` is for `bucket` in our query.
+    fn is_match(&self, entry: &CacheEntry, bucket: &str) -> bool {
+        entry.bucket == bucket
+            && entry.mode == self.mode
+            && entry.object_versions == self.object_versions
+    }
+
+    /// Return the cached size of `bucket`, if it was sized within `max_age`.
+    ///
+    /// Only the size in bytes is cached, so other details such as the object
+    /// count are unknown for cached sizes.
+    pub fn get(&self, bucket: &str) -> Option<BucketSize> {
+        let max_age = self.max_age?;
+
+        let entries = self.entries.lock().expect("cache lock");
+
+        let entry = entries.iter()
+            .find(|entry| self.is_match(entry, bucket))?;
+
+        let age = entry.age()?;
+
+        if age > max_age {
+            debug!("get: Cached size of '{}' is too old", bucket);
+
+            return None;
+        }
+
+        debug!("get: Using cached size of '{}'", bucket);
+
+        Some(BucketSize::from(entry.bytes))
+    }
+
+    /// Record `size` as the size of `bucket`, sized now.
+    pub fn insert(&self, bucket: &str, size: &BucketSize) {
+        let timestamp = DateTime::from(SystemTime::now())
+            .fmt(Format::DateTime)
+            .expect("current time formats");
+
+        let entry = CacheEntry {
+            timestamp,
+            bucket:          bucket.to_string(),
+            bytes:           size.bytes,
+            mode:            self.mode.clone(),
+            object_versions: self.object_versions.clone(),
+        };
+
+        let mut entries = self.entries.lock().expect("cache lock");
+
+        match entries.iter_mut().find(|entry| self.is_match(entry, bucket)) {
+            Some(existing) => *existing = entry,
+            None           => entries.push(entry),
+        }
+    }
+
+    /// Write every entry to the cache file, replacing its contents.
+    pub fn save(&self) -> Result<()> {
+        let entries = self.entries.lock().expect("cache lock");
+
+        let file = File::create(&self.path)
+            .with_context(|| {
+                format!("Failed to create cache '{}'", self.path.display())
+            })?;
+
+        let mut writer = BufWriter::new(file);
+
+        serde_json::to_writer_pretty(&mut writer, &*entries)?;
+        writeln!(writer)?;
+        writer.flush()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    // Return a path in the temporary directory for a cache file named after
+    // the test.
+    fn cache_path(name: &str) -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("s3du-test-cache-{}-{}", name, std::process::id()))
+    }
+
+    #[test]
+    fn test_load_missing() {
+        let path  = cache_path("missing");
+        let cache = SizeCache::load(path, None, "s3", None);
+
+        assert!(cache.entries.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_load_invalid() {
+        let path = cache_path("invalid");
+
+        fs::write(&path, "not json").unwrap();
+
+        let cache = SizeCache::load(path.clone(), None, "s3", None);
+
+        fs::remove_file(&path).unwrap();
+
+        assert!(cache.entries.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_get() {
+        let max_age = Some(Duration::from_secs(3600));
+
+        let now = DateTime::from(SystemTime::now())
+            .fmt(Format::DateTime)
+            .unwrap();
+
+        let entry = |bucket: &str, versions: &str, timestamp: &str| {
+            CacheEntry {
+                bucket:          bucket.into(),
+                bytes:           1024,
+                mode:            "s3".into(),
+                object_versions: Some(versions.into()),
+                timestamp:       timestamp.into(),
+            }
+        };
+
+        let tests = vec![
+            (entry("bucket", "current", &now),                  max_age, true),
+            (entry("bucket", "current", &now),                  None,    false),
+            (entry("bucket", "all",     &now),                  max_age, false),
+            (entry("other",  "current", &now),                  max_age, false),
+            (entry("bucket", "current", "2020-03-12T11:04:09Z"), max_age, false),
+            (entry("bucket", "current", "not a timestamp"),     max_age, false),
+        ];
+
+        for test in tests {
+            let entry    = test.0;
+            let max_age  = test.1;
+            let expected = test.2;
+
+            let cache = SizeCache {
+                max_age,
+                entries:         Mutex::new(vec![entry.clone()]),
+                mode:            "s3".into(),
+                object_versions: Some("current".into()),
+                path:            cache_path("get"),
+            };
+
+            let expected = expected.then(|| BucketSize::from(1024));
+
+            assert_eq!(cache.get("bucket"), expected, "{entry:?}");
+        }
+    }
+
+    #[test]
+    fn test_insert_save_load() {
+        let path    = cache_path("insert");
+        let max_age = Some(Duration::from_secs(3600));
+
+        let cache = SizeCache::load(
+            path.clone(),
+            max_age,
+            "s3",
+            Some("current".into()),
+        );
+
+        cache.insert("a-bucket", &BucketSize::from(1024));
+        cache.insert("b-bucket", &BucketSize::from(2048));
+        cache.insert("a-bucket", &BucketSize::from(4096));
+        cache.save().unwrap();
+
+        // A different query doesn't see these sizes, but keeps them.
+        let other = SizeCache::load(
+            path.clone(),
+            max_age,
+            "s3",
+            Some("all".into()),
+        );
+
+        assert_eq!(other.get("a-bucket"), None);
+
+        other.insert("a-bucket", &BucketSize::from(8192));
+        other.save().unwrap();
+
+        let cache = SizeCache::load(
+            path.clone(),
+            max_age,
+            "s3",
+            Some("current".into()),
+        );
+
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(cache.entries.lock().unwrap().len(), 3);
+        assert_eq!(cache.get("a-bucket"), Some(BucketSize::from(4096)));
+        assert_eq!(cache.get("b-bucket"), Some(BucketSize::from(2048)));
+    }
+}
@@ -11,16 +11,48 @@ use clap::{
     Command,
 };
 use clap::builder::PossibleValuesParser;
+use glob::Pattern;
+use crate::common::{
+    bucket_pattern,
+    is_wildcard,
+    parse_human_size,
+    Placeholder,
+    SizeUnit,
+    Template,
+};
+use crate::proxy;
+
+#[cfg(feature = "s3")]
+use crate::storage_lens;
+
+#[cfg(feature = "s3")]
+use clap::error::ErrorKind;
+
+#[cfg(feature = "s3")]
+use clap::parser::ValueSource;
 use once_cell::sync::Lazy;
 use std::env;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::time::Duration;
 use tracing::debug;
 
 #[cfg(feature = "s3")]
 use http::Uri;
 
+#[cfg(feature = "cloudwatch")]
+use std::num::NonZeroU32;
+
 // Our fallback default region if we fail to find a region in the environment
 const FALLBACK_REGION: &str = "us-east-1";
 
+/// Region used with a custom endpoint when no region has been given.
+///
+/// Single region S3 compatible storage, such as `MinIO`, will accept any
+/// region, this makes it obvious that the region isn't an AWS one.
+#[cfg(feature = "s3")]
+const CUSTOM_ENDPOINT_REGION: &str = "custom";
+
 // This catches cases where we've compiled with either:
 //   - Only "cloudwatch"
 //   - Both "cloudwatch" and "s3"
@@ -34,6 +66,32 @@ const DEFAULT_MODE: &str = "cloudwatch";
 #[cfg(all(feature = "s3", not(feature = "cloudwatch")))]
 const DEFAULT_MODE: &str = "s3";
 
+/// Default number of days of `CloudWatch` metrics to look back through for
+/// the latest datapoint.
+#[cfg(feature = "cloudwatch")]
+const DEFAULT_CLOUDWATCH_LOOKBACK_DAYS: &str = "2";
+
+/// Default statistic of each day's `CloudWatch` metrics to use.
+#[cfg(feature = "cloudwatch")]
+const DEFAULT_STATISTIC: &str = "average";
+
+/// Default number of buckets that are sized at once.
+const DEFAULT_CONCURRENCY: &str = "8";
+
+/// Default number of buckets that can be sized in S3 mode without asking for
+/// confirmation.
+#[cfg(feature = "s3")]
+const DEFAULT_CONFIRM_THRESHOLD: &str = "100";
+
+/// Default number of times that a throttled S3 listing request is retried.
+#[cfg(feature = "s3")]
+const DEFAULT_MAX_RETRIES: &str = "3";
+
+/// Default delay in milliseconds before the first retry of a throttled S3
+/// listing request.
+#[cfg(feature = "s3")]
+const DEFAULT_RETRY_BASE_DELAY_MS: &str = "100";
+
 /// Default object versions to sum in S3 mode.
 #[cfg(feature = "s3")]
 const DEFAULT_OBJECT_VERSIONS: &str = "current";
@@ -56,25 +114,67 @@ static DEFAULT_REGION: Lazy<String> = Lazy::new(|| {
     let region = possibilities
         .iter()
         .find_map(|region| region.as_ref().ok())
-        .map_or_else(
-            || FALLBACK_REGION,
-            |region| region,
-        );
+        .map_or(FALLBACK_REGION, |region| region);
 
     region.to_string()
 });
 
+/// Default output format.
+const DEFAULT_FORMAT: &str = "plain";
+
 /// Default unit to display sizes in.
 const DEFAULT_UNIT: &str = "binary";
 
 // This should match the string values in the ClientMode FromStr impl in
-// common.
+// common, other than `auto` which is resolved by `client_mode`.
 /// Valid modes for the `--mode` command line switch.
 const VALID_MODES: &[&str] = &[
+    #[cfg(all(feature = "cloudwatch", feature = "s3"))]
+    "auto",
     #[cfg(feature = "cloudwatch")]
     "cloudwatch",
     #[cfg(feature = "s3")]
     "s3",
+    #[cfg(feature = "s3")]
+    "storage-lens",
+];
+
+/// Flags that only have an effect in S3 mode, giving any of these selects S3
+/// mode with `--mode auto`.
+#[cfg(all(feature = "cloudwatch", feature = "s3"))]
+const S3_ONLY_FLAGS: &[&str] = &[
+    "ALL_REGIONS",
+    "ASSUME_REGION",
+    "BREAKDOWN_VERSIONS",
+    "FALLBACK_ON_UNSUPPORTED",
+    "MISSING_LIFECYCLE",
+    "NO_PARALLEL_SUM",
+    "PARALLEL_PREFIXES",
+    "PATH_STYLE",
+    "PREWARM",
+    "SHOW_MULTIPART_AGE",
+    "WARN_VERSIONED",
+];
+
+/// Options with a default value that only have an effect in S3 mode, giving
+/// any of these on the command line or in the environment selects S3 mode
+/// with `--mode auto`.
+#[cfg(all(feature = "cloudwatch", feature = "s3"))]
+const S3_ONLY_DEFAULTED_ARGS: &[&str] = &[
+    "CONFIRM_THRESHOLD",
+    "MAX_RETRIES",
+    "RETRY_BASE_DELAY_MS",
+];
+
+// This should match the string values in the OutputFormat FromStr impl in
+// common.
+/// Valid output formats for the `--format` command line switch.
+const VALID_OUTPUT_FORMATS: &[&str] = &[
+    "json",
+    "markdown",
+    "plain",
+    "prometheus",
+    "template",
 ];
 
 // This should match the string values in the UnitSize FromStr impl in common.
@@ -83,6 +183,90 @@ const VALID_SIZE_UNITS: &[&str] = &[
     "binary",
     "bytes",
     "decimal",
+    "gb",
+    "gib",
+    "kb",
+    "kib",
+    "mb",
+    "mib",
+    "tb",
+    "tib",
+];
+
+// This should match the string values in the SortKey FromStr impl in common.
+/// Valid sort keys for the `--sort` command line switch.
+const VALID_SORT_KEYS: &[&str] = &[
+    "name",
+    "size",
+];
+
+// This should match the string values in the SortOrder FromStr impl in
+// common.
+/// Valid sort orders for the `--order` command line switch.
+const VALID_SORT_ORDERS: &[&str] = &[
+    "asc",
+    "desc",
+];
+
+// This should match the string values in the MetricStatistic FromStr impl in
+// common.
+/// Valid `CloudWatch` statistics for the `--statistic` command line switch.
+#[cfg(feature = "cloudwatch")]
+const VALID_STATISTICS: &[&str] = &[
+    "average",
+    "maximum",
+    "minimum",
+];
+
+// These are the StorageType dimension values that CloudWatch reports the
+// BucketSizeBytes metric under.
+/// Valid `CloudWatch` storage types for the `--storage-type` switch.
+#[cfg(feature = "cloudwatch")]
+const VALID_STORAGE_TYPES: &[&str] = &[
+    "DeepArchiveObjectOverhead",
+    "DeepArchiveS3ObjectOverhead",
+    "DeepArchiveStagingStorage",
+    "DeepArchiveStorage",
+    "GlacierIRSizeOverhead",
+    "GlacierInstantRetrievalStorage",
+    "GlacierObjectOverhead",
+    "GlacierS3ObjectOverhead",
+    "GlacierStagingStorage",
+    "GlacierStorage",
+    "IntelligentTieringAAStorage",
+    "IntelligentTieringAIAStorage",
+    "IntelligentTieringDAAStorage",
+    "IntelligentTieringFAStorage",
+    "IntelligentTieringIAStorage",
+    "OneZoneIASizeOverhead",
+    "OneZoneIAStorage",
+    "ReducedRedundancyStorage",
+    "StandardIASizeOverhead",
+    "StandardIAStorage",
+    "StandardStorage",
+];
+
+/// Valid values for the `--request-payer` switch.
+#[cfg(feature = "s3")]
+const VALID_REQUEST_PAYERS: &[&str] = &[
+    "requester",
+];
+
+// These are the storage classes that S3 reports for objects.
+/// Valid S3 storage classes for the `--storage-class` switch.
+#[cfg(feature = "s3")]
+const VALID_STORAGE_CLASSES: &[&str] = &[
+    "DEEP_ARCHIVE",
+    "EXPRESS_ONEZONE",
+    "GLACIER",
+    "GLACIER_IR",
+    "INTELLIGENT_TIERING",
+    "ONEZONE_IA",
+    "OUTPOSTS",
+    "REDUCED_REDUNDANCY",
+    "SNOW",
+    "STANDARD",
+    "STANDARD_IA",
 ];
 
 // This should match the ObjectVersions in the common.rs
@@ -91,6 +275,7 @@ const VALID_SIZE_UNITS: &[&str] = &[
 const OBJECT_VERSIONS: &[&str] = &[
     "all",
     "current",
+    "delete-markers",
     "multipart",
     "non-current",
 ];
@@ -100,14 +285,22 @@ const OBJECT_VERSIONS: &[&str] = &[
 /// This validation is taken from
 /// <https://docs.aws.amazon.com/AmazonS3/latest/dev/BucketRestrictions.html>.
 /// We validate based on the legacy standard for compatibility.
+///
+/// Bucket names may contain `*` and `?` wildcards, these may match names
+/// longer than themselves so aren't held to the minimum length.
 fn is_valid_aws_s3_bucket_name(s: &str) -> Result<String, String> {
     // Bucket name cannot be empty
     if s.is_empty() {
         return Err("Bucket name cannot be empty".into());
     }
 
+    if is_wildcard(s) {
+        bucket_pattern(s)
+            .map_err(|e| format!("Invalid bucket name wildcard: {e}"))?;
+    }
+
     // Bucket names must be at least 3...
-    if s.len() < 3 {
+    if s.len() < 3 && !is_wildcard(s) {
         return Err("Bucket name is too short".into());
     }
 
@@ -122,7 +315,9 @@ fn is_valid_aws_s3_bucket_name(s: &str) -> Result<String, String> {
 /// Ensures that a given endpoint is valid, where valid means:
 ///   - Is not an empty string
 ///   - Is not an AWS endpoint
-///   - Parses as a valid URL
+///   - Parses as a valid URL, with a host and, if given, a usable port
+///
+/// Hosts may be IPv6 literals in brackets, such as `http://[::1]:9000`.
 #[cfg(feature = "s3")]
 fn is_valid_endpoint(s: &str) -> Result<String, String> {
     // Endpoint cannot be an empty string
@@ -149,38 +344,227 @@ fn is_valid_endpoint(s: &str) -> Result<String, String> {
         },
     }?;
 
+    // The URI parser allows an empty host, which we can't connect to. IPv6
+    // literals are returned in their brackets, e.g. `[::1]`.
+    let hostname = match uri.host() {
+        Some(hostname) if !hostname.is_empty() => Ok(hostname),
+        _                                      => Err("No endpoint host found"),
+    }?;
+
     // Endpoint cannot be an AWS endpoint
-    if let Some(hostname) = uri.host() {
-        if hostname.contains("amazonaws.com") {
-            return Err("Endpoint cannot be used to specify AWS endpoints".into());
+    if hostname.contains("amazonaws.com") {
+        return Err("Endpoint cannot be used to specify AWS endpoints".into());
+    }
+
+    // The URI parser accepts any digits as a port, so check that any port
+    // given is one that we can connect to. The colons within an IPv6 literal
+    // aren't a port separator.
+    let authority = uri.authority().map_or("", |authority| authority.as_str());
+
+    let host_port = authority.rsplit_once('@')
+        .map_or(authority, |(_, host_port)| host_port);
+
+    let after_host = host_port.rsplit_once(']')
+        .map_or(host_port, |(_, after_host)| after_host);
+
+    if let Some((_, port)) = after_host.rsplit_once(':') {
+        match port.parse::<u16>() {
+            Ok(port) if port > 0 => {},
+            _ => return Err(format!("Invalid endpoint port '{port}'")),
         }
     }
 
+    // The endpoint is passed to the SDK exactly as given.
+    Ok(s.to_string())
+}
+
+/// Ensures that a given Storage Lens export is a valid S3 URI, such as
+/// `s3://bucket/prefix/`, with a bucket name.
+#[cfg(feature = "s3")]
+fn is_valid_storage_lens_export(s: &str) -> Result<String, String> {
+    if storage_lens::parse_s3_uri(s).is_none() {
+        return Err("Storage Lens export must be an S3 URI, such as s3://bucket/prefix/".into());
+    }
+
     Ok(s.to_string())
 }
 
+/// Ensures that a given role ARN is an IAM role ARN, such as
+/// `arn:aws:iam::123456789012:role/s3du`.
+fn is_valid_role_arn(s: &str) -> Result<String, String> {
+    let parts: Vec<&str> = s.splitn(6, ':').collect();
+
+    match parts[..] {
+        ["arn", partition, "iam", "", account, resource]
+            if !partition.is_empty()
+            && !account.is_empty()
+            && resource.starts_with("role/")
+            && resource.len() > "role/".len() => Ok(s.to_string()),
+        _ => Err("Role ARN must be an IAM role ARN, such as arn:aws:iam::123456789012:role/s3du".into()),
+    }
+}
+
+/// Parses a glob pattern that bucket names will be matched against, such as
+/// `*-logs`.
+fn parse_glob(s: &str) -> Result<Pattern, String> {
+    Pattern::new(s).map_err(|e| format!("Invalid glob pattern: {e}"))
+}
+
+/// Parses a duration given as a whole number of seconds, minutes or hours,
+/// such as `90`, `90s`, `5m` or `1h`.
+///
+/// A number without a suffix is taken to be seconds. Zero durations are
+/// rejected, as nothing could complete within them.
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    let (number, multiplier) = match s.char_indices().last() {
+        Some((i, 's')) => (&s[..i], 1),
+        Some((i, 'm')) => (&s[..i], 60),
+        Some((i, 'h')) => (&s[..i], 60 * 60),
+        _              => (s, 1),
+    };
+
+    let number: u64 = number.parse()
+        .map_err(|_| format!("Could not parse duration: {s}"))?;
+
+    if number == 0 {
+        return Err("Duration must be greater than zero".into());
+    }
+
+    let seconds = number.checked_mul(multiplier)
+        .ok_or_else(|| format!("Duration is too long: {s}"))?;
+
+    Ok(Duration::from_secs(seconds))
+}
+
+/// Parses a `du -B` style block size, such as `1`, `1K` or `K`, into the
+/// `SizeUnit` showing sizes as a number of those blocks.
+///
+/// As with du(1), a block size given as only a unit, such as `K`, is shown
+/// after each number of blocks.
+fn parse_block_size(s: &str) -> Result<SizeUnit, String> {
+    let (size, suffix) = if s.starts_with(|c: char| c.is_ascii_digit()) {
+        (parse_human_size(s)?, None)
+    }
+    else {
+        (parse_human_size(&format!("1{s}"))?, Some(s.to_string()))
+    };
+
+    if size == 0 {
+        return Err("Block size must be greater than zero".into());
+    }
+
+    Ok(SizeUnit::Blocks { size, suffix })
+}
+
+/// Parses a template for each bucket, which may use any of the bucket
+/// placeholders.
+fn parse_template(s: &str) -> Result<Template, String> {
+    Template::parse(s, Placeholder::BUCKET)
+}
+
+/// Parses a template for the total, which may use any of the total
+/// placeholders.
+fn parse_total_template(s: &str) -> Result<Template, String> {
+    Template::parse(s, Placeholder::TOTAL)
+}
+
 /// Create the command line parser
 fn create_app() -> Command {
     debug!("Creating CLI app");
 
     // Below is a little odd looking, as we try to specify an argument order
     // but also have some options behind features.
+    // The `-h` short flag is used for `--human-readable` to match du(1), so
+    // the help flag is only available in its long form.
     let app = Command::new(crate_name!())
         .about(crate_description!())
         .version(crate_version!())
+        .disable_help_flag(true)
         .arg(
             Arg::new("BUCKET")
-                .action(ArgAction::Set)
+                .action(ArgAction::Append)
                 .env("S3DU_BUCKET")
-                .help("Bucket to retrieve size of, retrieves all if not passed")
+                .help("Buckets to retrieve sizes of, retrieves all if not passed")
                 .hide_env_values(true)
                 .index(1)
+                .num_args(1..)
+                .value_delimiter(',')
                 .value_name("BUCKET")
                 .value_parser(is_valid_aws_s3_bucket_name)
         );
 
     #[cfg(feature = "s3")]
     let app = app
+        .arg(
+            Arg::new("ALL_REGIONS")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("ASSUME_REGION")
+                .help("Size buckets in every region, not just the client region, in S3 mode")
+                .long("all-regions")
+        );
+
+    #[cfg(feature = "s3")]
+    let app = app
+        .arg(
+            Arg::new("ASSUME_REGION")
+                .action(ArgAction::SetTrue)
+                .help("Assume all buckets are in the client region, skipping location lookups in S3 mode")
+                .long("assume-region")
+        );
+
+    // Hidden, as it's only useful for checking that a build works.
+    #[cfg(feature = "benchmark")]
+    let app = app
+        .arg(
+            Arg::new("BENCHMARK")
+                .action(ArgAction::SetTrue)
+                .help("Size the bundled test fixtures without using AWS, showing the time taken")
+                .hide(true)
+                .long("benchmark")
+        );
+
+    let app = app
+        .arg(
+            Arg::new("BLOCK_SIZE")
+                .action(ArgAction::Set)
+                .env("S3DU_BLOCK_SIZE")
+                .help("Show sizes as a number of SIZE blocks, rounded up, like du -B")
+                .hide_env_values(true)
+                .long("block-size")
+                .overrides_with_all(["HUMAN_READABLE", "UNIT"])
+                .short('B')
+                .value_name("SIZE")
+                .value_parser(parse_block_size)
+        );
+
+    #[cfg(feature = "cloudwatch")]
+    let app = app
+        .arg(
+            Arg::new("BREAKDOWN")
+                .action(ArgAction::SetTrue)
+                .help("Show the size of each storage type under each bucket in CloudWatch mode")
+                .long("breakdown")
+        );
+
+    #[cfg(feature = "s3")]
+    let app = app
+        .arg(
+            Arg::new("BREAKDOWN_VERSIONS")
+                .action(ArgAction::SetTrue)
+                .help("Show current, non-current and multipart sizes separately in S3 'all' mode")
+                .long("breakdown-versions")
+        )
+        .arg(
+            Arg::new("CONFIRM_THRESHOLD")
+                .action(ArgAction::Set)
+                .default_value(DEFAULT_CONFIRM_THRESHOLD)
+                .env("S3DU_CONFIRM_THRESHOLD")
+                .help("Ask for confirmation before sizing more than COUNT buckets in S3 mode")
+                .hide_env_values(true)
+                .long("confirm-threshold")
+                .value_name("COUNT")
+                .value_parser(clap::value_parser!(usize))
+        )
         .arg(
             Arg::new("ENDPOINT")
                 .action(ArgAction::Set)
@@ -193,12 +577,212 @@ fn create_app() -> Command {
                 .value_parser(is_valid_endpoint)
         );
 
-    let app = app.arg(
+    let app = app
+        .arg(
+            Arg::new("CACHE")
+                .action(ArgAction::Set)
+                .env("S3DU_CACHE")
+                .help("Record bucket sizes in FILE, reusing them with --max-age")
+                .hide_env_values(true)
+                .long("cache")
+                .value_name("FILE")
+                .value_parser(clap::value_parser!(PathBuf))
+        );
+
+    #[cfg(feature = "cloudwatch")]
+    let app = app
+        .arg(
+            Arg::new("CLOUDWATCH_LOOKBACK_DAYS")
+                .action(ArgAction::Set)
+                .default_value(DEFAULT_CLOUDWATCH_LOOKBACK_DAYS)
+                .env("S3DU_CLOUDWATCH_LOOKBACK_DAYS")
+                .help("Look back up to DAYS days for the latest metrics in CloudWatch mode")
+                .hide_env_values(true)
+                .long("cloudwatch-lookback-days")
+                .value_name("DAYS")
+                .value_parser(clap::value_parser!(NonZeroU32))
+        );
+
+    let app = app
+        .arg(
+            Arg::new("CONCURRENCY")
+                .action(ArgAction::Set)
+                .default_value(DEFAULT_CONCURRENCY)
+                .env("S3DU_CONCURRENCY")
+                .help("Size up to COUNT buckets at once")
+                .hide_env_values(true)
+                .long("concurrency")
+                .value_name("COUNT")
+                .value_parser(clap::value_parser!(NonZeroUsize))
+        )
+        .arg(
+            Arg::new("CONTINUE_ON_ERROR")
+                .action(ArgAction::SetTrue)
+                .help("Skip buckets that fail to be sized instead of exiting")
+                .long("continue-on-error")
+        )
+        .arg(
+            Arg::new("COUNT")
+                .action(ArgAction::SetTrue)
+                .help("Show the number of objects in each bucket")
+                .long("count")
+        );
+
+    #[cfg(feature = "cloudwatch")]
+    let app = app
+        .arg(
+            Arg::new("DATAPOINTS")
+                .action(ArgAction::SetTrue)
+                .help("Include the raw CloudWatch datapoints in JSON output in CloudWatch mode")
+                .long("datapoints")
+        );
+
+    let app = app
+        .arg(
+            Arg::new("EXCLUDE")
+                .action(ArgAction::Append)
+                .env("S3DU_EXCLUDE")
+                .help("Skip buckets with names matching PATTERN, may be given multiple times")
+                .hide_env_values(true)
+                .long("exclude")
+                .value_delimiter(',')
+                .value_name("PATTERN")
+                .value_parser(parse_glob)
+        )
+        .arg(
+            Arg::new("EXTERNAL_ID")
+                .action(ArgAction::Set)
+                .env("S3DU_EXTERNAL_ID")
+                .help("Pass the given external ID when assuming the --role-arn")
+                .hide_env_values(true)
+                .long("external-id")
+                .requires("ROLE_ARN")
+                .value_name("ID")
+        );
+
+    #[cfg(feature = "s3")]
+    let app = app
+        .arg(
+            Arg::new("FALLBACK_ON_UNSUPPORTED")
+                .action(ArgAction::SetTrue)
+                .help("Size only current objects if listing object versions is unsupported in S3 mode")
+                .long("fallback-on-unsupported")
+        );
+
+    let app = app
+        .arg(
+            Arg::new("FORMAT")
+                .action(ArgAction::Set)
+                .default_value(DEFAULT_FORMAT)
+                .env("S3DU_FORMAT")
+                .help("Sets the format to output bucket sizes in")
+                .hide_env_values(true)
+                .long("format")
+                .short('f')
+                .value_name("FORMAT")
+                .value_parser(PossibleValuesParser::new(VALID_OUTPUT_FORMATS))
+        )
+        .arg(
+            Arg::new("GROUP_DIGITS")
+                .action(ArgAction::SetTrue)
+                .help("Group the digits of sizes in thousands with '--unit bytes'")
+                .long("group-digits")
+        )
+        .arg(
+            Arg::new("HELP")
+                .action(ArgAction::Help)
+                .help("Print help")
+                .long("help")
+        )
+        .arg(
+            Arg::new("HUMAN_READABLE")
+                .action(ArgAction::SetTrue)
+                .help("Show sizes in binary units, equivalent to '--unit binary'")
+                .long("human-readable")
+                .overrides_with_all(["BLOCK_SIZE", "UNIT"])
+                .short('h')
+        );
+
+    let app = app
+        .arg(
+            Arg::new("KEEP_GOING")
+                .action(ArgAction::SetTrue)
+                .help("Skip buckets that fail to be sized, exiting with an error once the others are shown")
+                .long("keep-going")
+        )
+        .arg(
+            Arg::new("LIST_ONLY")
+                .action(ArgAction::SetTrue)
+                .help("List the buckets that would be sized, without sizing them")
+                .long("list-only")
+                .visible_alias("dry-run")
+        )
+        .arg(
+            Arg::new("MAX_AGE")
+                .action(ArgAction::Set)
+                .env("S3DU_MAX_AGE")
+                .help("Reuse cached bucket sizes younger than DURATION, e.g. 90s, 5m or 1h")
+                .hide_env_values(true)
+                .long("max-age")
+                .requires("CACHE")
+                .value_name("DURATION")
+                .value_parser(parse_duration)
+        )
+        .arg(
+            Arg::new("MAX_CONNECTIONS")
+                .action(ArgAction::Set)
+                .env("S3DU_MAX_CONNECTIONS")
+                .help("Keep at most COUNT idle HTTP connections open to each host for reuse")
+                .hide_env_values(true)
+                .long("max-connections")
+                .value_name("COUNT")
+                .value_parser(clap::value_parser!(usize))
+        );
+
+    #[cfg(feature = "s3")]
+    let app = app
+        .arg(
+            Arg::new("MAX_RETRIES")
+                .action(ArgAction::Set)
+                .default_value(DEFAULT_MAX_RETRIES)
+                .env("S3DU_MAX_RETRIES")
+                .help("Retry throttled S3 listing requests up to COUNT times in S3 mode")
+                .hide_env_values(true)
+                .long("max-retries")
+                .value_name("COUNT")
+                .value_parser(clap::value_parser!(u32))
+        );
+
+    #[cfg(feature = "s3")]
+    let app = app
+        .arg(
+            Arg::new("MISSING_LIFECYCLE")
+                .action(ArgAction::SetTrue)
+                .help("Only show buckets without a lifecycle rule expiring non-current versions in S3 mode")
+                .long("missing-lifecycle")
+        );
+
+    #[cfg(feature = "s3")]
+    let app = app
+        .arg(
+            Arg::new("MULTIPART_OLDER_THAN")
+                .action(ArgAction::Set)
+                .env("S3DU_MULTIPART_OLDER_THAN")
+                .help("Only size multipart uploads initiated more than DAYS days ago in S3 mode")
+                .hide_env_values(true)
+                .long("multipart-older-than")
+                .value_name("DAYS")
+                .value_parser(clap::value_parser!(u64))
+        );
+
+    #[cfg(any(feature = "cloudwatch", feature = "s3"))]
+    let app = app
+        .arg(
             Arg::new("MODE")
                 .action(ArgAction::Set)
                 .default_value(DEFAULT_MODE)
                 .env("S3DU_MODE")
-                .help("Use either CloudWatch or S3 to obtain bucket sizes")
+                .help("Use CloudWatch, S3, an S3 Storage Lens export or auto to obtain bucket sizes")
                 .hide_env_values(true)
                 .long("mode")
                 .short('m')
@@ -219,41 +803,557 @@ fn create_app() -> Command {
                 .short('o')
                 .value_name("VERSIONS")
                 .value_parser(PossibleValuesParser::new(OBJECT_VERSIONS))
+        )
+        .arg(
+            Arg::new("PARALLEL_PREFIXES")
+                .action(ArgAction::SetTrue)
+                .help("List top level prefixes concurrently when sizing current objects in S3 mode")
+                .long("parallel-prefixes")
+        )
+        .arg(
+            Arg::new("NO_PARALLEL_SUM")
+                .action(ArgAction::SetTrue)
+                .help("Sum object sizes on a single thread in S3 mode")
+                .long("no-parallel-sum")
+        )
+        .arg(
+            Arg::new("PATH_STYLE")
+                .action(ArgAction::SetTrue)
+                .help("Use path style addressing with a custom endpoint")
+                .long("path-style")
+                .requires("ENDPOINT")
         );
 
-    app.arg(
-            Arg::new("REGION")
+    let app = app
+        .arg(
+            Arg::new("NO_TOTAL")
+                .action(ArgAction::SetTrue)
+                .help("Do not show the total size of all buckets")
+                .long("no-total")
+                .overrides_with("TOTAL")
+        )
+        .arg(
+            Arg::new("ORDER")
                 .action(ArgAction::Set)
-                .default_value(&**DEFAULT_REGION)
-                .env("AWS_REGION")
-                .help("Set the AWS region to create the client in.")
+                .env("S3DU_ORDER")
+                .help("Sets the direction to sort buckets in, defaults to asc for names and desc for sizes")
                 .hide_env_values(true)
-                .long("region")
-                .short('r')
-                .value_name("REGION")
+                .long("order")
+                .requires("SORT")
+                .value_name("ORDER")
+                .value_parser(PossibleValuesParser::new(VALID_SORT_ORDERS))
         )
         .arg(
-            Arg::new("UNIT")
+            Arg::new("OUTPUT")
                 .action(ArgAction::Set)
-                .default_value(DEFAULT_UNIT)
-                .env("S3DU_UNIT")
-                .help("Sets the unit to use for size display")
+                .env("S3DU_OUTPUT")
+                .help("Write bucket sizes to FILE instead of standard output, truncating it if it exists")
                 .hide_env_values(true)
-                .long("unit")
-                .short('u')
-                .value_name("UNIT")
-                .value_parser(PossibleValuesParser::new(VALID_SIZE_UNITS))
+                .long("output")
+                .value_name("FILE")
+                .value_parser(clap::value_parser!(PathBuf))
         )
-}
-
-/// Parse the command line arguments
-pub fn parse_args() -> ArgMatches {
-    debug!("Parsing command line arguments");
-
-    create_app().get_matches()
-}
+        .arg(
+            Arg::new("PER_BUCKET_TIMEOUT")
+                .action(ArgAction::Set)
+                .env("S3DU_PER_BUCKET_TIMEOUT")
+                .help("Abort sizing a bucket if it takes longer than DURATION, e.g. 90s, 5m or 1h")
+                .hide_env_values(true)
+                .long("per-bucket-timeout")
+                .value_name("DURATION")
+                .value_parser(parse_duration)
+        )
+        .arg(
+            Arg::new("PRECISION")
+                .action(ArgAction::Set)
+                .env("S3DU_PRECISION")
+                .help("Show human readable sizes with PLACES decimal places")
+                .hide_env_values(true)
+                .long("precision")
+                .value_name("PLACES")
+                .value_parser(clap::value_parser!(usize))
+        );
 
-#[cfg(test)]
+    #[cfg(feature = "s3")]
+    let app = app
+        .arg(
+            Arg::new("PREFIX")
+                .action(ArgAction::Set)
+                .env("S3DU_PREFIX")
+                .help("Only size objects with keys beginning with PREFIX in S3 mode")
+                .hide_env_values(true)
+                .long("prefix")
+                .value_name("PREFIX")
+        )
+        .arg(
+            Arg::new("PREWARM")
+                .action(ArgAction::SetTrue)
+                .help("Look up the locations of all buckets concurrently in S3 mode")
+                .long("prewarm")
+        );
+
+    let app = app
+        .arg(
+            Arg::new("PROFILE")
+                .action(ArgAction::Set)
+                .env("S3DU_PROFILE")
+                .help("Use the given profile from the AWS config files")
+                .hide_env_values(true)
+                .long("profile")
+                .value_name("PROFILE")
+        )
+        .arg(
+            Arg::new("PROXY")
+                .action(ArgAction::Set)
+                .env("S3DU_PROXY")
+                .help("Make all requests via the given HTTP proxy")
+                .hide_env_values(true)
+                .long("proxy")
+                .value_name("URL")
+                .value_parser(proxy::is_valid_proxy)
+        )
+        .arg(
+            Arg::new("REGION")
+                .action(ArgAction::Set)
+                .default_value(&**DEFAULT_REGION)
+                .env("AWS_REGION")
+                .help("Set the AWS region to create the client in.")
+                .hide_env_values(true)
+                .long("region")
+                .short('r')
+                .value_name("REGION")
+        );
+
+    #[cfg(feature = "s3")]
+    let app = app
+        .arg(
+            Arg::new("REQUEST_PAYER")
+                .action(ArgAction::Set)
+                .env("S3DU_REQUEST_PAYER")
+                .help("Agree to pay for listing objects in requester pays buckets in S3 mode")
+                .hide_env_values(true)
+                .long("request-payer")
+                .value_name("PAYER")
+                .value_parser(PossibleValuesParser::new(VALID_REQUEST_PAYERS))
+        )
+        .arg(
+            Arg::new("REQUIRE_HTTPS")
+                .action(ArgAction::SetTrue)
+                .help("Reject custom endpoints that don't use HTTPS")
+                .long("require-https")
+        )
+        .arg(
+            Arg::new("RETRY_BASE_DELAY_MS")
+                .action(ArgAction::Set)
+                .default_value(DEFAULT_RETRY_BASE_DELAY_MS)
+                .env("S3DU_RETRY_BASE_DELAY_MS")
+                .help("Wait up to MS milliseconds before the first retry of a throttled request in S3 mode")
+                .hide_env_values(true)
+                .long("retry-base-delay-ms")
+                .value_name("MS")
+                .value_parser(clap::value_parser!(u64))
+        );
+
+    let app = app
+        .arg(
+            Arg::new("ROLE_ARN")
+                .action(ArgAction::Set)
+                .env("S3DU_ROLE_ARN")
+                .help("Assume the given IAM role for all requests")
+                .hide_env_values(true)
+                .long("role-arn")
+                .value_name("ARN")
+                .value_parser(is_valid_role_arn)
+        );
+
+    #[cfg(feature = "s3")]
+    let app = app
+        .arg(
+            Arg::new("SHOW_MULTIPART_AGE")
+                .action(ArgAction::SetTrue)
+                .help("Show the age in days of the oldest in-progress multipart upload in S3 mode")
+                .long("show-multipart-age")
+        );
+
+    #[cfg(feature = "cloudwatch")]
+    let app = app
+        .arg(
+            Arg::new("SHOW_TIMESTAMP")
+                .action(ArgAction::SetTrue)
+                .help("Show the time of the datapoints that each size was taken from in CloudWatch mode")
+                .long("show-timestamp")
+        );
+
+    let app = app
+        .arg(
+            Arg::new("SORT")
+                .action(ArgAction::Set)
+                .env("S3DU_SORT")
+                .help("Sort buckets by KEY before output")
+                .hide_env_values(true)
+                .long("sort")
+                .value_name("KEY")
+                .value_parser(PossibleValuesParser::new(VALID_SORT_KEYS))
+        );
+
+    #[cfg(feature = "cloudwatch")]
+    let app = app
+        .arg(
+            Arg::new("STATISTIC")
+                .action(ArgAction::Set)
+                .default_value(DEFAULT_STATISTIC)
+                .env("S3DU_STATISTIC")
+                .help("Sets the statistic of each day's metrics to use in CloudWatch mode")
+                .hide_env_values(true)
+                .long("statistic")
+                .value_name("STATISTIC")
+                .value_parser(PossibleValuesParser::new(VALID_STATISTICS))
+        );
+
+    let app = app
+        .arg(
+            Arg::new("STATS")
+                .action(ArgAction::SetTrue)
+                .help("Show extra diagnostics, such as S3 multipart uploads with no sized parts")
+                .long("stats")
+        )
+        .arg(
+            Arg::new("STORAGE_CLASS_BREAKDOWN")
+                .action(ArgAction::SetTrue)
+                .help("Include the size of each storage class in each bucket in JSON output")
+                .long("storage-class-breakdown")
+        );
+
+    #[cfg(feature = "s3")]
+    let app = app
+        .arg(
+            Arg::new("STORAGE_CLASS")
+                .action(ArgAction::Append)
+                .env("S3DU_STORAGE_CLASS")
+                .help("Only size objects in the given storage classes in S3 mode, may be given multiple times")
+                .hide_env_values(true)
+                .long("storage-class")
+                .value_delimiter(',')
+                .value_name("CLASS")
+                .value_parser(PossibleValuesParser::new(VALID_STORAGE_CLASSES))
+        );
+
+    #[cfg(feature = "cloudwatch")]
+    let app = app
+        .arg(
+            Arg::new("STORAGE_TYPE")
+                .action(ArgAction::Append)
+                .env("S3DU_STORAGE_TYPE")
+                .help("Only sum the given storage types in CloudWatch mode, may be given multiple times")
+                .hide_env_values(true)
+                .long("storage-type")
+                .value_delimiter(',')
+                .value_name("TYPE")
+                .value_parser(PossibleValuesParser::new(VALID_STORAGE_TYPES))
+        );
+
+    #[cfg(feature = "s3")]
+    let app = app
+        .arg(
+            Arg::new("STORAGE_LENS_EXPORT")
+                .action(ArgAction::Set)
+                .env("S3DU_STORAGE_LENS_EXPORT")
+                .help("Read bucket sizes from the S3 Storage Lens export at this S3 URI in storage-lens mode")
+                .hide_env_values(true)
+                .long("storage-lens-export")
+                .required_if_eq("MODE", "storage-lens")
+                .value_name("URI")
+                .value_parser(is_valid_storage_lens_export)
+        );
+
+    let app = app
+        .arg(
+            Arg::new("SUMMARIZE")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("NO_TOTAL")
+                .help("Only show the total size of all buckets")
+                .long("summarize")
+                .short('s')
+        )
+        .arg(
+            Arg::new("SUMMARY_BY_REGION")
+                .action(ArgAction::SetTrue)
+                .help("Include the total size of the buckets in each region in JSON output")
+                .long("summary-by-region")
+        )
+        .arg(
+            Arg::new("SUM_BY_STORAGE_CLASS")
+                .action(ArgAction::SetTrue)
+                .help("Include the total size of each storage class in JSON output")
+                .long("sum-by-storage-class")
+        )
+        .arg(
+            Arg::new("TEMPLATE")
+                .action(ArgAction::Set)
+                .env("S3DU_TEMPLATE")
+                .help("Sets the template used for each bucket with the template format")
+                .hide_env_values(true)
+                .long("template")
+                .required_if_eq("FORMAT", "template")
+                .value_name("TEMPLATE")
+                .value_parser(parse_template)
+        )
+        .arg(
+            Arg::new("TEMPLATE_TOTAL")
+                .action(ArgAction::Set)
+                .env("S3DU_TEMPLATE_TOTAL")
+                .help("Sets the template used for the total with the template format")
+                .hide_env_values(true)
+                .long("template-total")
+                .requires("TEMPLATE")
+                .value_name("TEMPLATE")
+                .value_parser(parse_total_template)
+        )
+        .arg(
+            Arg::new("THRESHOLD")
+                .action(ArgAction::Set)
+                .conflicts_with("SUMMARIZE")
+                .env("S3DU_THRESHOLD")
+                .help("Hide buckets smaller than SIZE, such as 1GiB, the total still includes every bucket")
+                .hide_env_values(true)
+                .long("threshold")
+                .value_name("SIZE")
+                .value_parser(parse_human_size)
+        )
+        .arg(
+            Arg::new("TOP")
+                .action(ArgAction::Set)
+                .conflicts_with("SUMMARIZE")
+                .env("S3DU_TOP")
+                .help("Only show the N largest buckets, the total still includes every bucket")
+                .hide_env_values(true)
+                .long("top")
+                .value_name("N")
+                .value_parser(clap::value_parser!(NonZeroUsize))
+        )
+        .arg(
+            Arg::new("TOTAL")
+                .action(ArgAction::SetTrue)
+                .help("Show the total size of all buckets, this is the default")
+                .long("total")
+                .overrides_with("NO_TOTAL")
+                .short('c')
+        )
+        .arg(
+            Arg::new("UNIT")
+                .action(ArgAction::Set)
+                .default_value(DEFAULT_UNIT)
+                .env("S3DU_UNIT")
+                .help("Sets the unit to use for size display")
+                .hide_env_values(true)
+                .long("unit")
+                .overrides_with_all(["BLOCK_SIZE", "HUMAN_READABLE"])
+                .short('u')
+                .value_name("UNIT")
+                .value_parser(PossibleValuesParser::new(VALID_SIZE_UNITS))
+        );
+
+    #[cfg(all(feature = "cloudwatch", feature = "s3"))]
+    let app = app
+        .arg(
+            Arg::new("VERIFY_EXISTS")
+                .action(ArgAction::SetTrue)
+                .help("Skip buckets that no longer exist in S3 in CloudWatch mode")
+                .long("verify-exists")
+        );
+
+    #[cfg(feature = "s3")]
+    let app = app
+        .arg(
+            Arg::new("WARN_VERSIONED")
+                .action(ArgAction::SetTrue)
+                .help("Warn about versioned buckets when sizing current objects in S3 mode")
+                .long("warn-versioned")
+        )
+        .arg(
+            Arg::new("YES")
+                .action(ArgAction::SetTrue)
+                .help("Size all buckets in S3 mode without asking for confirmation")
+                .long("yes")
+                .short('y')
+        );
+
+    app
+}
+
+/// Returns the unit that sizes should be displayed in.
+///
+/// `--human-readable` and `--unit` override each other, with the last one
+/// given on the command line taking precedence.
+pub fn size_unit(matches: &ArgMatches) -> &str {
+    if matches.get_flag("HUMAN_READABLE") {
+        return "binary";
+    }
+
+    // This should be safe, the unit has a default value.
+    matches.get_one::<String>("UNIT")
+        .expect("size unit")
+}
+
+/// Returns the mode that `s3du` should run in.
+///
+/// With `--mode auto`, `cloudwatch` is preferred as it costs nothing per
+/// object. `storage-lens` is used instead if a `--storage-lens-export` was
+/// given, and `s3` if a custom `--endpoint`, a `--prefix`, a
+/// `--request-payer`, a `--storage-class`, non-current `--object-versions` or
+/// any other option that only has an effect in S3 mode was given, including
+/// `--max-retries`, `--retry-base-delay-ms` and `--confirm-threshold` if they
+/// were given on the command line or in the environment.
+pub fn client_mode(matches: &ArgMatches) -> &str {
+    // This should be safe, the mode has a default value.
+    let mode = matches.get_one::<String>("MODE")
+        .expect("client mode");
+
+    #[cfg(all(feature = "cloudwatch", feature = "s3"))]
+    if mode == "auto" {
+        let object_versions = matches.get_one::<String>("OBJECT_VERSIONS")
+            .expect("object versions");
+
+        let needs_s3 = matches.contains_id("ENDPOINT")
+            || matches.contains_id("PREFIX")
+            || matches.contains_id("REQUEST_PAYER")
+            || matches.contains_id("STORAGE_CLASS")
+            || object_versions != DEFAULT_OBJECT_VERSIONS
+            || S3_ONLY_FLAGS.iter().any(|flag| matches.get_flag(flag))
+            || S3_ONLY_DEFAULTED_ARGS.iter().any(|arg| {
+                matches!(
+                    matches.value_source(arg),
+                    Some(ValueSource::CommandLine | ValueSource::EnvVariable),
+                )
+            });
+
+        let mode = if matches.contains_id("STORAGE_LENS_EXPORT") {
+            "storage-lens"
+        }
+        else if needs_s3 {
+            "s3"
+        }
+        else {
+            "cloudwatch"
+        };
+
+        debug!("client_mode: auto resolved to '{}'", mode);
+
+        return mode;
+    }
+
+    mode
+}
+
+/// Returns a `bool` indicating if the total line should be shown.
+///
+/// `--total` and `--no-total` override each other, with the last one given on
+/// the command line taking precedence.
+pub fn show_total(matches: &ArgMatches) -> bool {
+    !matches.get_flag("NO_TOTAL")
+}
+
+/// Returns the region to use with a custom endpoint.
+///
+/// If no region was given on the command line or found in the environment,
+/// the fallback AWS region would be meaningless, so `custom` is used instead.
+#[cfg(feature = "s3")]
+pub fn endpoint_region(matches: &ArgMatches) -> &str {
+    let defaulted = matches.value_source("REGION")
+        == Some(ValueSource::DefaultValue);
+
+    if defaulted && *DEFAULT_REGION == FALLBACK_REGION {
+        return CUSTOM_ENDPOINT_REGION;
+    }
+
+    // This should be safe, the region has a default value.
+    matches.get_one::<String>("REGION")
+        .expect("region")
+}
+
+/// Ensures that any custom endpoint uses HTTPS if `--require-https` was
+/// given, so that credentials are never sent in plaintext by mistake.
+///
+/// Plain HTTP endpoints are otherwise allowed, as they're common for local
+/// S3 compatible storage.
+#[cfg(feature = "s3")]
+fn require_https(matches: &ArgMatches) -> Result<(), String> {
+    if !matches.get_flag("REQUIRE_HTTPS") {
+        return Ok(());
+    }
+
+    let Some(endpoint) = matches.get_one::<String>("ENDPOINT") else {
+        return Ok(());
+    };
+
+    // This should be safe, the endpoint was validated when it was parsed.
+    let uri = Uri::try_from(endpoint.as_str())
+        .expect("endpoint");
+
+    if uri.scheme_str() != Some("https") {
+        return Err(format!("Endpoint must use HTTPS with --require-https, found {endpoint}"));
+    }
+
+    Ok(())
+}
+
+/// Returns a `bool` indicating if what's sized was narrowed down by a
+/// `--prefix`, an `--exclude` or a `--region` given on the command line, in
+/// which case sizing every bucket doesn't need to be confirmed.
+///
+/// The region always has a value, so it only counts if it was given
+/// explicitly, and not with `--all-regions`.
+#[cfg(feature = "s3")]
+pub fn is_narrowed(matches: &ArgMatches) -> bool {
+    let region_given = matches.value_source("REGION")
+        == Some(ValueSource::CommandLine);
+
+    matches.contains_id("PREFIX")
+        || matches.contains_id("EXCLUDE")
+        || (region_given && !matches.get_flag("ALL_REGIONS"))
+}
+
+/// Ensures that options selecting what's sized within buckets, or how it's
+/// paid for, weren't given outside of S3 mode, as they'd be silently ignored
+/// there.
+#[cfg(feature = "s3")]
+pub fn s3_only_options(matches: &ArgMatches) -> Result<(), String> {
+    // Other modes only know the size of whole buckets.
+    if matches.contains_id("PREFIX") {
+        return Err("Prefixes are only supported in S3 mode".into());
+    }
+
+    // Other modes can't tell storage classes apart.
+    if matches.contains_id("STORAGE_CLASS") {
+        return Err("Storage classes are only supported in S3 mode".into());
+    }
+
+    // Other modes never list objects, so never need to pay for it.
+    if matches.contains_id("REQUEST_PAYER") {
+        return Err("Requester pays is only supported in S3 mode".into());
+    }
+
+    Ok(())
+}
+
+/// Parse the command line arguments
+pub fn parse_args() -> ArgMatches {
+    debug!("Parsing command line arguments");
+
+    let mut app = create_app();
+    let matches = app.get_matches_mut();
+
+    // Some arguments can only be validated against others once everything
+    // has been parsed.
+    #[cfg(feature = "s3")]
+    if let Err(e) = require_https(&matches) {
+        app.error(ErrorKind::ValueValidation, e).exit();
+    }
+
+    matches
+}
+
+#[cfg(test)]
 mod tests {
     use super::*;
 
@@ -273,18 +1373,551 @@ mod tests {
             ("-invalid",     true),
             (&long_invalid,  false),
             (&long_valid,    true),
+            ("*",            true),
+            ("prod-*",       true),
+            ("prod-??",      true),
+            ("prod-**",      false),
         ];
 
         for test in tests {
             let name  = test.0;
             let valid = test.1;
 
-            let ret = is_valid_aws_s3_bucket_name(name.into());
+            let ret = is_valid_aws_s3_bucket_name(name);
 
             assert_eq!(ret.is_ok(), valid);
         }
     }
 
+    #[test]
+    fn test_buckets() {
+        let tests = vec![
+            (vec![],                       Some(vec![])),
+            (vec!["a-bucket"],             Some(vec!["a-bucket"])),
+            (vec!["a-bucket", "b-bucket"], Some(vec!["a-bucket", "b-bucket"])),
+            (vec!["a-bucket,b-bucket"],    Some(vec!["a-bucket", "b-bucket"])),
+            (vec!["a-bucket", "B"],        None),
+        ];
+
+        for test in tests {
+            let args     = test.0;
+            let expected = test.1;
+
+            let ret = create_app()
+                .try_get_matches_from([&["s3du"], &args[..]].concat())
+                .ok()
+                .map(|matches| {
+                    matches.get_many::<String>("BUCKET")
+                        .into_iter()
+                        .flatten()
+                        .cloned()
+                        .collect::<Vec<_>>()
+                });
+
+            let expected = expected.map(|names| {
+                names.into_iter().map(String::from).collect::<Vec<_>>()
+            });
+
+            assert_eq!(ret, expected, "{:?}", args);
+        }
+    }
+
+    #[test]
+    fn test_du_compatible_flags() {
+        let tests = vec![
+            (vec![],                              "binary", true,  false),
+            (vec!["-h"],                          "binary", true,  false),
+            (vec!["--unit", "bytes"],             "bytes",  true,  false),
+            (vec!["--unit", "bytes", "-h"],       "binary", true,  false),
+            (vec!["-h", "--unit", "bytes"],       "bytes",  true,  false),
+            (vec!["-c"],                          "binary", true,  false),
+            (vec!["--no-total"],                  "binary", false, false),
+            (vec!["--no-total", "-c"],            "binary", true,  false),
+            (vec!["-c", "--no-total"],            "binary", false, false),
+            (vec!["-s"],                          "binary", true,  true),
+            (vec!["-shc"],                        "binary", true,  true),
+            (vec!["-s", "--unit", "decimal"],     "decimal", true, true),
+        ];
+
+        for test in tests {
+            let args      = test.0;
+            let unit      = test.1;
+            let total     = test.2;
+            let summarize = test.3;
+
+            let matches = create_app()
+                .try_get_matches_from([&["s3du"], &args[..]].concat())
+                .unwrap();
+
+            assert_eq!(size_unit(&matches), unit);
+            assert_eq!(show_total(&matches), total);
+            assert_eq!(matches.get_flag("SUMMARIZE"), summarize);
+        }
+    }
+
+    #[test]
+    fn test_block_size() {
+        let tests = vec![
+            (vec!["-B1"],                    Some((1, None))),
+            (vec!["-B", "1K"],               Some((1_024, None))),
+            (vec!["--block-size", "K"],      Some((1_024, Some("K")))),
+            (vec!["--block-size=MB"],        Some((1_000_000, Some("MB")))),
+            (vec!["-B1", "-h"],              None),
+            (vec!["-B1", "--unit", "bytes"], None),
+            (vec!["-h", "-B1"],              Some((1, None))),
+        ];
+
+        for test in tests {
+            let args     = test.0;
+            let expected = test.1;
+
+            let matches = create_app()
+                .try_get_matches_from([&["s3du"], &args[..]].concat())
+                .unwrap();
+
+            let ret = matches.get_one::<SizeUnit>("BLOCK_SIZE")
+                .map(|unit| {
+                    let SizeUnit::Blocks { size, suffix } = unit else {
+                        panic!("not a block size: {unit:?}");
+                    };
+
+                    (*size, suffix.as_deref())
+                });
+
+            assert_eq!(ret, expected, "{args:?}");
+        }
+    }
+
+    #[test]
+    fn test_parse_block_size_zero() {
+        let ret = parse_block_size("0");
+
+        assert_eq!(ret.unwrap_err(), "Block size must be greater than zero");
+    }
+
+    #[cfg(all(feature = "cloudwatch", feature = "s3"))]
+    #[test]
+    fn test_client_mode_auto() {
+        let tests = vec![
+            (vec!["--mode", "s3"],                               "s3"),
+            (vec!["--mode", "auto"],                             "cloudwatch"),
+            (vec!["--mode", "auto", "--count"],                  "cloudwatch"),
+            (vec!["--mode", "auto", "--object-versions", "all"], "s3"),
+            (vec!["--mode", "auto", "--prewarm"],                "s3"),
+            (vec!["--mode", "auto", "--all-regions"],            "s3"),
+            (vec!["--mode", "auto", "--endpoint", "http://x"],   "s3"),
+            (vec!["--mode", "auto", "--prefix", "team-a/"],      "s3"),
+            (vec!["--mode", "auto", "--storage-class", "GLACIER"], "s3"),
+            (vec!["--mode", "auto", "--request-payer", "requester"], "s3"),
+            (vec!["--mode", "auto", "--max-retries", "10"],      "s3"),
+            (vec!["--mode", "auto", "--retry-base-delay-ms", "50"], "s3"),
+            (vec!["--mode", "auto", "--confirm-threshold", "5"], "s3"),
+            (
+                vec!["--mode", "auto", "--storage-lens-export", "s3://lens/"],
+                "storage-lens",
+            ),
+        ];
+
+        for test in tests {
+            let args     = test.0;
+            let expected = test.1;
+
+            let matches = create_app()
+                .try_get_matches_from([&["s3du"], &args[..]].concat())
+                .unwrap();
+
+            assert_eq!(client_mode(&matches), expected, "args {args:?}");
+        }
+    }
+
+    #[cfg(feature = "s3")]
+    #[test]
+    fn test_all_regions_conflicts_with_assume_region() {
+        let ret = create_app()
+            .try_get_matches_from(["s3du", "--all-regions", "--assume-region"]);
+
+        assert!(ret.is_err());
+    }
+
+    #[cfg(feature = "s3")]
+    #[test]
+    fn test_path_style_requires_endpoint() {
+        let ret = create_app()
+            .try_get_matches_from(["s3du", "--path-style"]);
+
+        assert!(ret.is_err());
+
+        let ret = create_app()
+            .try_get_matches_from([
+                "s3du",
+                "--endpoint", "http://127.0.0.1:9000",
+                "--path-style",
+            ]);
+
+        assert!(ret.is_ok());
+    }
+
+    #[cfg(feature = "s3")]
+    #[test]
+    fn test_storage_class() {
+        let matches = create_app()
+            .try_get_matches_from([
+                "s3du",
+                "--storage-class", "GLACIER,DEEP_ARCHIVE",
+                "--storage-class", "STANDARD",
+            ])
+            .unwrap();
+
+        let classes: Vec<&String> = matches
+            .get_many::<String>("STORAGE_CLASS")
+            .unwrap()
+            .collect();
+
+        assert_eq!(classes, ["GLACIER", "DEEP_ARCHIVE", "STANDARD"]);
+
+        let ret = create_app()
+            .try_get_matches_from(["s3du", "--storage-class", "COLD"]);
+
+        assert!(ret.is_err());
+    }
+
+    #[test]
+    fn test_list_only() {
+        let tests = vec![
+            (vec!["s3du"],                false),
+            (vec!["s3du", "--list-only"], true),
+            (vec!["s3du", "--dry-run"],   true),
+        ];
+
+        for test in tests {
+            let args     = test.0;
+            let expected = test.1;
+
+            let matches = create_app()
+                .try_get_matches_from(&args)
+                .unwrap();
+
+            assert_eq!(matches.get_flag("LIST_ONLY"), expected, "{args:?}");
+        }
+    }
+
+    #[test]
+    fn test_max_age_requires_cache() {
+        let ret = create_app()
+            .try_get_matches_from(["s3du", "--max-age", "1h"]);
+
+        assert!(ret.is_err());
+
+        let ret = create_app()
+            .try_get_matches_from(["s3du", "--cache", "sizes.json", "--max-age", "1h"]);
+
+        assert!(ret.is_ok());
+    }
+
+    #[test]
+    fn test_summarize_conflicts_with_no_total() {
+        let ret = create_app()
+            .try_get_matches_from(["s3du", "-s", "--no-total"]);
+
+        assert!(ret.is_err());
+    }
+
+    #[test]
+    fn test_order_requires_sort() {
+        let ret = create_app()
+            .try_get_matches_from(["s3du", "--order", "asc"]);
+
+        assert!(ret.is_err());
+
+        let ret = create_app()
+            .try_get_matches_from(["s3du", "--sort", "size", "--order", "asc"]);
+
+        assert!(ret.is_ok());
+    }
+
+    #[cfg(feature = "s3")]
+    #[test]
+    fn test_storage_lens_export() {
+        let tests = vec![
+            (vec!["--mode", "storage-lens"],                                      false),
+            (vec!["--mode", "storage-lens", "--storage-lens-export", "s3://lens/"], true),
+            (vec!["--mode", "storage-lens", "--storage-lens-export", "s3://"],      false),
+            (vec!["--mode", "storage-lens", "--storage-lens-export", "lens/"],      false),
+            (vec!["--mode", "s3"],                                                true),
+        ];
+
+        for test in tests {
+            let args  = test.0;
+            let valid = test.1;
+
+            let ret = create_app()
+                .try_get_matches_from([&["s3du"], &args[..]].concat());
+
+            assert_eq!(ret.is_ok(), valid, "{args:?}");
+        }
+    }
+
+    #[cfg(feature = "s3")]
+    #[test]
+    fn test_confirm_threshold() {
+        let tests = vec![
+            (vec![],                              100, false),
+            (vec!["--confirm-threshold", "5"],    5,   false),
+            (vec!["--yes"],                       100, true),
+            (vec!["-y", "--confirm-threshold=0"], 0,   true),
+        ];
+
+        for test in tests {
+            let args      = test.0;
+            let threshold = test.1;
+            let yes       = test.2;
+
+            let matches = create_app()
+                .try_get_matches_from([&["s3du"], &args[..]].concat())
+                .unwrap();
+
+            let ret = matches.get_one::<usize>("CONFIRM_THRESHOLD").copied();
+
+            assert_eq!(ret, Some(threshold));
+            assert_eq!(matches.get_flag("YES"), yes);
+        }
+    }
+
+    #[cfg(feature = "cloudwatch")]
+    #[test]
+    fn test_cloudwatch_lookback_days() {
+        let tests = vec![
+            (vec![],                                    Some(2)),
+            (vec!["--cloudwatch-lookback-days", "14"],  Some(14)),
+            (vec!["--cloudwatch-lookback-days=0"],      None),
+        ];
+
+        for test in tests {
+            let args     = test.0;
+            let expected = test.1;
+
+            let ret = create_app()
+                .try_get_matches_from([&["s3du"], &args[..]].concat())
+                .ok()
+                .map(|matches| {
+                    matches.get_one::<NonZeroU32>("CLOUDWATCH_LOOKBACK_DAYS")
+                        .unwrap()
+                        .get()
+                });
+
+            assert_eq!(ret, expected, "{:?}", args);
+        }
+    }
+
+    #[cfg(feature = "cloudwatch")]
+    #[test]
+    fn test_storage_type() {
+        let tests = vec![
+            (
+                vec![],
+                Some(vec![]),
+            ),
+            (
+                vec!["--storage-type", "StandardStorage"],
+                Some(vec!["StandardStorage"]),
+            ),
+            (
+                vec![
+                    "--storage-type", "StandardStorage",
+                    "--storage-type", "GlacierStorage",
+                ],
+                Some(vec!["StandardStorage", "GlacierStorage"]),
+            ),
+            (
+                vec!["--storage-type=StandardStorage,StandardIAStorage"],
+                Some(vec!["StandardStorage", "StandardIAStorage"]),
+            ),
+            (
+                vec!["--storage-type", "StandardStorag"],
+                None,
+            ),
+        ];
+
+        for test in tests {
+            let args     = test.0;
+            let expected = test.1;
+
+            let ret = create_app()
+                .try_get_matches_from([&["s3du"], &args[..]].concat())
+                .ok()
+                .map(|matches| {
+                    matches.get_many::<String>("STORAGE_TYPE")
+                        .into_iter()
+                        .flatten()
+                        .map(String::as_str)
+                        .collect::<Vec<_>>()
+                        .join(",")
+                });
+
+            let expected = expected.map(|types| types.join(","));
+
+            assert_eq!(ret, expected, "{:?}", args);
+        }
+    }
+
+    #[test]
+    fn test_concurrency() {
+        let tests = vec![
+            (vec![],                      Some(8)),
+            (vec!["--concurrency", "1"],  Some(1)),
+            (vec!["--concurrency=32"],    Some(32)),
+            (vec!["--concurrency=0"],     None),
+        ];
+
+        for test in tests {
+            let args     = test.0;
+            let expected = test.1;
+
+            let ret = create_app()
+                .try_get_matches_from([&["s3du"], &args[..]].concat())
+                .ok()
+                .map(|matches| {
+                    matches.get_one::<NonZeroUsize>("CONCURRENCY")
+                        .unwrap()
+                        .get()
+                });
+
+            assert_eq!(ret, expected, "{:?}", args);
+        }
+    }
+
+    #[cfg(feature = "s3")]
+    #[test]
+    fn test_endpoint_region() {
+        // Without a region on the command line, the environment may still
+        // provide one.
+        let default_region = if *DEFAULT_REGION == FALLBACK_REGION {
+            CUSTOM_ENDPOINT_REGION
+        }
+        else {
+            &DEFAULT_REGION
+        };
+
+        let tests = vec![
+            (vec![],                            default_region),
+            (vec!["--region", "eu-west-1"],     "eu-west-1"),
+            (vec!["--region", FALLBACK_REGION], FALLBACK_REGION),
+        ];
+
+        for test in tests {
+            let args     = test.0;
+            let expected = test.1;
+
+            let args = [
+                &["s3du", "--endpoint", "https://minio.example.com"],
+                &args[..],
+            ].concat();
+
+            let matches = create_app()
+                .try_get_matches_from(args)
+                .unwrap();
+
+            let ret = endpoint_region(&matches);
+
+            assert_eq!(ret, expected);
+        }
+    }
+
+    #[test]
+    fn test_parse_glob() {
+        let tests = vec![
+            ("*-logs",     "access-logs",  true),
+            ("*-logs",     "logs-archive", false),
+            ("logs-[0-9]", "logs-1",       true),
+            ("logs-[0-9]", "logs-a",       false),
+            ("Logs",       "Logs",         true),
+            ("Logs",       "logs",         false),
+        ];
+
+        for test in tests {
+            let input    = test.0;
+            let name     = test.1;
+            let expected = test.2;
+
+            let ret = parse_glob(input).unwrap().matches(name);
+
+            assert_eq!(ret, expected, "{input} {name}");
+        }
+
+        assert!(parse_glob("logs-[").is_err());
+    }
+
+    #[test]
+    fn test_is_valid_role_arn() {
+        let tests = vec![
+            ("arn:aws:iam::123456789012:role/s3du",            true),
+            ("arn:aws:iam::123456789012:role/path/to/s3du",    true),
+            ("arn:aws-cn:iam::123456789012:role/s3du",         true),
+            ("arn:aws:iam::123456789012:role/",                false),
+            ("arn:aws:iam::123456789012:user/s3du",            false),
+            ("arn:aws:sts::123456789012:role/s3du",            false),
+            ("arn:aws:iam:eu-west-1:123456789012:role/s3du",   false),
+            ("arn:aws:iam:::role/s3du",                        false),
+            ("s3du",                                           false),
+            ("",                                               false),
+        ];
+
+        for test in tests {
+            let arn   = test.0;
+            let valid = test.1;
+
+            let ret = is_valid_role_arn(arn);
+
+            assert_eq!(ret.is_ok(), valid, "{arn}");
+        }
+    }
+
+    #[test]
+    fn test_external_id_requires_role_arn() {
+        let ret = create_app()
+            .try_get_matches_from(["s3du", "--external-id", "an-id"]);
+
+        assert!(ret.is_err());
+
+        let ret = create_app()
+            .try_get_matches_from([
+                "s3du",
+                "--role-arn",
+                "arn:aws:iam::123456789012:role/s3du",
+                "--external-id",
+                "an-id",
+            ]);
+
+        assert!(ret.is_ok());
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        let tests = vec![
+            ("90",                Some(Duration::from_secs(90))),
+            ("90s",               Some(Duration::from_secs(90))),
+            ("5m",                Some(Duration::from_secs(300))),
+            ("1h",                Some(Duration::from_secs(3_600))),
+            ("0",                 None),
+            ("0s",                None),
+            ("",                  None),
+            ("s",                 None),
+            ("-1s",               None),
+            ("1.5m",              None),
+            ("1d",                None),
+            ("9999999999999999h", None),
+        ];
+
+        for test in tests {
+            let input    = test.0;
+            let expected = test.1;
+
+            let ret = parse_duration(input).ok();
+
+            assert_eq!(ret, expected);
+        }
+    }
+
     #[cfg(feature = "s3")]
     #[test]
     fn test_is_valid_endpoint() {
@@ -293,6 +1926,16 @@ mod tests {
             ("https://minio.example.org/endpoint", true),
             ("http://minio.example.org/endpoint",  true),
             ("http://127.0.0.1:9000",              true),
+            ("http://127.0.0.1:65535",             true),
+            ("http://127.0.0.1:65536",             false),
+            ("http://127.0.0.1:0",                 false),
+            ("http://[::1]:9000",                  true),
+            ("http://[::1]",                       true),
+            ("https://[2001:db8::1]:9443/minio",   true),
+            ("http://[::1:9000",                   false),
+            ("http://::1:9000",                    false),
+            ("http://:9000",                       false),
+            ("http://user:secret@[::1]:9000",      true),
             ("../ohno",                            false),
             ("minio.example.org",                  false),
             ("",                                   false),
@@ -306,9 +1949,83 @@ mod tests {
             let url   = test.0;
             let valid = test.1;
 
-            let ret = is_valid_endpoint(url.into());
+            let ret = is_valid_endpoint(url);
 
-            assert_eq!(ret.is_ok(), valid);
+            assert_eq!(ret.is_ok(), valid, "{url}");
+        }
+    }
+
+    #[cfg(feature = "s3")]
+    #[test]
+    fn test_require_https() {
+        let tests = vec![
+            (vec![],                                                     true),
+            (vec!["--require-https"],                                    true),
+            (vec!["-e", "http://127.0.0.1:9000"],                        true),
+            (vec!["-e", "https://minio.example.org"],                    true),
+            (vec!["--require-https", "-e", "http://127.0.0.1:9000"],     false),
+            (vec!["--require-https", "-e", "https://minio.example.org"], true),
+        ];
+
+        for test in tests {
+            let args  = test.0;
+            let valid = test.1;
+
+            let matches = create_app()
+                .try_get_matches_from([&["s3du"], &args[..]].concat())
+                .unwrap();
+
+            let ret = require_https(&matches);
+
+            assert_eq!(ret.is_ok(), valid, "{args:?}");
+        }
+    }
+
+    #[cfg(feature = "s3")]
+    #[test]
+    fn test_is_narrowed() {
+        let tests = vec![
+            (vec![],                                     false),
+            (vec!["--prefix", "team-a/"],                true),
+            (vec!["--exclude", "logs-*"],                true),
+            (vec!["--region", "eu-west-1"],              true),
+            (vec!["--region", "eu-west-1", "--all-regions"], false),
+            (vec!["--storage-class", "GLACIER"],         false),
+        ];
+
+        for test in tests {
+            let args     = test.0;
+            let expected = test.1;
+
+            let matches = create_app()
+                .try_get_matches_from([&["s3du"], &args[..]].concat())
+                .unwrap();
+
+            assert_eq!(is_narrowed(&matches), expected, "{args:?}");
+        }
+    }
+
+    #[cfg(feature = "s3")]
+    #[test]
+    fn test_s3_only_options() {
+        let tests = vec![
+            (vec![],                                        None),
+            (vec!["--prefix", "team-a/"],                   Some("Prefixes are only supported in S3 mode")),
+            (vec!["--storage-class", "GLACIER"],            Some("Storage classes are only supported in S3 mode")),
+            (vec!["--request-payer", "requester"],          Some("Requester pays is only supported in S3 mode")),
+        ];
+
+        for test in tests {
+            let args     = test.0;
+            let expected = test.1.map(ToString::to_string);
+
+            let matches = create_app()
+                .try_get_matches_from([&["s3du"], &args[..]].concat())
+                .unwrap();
+
+            let ret = s3_only_options(&matches).err();
+
+            assert_eq!(ret, expected, "{args:?}");
         }
     }
 }
@@ -8,7 +8,10 @@ use crate::common::{
 use aws_sdk_cloudwatch::types::Metric;
 use std::collections::HashMap;
 use std::string::ToString;
-use tracing::debug;
+use tracing::{
+    debug,
+    warn,
+};
 
 // This Hash is keyed by bucket name and contains a list of storage types that
 // are used within the bucket.
@@ -40,7 +43,59 @@ impl BucketMetrics {
     }
 }
 
+/// Returns the bucket name and storage type from the dimensions of the given
+/// `metric`.
+///
+/// Metrics that are missing either dimension, or have either dimension more
+/// than once, can't be reliably associated with a bucket and storage type, so
+/// a warning is logged and `None` is returned for them.
+fn bucket_storage_type(metric: &Metric) -> Option<(String, String)> {
+    let dimensions = metric.dimensions();
+
+    // Storage for what we'll pull out of the dimensions
+    let mut name         = None;
+    let mut storage_type = None;
+
+    // Process the dimensions, taking the bucket name and storage type
+    for dimension in dimensions {
+        let (dimension_name, slot) = match dimension.name() {
+            Some(n @ "BucketName")  => (n, &mut name),
+            Some(n @ "StorageType") => (n, &mut storage_type),
+            _                       => continue,
+        };
+
+        let value = dimension.value().unwrap_or_default();
+
+        if slot.replace(value.to_owned()).is_some() {
+            warn!(
+                "Skipping metric with duplicate '{}' dimensions: {:?}",
+                dimension_name,
+                dimensions,
+            );
+
+            return None;
+        }
+    }
+
+    match (name, storage_type) {
+        (Some(name), Some(storage_type)) => Some((name, storage_type)),
+        _ => {
+            warn!(
+                "Skipping metric without BucketName and StorageType dimensions: {:?}",
+                dimensions,
+            );
+
+            None
+        },
+    }
+}
+
 /// Conversion from a `Vec<Metric>` as returned by AWS to our `BucketMetrics`.
+///
+/// Metrics with unexpected dimensions are skipped, see
+/// `bucket_storage_type`. Each storage type is only recorded once per bucket,
+/// even if `ListMetrics` returns it more than once, so that it's neither
+/// queried nor counted twice.
 impl From<Vec<Metric>> for BucketMetrics {
     fn from(metrics: Vec<Metric>) -> Self {
         debug!("From: Vec<Metric> for BucketMetrics");
@@ -48,37 +103,13 @@ impl From<Vec<Metric>> for BucketMetrics {
         let mut bucket_metrics = HashMap::new();
 
         for metric in metrics {
-            let dimensions = metric.dimensions();
-
-            if dimensions.is_empty() {
+            if metric.dimensions().is_empty() {
                 continue
             };
 
-            // Storage for what we'll pull out of the dimensions
-            let mut name         = String::new();
-            let mut storage_type = String::new();
-
-            // Process the dimensions, taking the bucket name and storage types
-            for dimension in dimensions {
-                // Extract the dimension name
-                let Some(dimension_name) = dimension.name() else {
-                    continue
-                };
-
-                match dimension_name {
-                    "BucketName" => {
-                        name = dimension.value()
-                            .map(ToOwned::to_owned)
-                            .unwrap();
-                    },
-                    "StorageType" => {
-                        storage_type = dimension.value()
-                            .map(ToOwned::to_owned)
-                            .unwrap();
-                    },
-                    _ => {},
-                }
-            }
+            let Some((name, storage_type)) = bucket_storage_type(&metric) else {
+                continue
+            };
 
             // Get the existing StorageTypes entry for the bucket, or create a
             // new one if it doesn't exist yet.
@@ -86,8 +117,11 @@ impl From<Vec<Metric>> for BucketMetrics {
                 .entry(name)
                 .or_insert_with(StorageTypes::new);
 
-            // Push the new storage type into the vec
-            storage_types.push(storage_type);
+            // Push the new storage type into the vec, unless we've seen it
+            // already.
+            if !storage_types.contains(&storage_type) {
+                storage_types.push(storage_type);
+            }
         }
 
         BucketMetrics(bucket_metrics)
@@ -177,6 +211,70 @@ mod tests {
         assert_eq!(metrics, expected);
     }
 
+    #[test]
+    fn test_bucket_metrics_from_malformed() {
+        let dimension = |name, value| {
+            Dimension::builder()
+                .name(name)
+                .value(value)
+                .build()
+        };
+
+        let metric = |dimensions| {
+            Metric::builder()
+                .metric_name("BucketSizeBytes")
+                .namespace("AWS/S3")
+                .set_dimensions(Some(dimensions))
+                .build()
+        };
+
+        let mut metrics = get_metrics();
+
+        // Two storage types, which can't be told apart.
+        metrics.push(metric(vec![
+            dimension("BucketName",  "some-bucket-name"),
+            dimension("StorageType", "GlacierStorage"),
+            dimension("StorageType", "StandardStorage"),
+        ]));
+
+        // Two bucket names.
+        metrics.push(metric(vec![
+            dimension("BucketName",  "some-bucket-name"),
+            dimension("BucketName",  "malformed-bucket-name"),
+            dimension("StorageType", "StandardStorage"),
+        ]));
+
+        // No storage type.
+        metrics.push(metric(vec![
+            dimension("BucketName", "malformed-bucket-name"),
+        ]));
+
+        // No bucket name.
+        metrics.push(metric(vec![
+            dimension("StorageType", "StandardStorage"),
+        ]));
+
+        let metrics: BucketMetrics = metrics.into();
+
+        // Only the well formed metrics remain.
+        let expected: BucketMetrics = get_metrics().into();
+
+        assert_eq!(metrics, expected);
+    }
+
+    #[test]
+    fn test_bucket_metrics_from_duplicates() {
+        // The same metrics again, as if returned across two pages.
+        let mut metrics = get_metrics();
+        metrics.extend(get_metrics());
+
+        let metrics: BucketMetrics = metrics.into();
+
+        let expected: BucketMetrics = get_metrics().into();
+
+        assert_eq!(metrics, expected);
+    }
+
     #[test]
     fn test_bucket_metrics_bucket_names() {
         let metrics = get_metrics();
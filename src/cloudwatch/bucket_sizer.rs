@@ -3,23 +3,99 @@
 #![deny(missing_docs)]
 use anyhow::{
     anyhow,
+    Context,
     Result,
 };
 use async_trait::async_trait;
+use aws_sdk_cloudwatch::types::{
+    Datapoint,
+    StandardUnit,
+};
 use crate::common::{
+    is_selected,
     Bucket,
     Buckets,
+    BucketSize,
     BucketSizer,
+    MetricDatapoint,
+    MetricStatistic,
 };
 use super::bucket_metrics::BucketMetrics;
 use super::client::Client;
+use std::collections::BTreeMap;
+use std::time::SystemTime;
 use tracing::debug;
 
+#[cfg(feature = "s3")]
+use tracing::warn;
+
+/// Returns the latest of the given `datapoints`, if any.
+fn latest_datapoint(mut datapoints: Vec<Datapoint>) -> Option<Datapoint> {
+    // We don't know which order datapoints will be in if we get more than a
+    // single datapoint, so we must sort them.
+    // We sort so that the latest datapoint is at index 0 of the vec.
+    datapoints.sort_by(|a, b| {
+        b.timestamp.cmp(&a.timestamp)
+    });
+
+    datapoints.into_iter().next()
+}
+
+/// Returns an error if the given `datapoint` isn't in the `expected` unit.
+///
+/// We always ask `CloudWatch` for the unit that we expect, a datapoint in any
+/// other unit would be silently misreported. Datapoints without a unit are
+/// assumed to be in the unit that we asked for.
+fn check_unit(datapoint: &Datapoint, expected: &StandardUnit) -> Result<()> {
+    match &datapoint.unit {
+        Some(unit) if unit != expected => {
+            Err(anyhow!(
+                "expected {}, got {}",
+                expected.as_str(),
+                unit.as_str(),
+            ))
+        },
+        _ => Ok(()),
+    }
+}
+
+/// Returns the `MetricDatapoint` for the given `datapoint`, with the given
+/// `value` of its `statistic`.
+fn metric_datapoint(
+    datapoint: Datapoint,
+    statistic: MetricStatistic,
+    value: f64,
+) -> MetricDatapoint {
+    let timestamp = datapoint.timestamp
+        .and_then(|timestamp| SystemTime::try_from(timestamp).ok());
+
+    let unit = datapoint.unit.map(|unit| unit.as_str().to_string());
+
+    MetricDatapoint {
+        statistic: statistic.to_string(),
+        timestamp,
+        unit,
+        value,
+    }
+}
+
 #[async_trait]
 impl BucketSizer for Client {
     /// Return a list of S3 bucket names from CloudWatch.
     /// We also cache the returned metrics here, since we need to reference this
     /// elsewhere, and we don't want to have to query for it again.
+    ///
+    /// `CloudWatch` metrics are regional, so every bucket is in our `Region`.
+    ///
+    /// Buckets are filtered by the `bucket` arguments provided on the command
+    /// line, if any, which may contain wildcards. Buckets matching any of the
+    /// `--exclude` patterns are skipped.
+    ///
+    /// If storage types were selected, only those storage types are kept and
+    /// buckets without any of them are skipped.
+    ///
+    /// If verification was requested, buckets that no longer exist in S3 are
+    /// skipped with a warning.
     async fn buckets(&self) -> Result<Buckets> {
         debug!("buckets: Listing...");
 
@@ -28,14 +104,45 @@ impl BucketSizer for Client {
         let mut buckets = Buckets::new();
 
         for bucket in metrics.bucket_names() {
-            let storage_types = metrics.storage_types(&bucket).clone();
+            if !is_selected(&self.bucket_names, &bucket) {
+                continue;
+            }
+
+            if self.exclude.iter().any(|pattern| pattern.matches(&bucket)) {
+                debug!("Excluding '{}'", bucket);
+
+                continue;
+            }
+
+            let mut storage_types = metrics.storage_types(&bucket).clone();
+
+            if let Some(selected) = &self.storage_types {
+                storage_types.retain(|st| selected.contains(st));
+
+                if storage_types.is_empty() {
+                    debug!("Skipping '{}', no selected storage types", bucket);
+
+                    continue;
+                }
+            }
 
             let bucket = Bucket {
                 name:          bucket,
-                region:        None,
+                region:        Some(self.region.clone()),
                 storage_types: Some(storage_types),
+                is_versioned:  None,
+                created:       None,
             };
 
+            #[cfg(feature = "s3")]
+            if let Some(s3) = &self.s3 {
+                if !self.bucket_exists(s3, &bucket.name).await? {
+                    warn!("Skipping '{}', it no longer exists", bucket.name);
+
+                    continue;
+                }
+            }
+
             buckets.push(bucket);
         }
 
@@ -45,17 +152,28 @@ impl BucketSizer for Client {
     /// Get the size of a given bucket
     #[allow(clippy::cast_possible_truncation)]
     #[allow(clippy::cast_sign_loss)]
-    async fn bucket_size(&self, bucket: &Bucket) -> Result<u64> {
+    async fn bucket_size(&self, bucket: &Bucket) -> Result<BucketSize> {
         let bucket_name = &bucket.name;
 
         debug!("bucket_size: Calculating size for '{}'", bucket_name);
 
-        let mut size = 0;
+        // CloudWatch metrics are daily averages, so are always an estimate.
+        let mut size = BucketSize {
+            estimated: true,
+            ..Default::default()
+        };
+
+        let mut storage_classes = BTreeMap::new();
+        let mut datapoints_used = self.datapoints.then(BTreeMap::new);
+
+        // There is one output per storage type, in the same order as the
+        // bucket's storage types.
+        let storage_types = bucket.storage_types.iter().flatten();
 
         let metric_statistics = self.get_metric_statistics(bucket).await?;
-        for stats in metric_statistics {
+        for (storage_type, stats) in storage_types.zip(metric_statistics) {
             // If we don't get any datapoints, proceed to the next input.
-            let Some(mut datapoints) = stats.datapoints else {
+            let Some(datapoints) = stats.datapoints else {
                 continue
             };
 
@@ -63,32 +181,83 @@ impl BucketSizer for Client {
             // error in this case.
             if datapoints.is_empty() {
                 return Err(
-                    anyhow!("Failed to fetch any CloudWatch datapoints!")
+                    anyhow!(
+                        "Failed to fetch any CloudWatch datapoints, a larger \
+                         --cloudwatch-lookback-days may find older ones",
+                    )
                 )
             };
 
-            // We don't know which order datapoints will be in if we get more
-            // than a single datapoint, so we must sort them.
-            // We sort so that the latest datapoint is at index 0 of the vec.
-            datapoints.sort_by(|a, b| {
-                b.timestamp.cmp(&a.timestamp)
-            });
-
-            let datapoint = &datapoints[0];
-
-            // BucketSizeBytes only supports Average, so this should be safe
-            // to unwrap.
-            let bytes = datapoint.average
-                .expect("Couldn't unwrap average");
+            // We know that there's at least one datapoint, so this should be
+            // safe to unwrap.
+            let datapoint = latest_datapoint(datapoints)
+                .expect("Couldn't unwrap datapoint");
+
+            check_unit(&datapoint, &StandardUnit::Bytes)
+                .with_context(|| {
+                    format!(
+                        "Unexpected BucketSizeBytes unit for '{}' {}",
+                        bucket_name,
+                        storage_type,
+                    )
+                })?;
+
+            // We asked for this statistic, so this should be safe to unwrap.
+            let value = self.statistic.value(&datapoint)
+                .expect("Couldn't unwrap statistic");
+
+            // The size is only as fresh as the oldest storage type.
+            let timestamp = datapoint.timestamp
+                .and_then(|timestamp| SystemTime::try_from(timestamp).ok());
+
+            size.datapoint_timestamp = [size.datapoint_timestamp, timestamp]
+                .into_iter()
+                .flatten()
+                .min();
+
+            if let Some(datapoints_used) = &mut datapoints_used {
+                datapoints_used.insert(
+                    storage_type.clone(),
+                    metric_datapoint(datapoint, self.statistic, value),
+                );
+            }
 
             // Add up the size of each storage type
             // Do a bit of rounding here to get an integer value before
             // converting to u64.
-            size += bytes.round() as u64;
+            let bytes = value.round() as u64;
+
+            size.bytes += bytes;
+            storage_classes.insert(storage_type.clone(), bytes);
+        }
+
+        size.storage_classes = Some(storage_classes);
+        size.datapoints      = datapoints_used;
+
+        // Unlike the size, a missing object count isn't an error. The count
+        // is simply reported as unknown.
+        if self.count {
+            let stats = self.get_object_count_statistics(bucket).await?;
+
+            let datapoint = stats.datapoints.and_then(latest_datapoint);
+
+            if let Some(datapoint) = &datapoint {
+                check_unit(datapoint, &StandardUnit::Count)
+                    .with_context(|| {
+                        format!(
+                            "Unexpected NumberOfObjects unit for '{}'",
+                            bucket_name,
+                        )
+                    })?;
+            }
+
+            size.count = datapoint
+                .and_then(|datapoint| self.statistic.value(&datapoint))
+                .map(|count| count.round() as u64);
         }
 
         debug!(
-            "bucket_size: Calculated bucket size for '{}' is '{}'",
+            "bucket_size: Calculated bucket size for '{}' is '{:?}'",
             bucket_name,
             size,
         );
@@ -100,6 +269,7 @@ impl BucketSizer for Client {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::common::Region;
     use aws_credential_types::Credentials;
     use aws_sdk_cloudwatch::{
         client::Client as CloudWatchClient,
@@ -110,25 +280,42 @@ mod tests {
         StaticReplayClient,
     };
     use aws_smithy_types::body::SdkBody;
+    use glob::Pattern;
     use pretty_assertions::assert_eq;
     use std::fs;
     use std::path::Path;
+    use std::time::Duration;
+
+    // Time of the datapoints in the test data, 2020-03-01T20:59:00Z.
+    fn datapoint_timestamp() -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(1_583_096_340)
+    }
 
     // Create a mock CloudWatch client, returning the data from the specified
     // data_file.
     fn mock_client(
         data_file: Option<&str>,
     ) -> Client {
-        let data = match data_file {
-            None    => "".to_string(),
-            Some(d) => {
-                let path = Path::new("test-data").join(d);
-                fs::read_to_string(path).unwrap()
-            },
+        let files = match data_file {
+            None    => Vec::new(),
+            Some(d) => vec![d],
         };
 
-        let http_client = StaticReplayClient::new(vec![
-            ReplayEvent::new(
+        mock_client_with_files(files)
+    }
+
+    // Create a mock CloudWatch client, returning the data from each of the
+    // specified data_files in turn.
+    fn mock_client_with_files(
+        data_files: Vec<&str>,
+    ) -> Client {
+        let mut events = Vec::new();
+
+        for data_file in data_files {
+            let path = Path::new("test-data").join(data_file);
+            let data = fs::read_to_string(path).unwrap();
+
+            let event = ReplayEvent::new(
                 http::Request::builder()
                     .body(SdkBody::from("request body"))
                     .unwrap(),
@@ -137,8 +324,12 @@ mod tests {
                     .status(200)
                     .body(SdkBody::from(data))
                     .unwrap(),
-            ),
-        ]);
+            );
+
+            events.push(event);
+        }
+
+        let http_client = StaticReplayClient::new(events);
 
         let creds = Credentials::for_tests_with_session_token();
 
@@ -153,7 +344,17 @@ mod tests {
 
         Client {
             client,
-            bucket_name: None,
+            bucket_names:  Vec::new(),
+            count:         false,
+            datapoints:    false,
+            exclude:       Vec::new(),
+            lookback_days: 2,
+            region:        Region::new().set_region("eu-west-1"),
+            statistic:     MetricStatistic::Average,
+            storage_types: None,
+
+            #[cfg(feature = "s3")]
+            s3: None,
         }
     }
 
@@ -179,6 +380,140 @@ mod tests {
         assert_eq!(buckets, expected);
     }
 
+    #[tokio::test]
+    async fn test_buckets_selected() {
+        let tests = vec![
+            (vec!["another-*"],     vec!["another-bucket-name"]),
+            (vec!["a-bucket-name"], vec!["a-bucket-name"]),
+            (vec!["*-bucket-?ame"], vec!["a-bucket-name", "another-bucket-name"]),
+            (vec!["missing-*"],     vec![]),
+        ];
+
+        for test in tests {
+            let bucket_names = test.0;
+            let expected     = test.1;
+
+            let mut client = mock_client(
+                Some("cloudwatch-list-metrics.xml"),
+            );
+
+            client.bucket_names = bucket_names.iter()
+                .map(|name| name.to_string())
+                .collect();
+
+            let buckets = client.buckets().await.unwrap();
+
+            let mut buckets: Vec<String> = buckets.iter()
+                .map(|b| b.name.to_owned())
+                .collect();
+
+            buckets.sort();
+
+            assert_eq!(buckets, expected, "{bucket_names:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_buckets_exclude() {
+        let tests = vec![
+            ("another-*",     vec!["a-bucket-name"]),
+            ("*-bucket-name", vec![]),
+            ("A-*",           vec!["a-bucket-name", "another-bucket-name"]),
+        ];
+
+        for test in tests {
+            let pattern  = test.0;
+            let expected = test.1;
+
+            let mut client = mock_client(
+                Some("cloudwatch-list-metrics.xml"),
+            );
+
+            client.exclude = vec![
+                Pattern::new(pattern).unwrap(),
+            ];
+
+            let buckets = client.buckets().await.unwrap();
+
+            let mut buckets: Vec<String> = buckets.iter()
+                .map(|b| b.name.to_owned())
+                .collect();
+
+            buckets.sort();
+
+            assert_eq!(buckets, expected, "{pattern}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_buckets_storage_types() {
+        let tests = vec![
+            (
+                None,
+                vec![
+                    ("a-bucket-name", vec![
+                        "StandardIAStorage",
+                        "StandardStorage",
+                    ]),
+                    ("another-bucket-name", vec!["StandardStorage"]),
+                ],
+            ),
+            (
+                Some(vec!["StandardStorage"]),
+                vec![
+                    ("a-bucket-name", vec!["StandardStorage"]),
+                    ("another-bucket-name", vec!["StandardStorage"]),
+                ],
+            ),
+            (
+                Some(vec!["StandardIAStorage"]),
+                vec![
+                    ("a-bucket-name", vec!["StandardIAStorage"]),
+                ],
+            ),
+            (
+                Some(vec!["GlacierStorage"]),
+                vec![],
+            ),
+        ];
+
+        for test in tests {
+            let storage_types = test.0;
+            let expected      = test.1;
+
+            let mut client = mock_client(
+                Some("cloudwatch-list-metrics.xml"),
+            );
+
+            client.storage_types = storage_types.clone().map(|types| {
+                types.into_iter().map(String::from).collect()
+            });
+
+            let buckets = client.buckets().await.unwrap();
+
+            let mut ret: Vec<(String, Vec<String>)> = buckets.iter()
+                .map(|b| {
+                    let mut storage_types = b.storage_types.clone().unwrap();
+                    storage_types.sort();
+
+                    (b.name.clone(), storage_types)
+                })
+                .collect();
+
+            ret.sort();
+
+            let expected: Vec<(String, Vec<String>)> = expected.into_iter()
+                .map(|(name, types)| {
+                    let types = types.into_iter().map(String::from).collect();
+
+                    (name.into(), types)
+                })
+                .collect();
+
+            assert_eq!(ret, expected, "{storage_types:?}");
+        }
+    }
+
     #[tokio::test]
     async fn test_bucket_size() {
         let client = mock_client(
@@ -193,12 +528,187 @@ mod tests {
             name:          "some-other-bucket-name".into(),
             region:        None,
             storage_types: Some(storage_types),
+            is_versioned:  None,
+            created:       None,
         };
 
         let ret = client.bucket_size(&bucket).await.unwrap();
 
-        let expected = 123_456_789;
+        let expected = BucketSize {
+            bytes:               123_456_789,
+            datapoint_timestamp: Some(datapoint_timestamp()),
+            estimated:           true,
+            storage_classes:     Some(BTreeMap::from([
+                ("StandardStorage".into(), 123_456_789),
+            ])),
+            ..Default::default()
+        };
 
         assert_eq!(ret, expected);
     }
+
+    #[tokio::test]
+    async fn test_bucket_size_count() {
+        let tests = vec![
+            ("cloudwatch-get-metric-statistics-count.xml", Some(42)),
+            ("cloudwatch-get-metric-statistics-empty.xml", None),
+        ];
+
+        for test in tests {
+            let count_file = test.0;
+            let expected   = test.1;
+
+            let mut client = mock_client_with_files(vec![
+                "cloudwatch-get-metric-statistics.xml",
+                count_file,
+            ]);
+
+            client.count = true;
+
+            let storage_types = vec![
+                "StandardStorage".into(),
+            ];
+
+            let bucket = Bucket {
+                name:          "some-other-bucket-name".into(),
+                region:        None,
+                storage_types: Some(storage_types),
+                is_versioned:  None,
+                created:       None,
+            };
+
+            let ret = client.bucket_size(&bucket).await.unwrap();
+
+            let expected = BucketSize {
+                bytes:               123_456_789,
+                count:               expected,
+                datapoint_timestamp: Some(datapoint_timestamp()),
+                estimated:           true,
+                storage_classes:     Some(BTreeMap::from([
+                    ("StandardStorage".into(), 123_456_789),
+                ])),
+                ..Default::default()
+            };
+
+            assert_eq!(ret, expected);
+        }
+    }
+    #[tokio::test]
+    async fn test_bucket_size_statistic() {
+        let mut client = mock_client(
+            Some("cloudwatch-get-metric-statistics-maximum.xml"),
+        );
+
+        client.statistic = MetricStatistic::Maximum;
+
+        let storage_types = vec![
+            "StandardStorage".into(),
+        ];
+
+        let bucket = Bucket {
+            name:          "some-other-bucket-name".into(),
+            region:        None,
+            storage_types: Some(storage_types),
+            is_versioned:  None,
+            created:       None,
+        };
+
+        let ret = client.bucket_size(&bucket).await.unwrap();
+
+        let expected = BucketSize {
+            bytes:               987_654_321,
+            datapoint_timestamp: Some(datapoint_timestamp()),
+            estimated:           true,
+            storage_classes:     Some(BTreeMap::from([
+                ("StandardStorage".into(), 987_654_321),
+            ])),
+            ..Default::default()
+        };
+
+        assert_eq!(ret, expected);
+    }
+
+    #[tokio::test]
+    async fn test_bucket_size_datapoints() {
+        let mut client = mock_client(
+            Some("cloudwatch-get-metric-statistics.xml"),
+        );
+
+        client.datapoints = true;
+
+        let storage_types = vec![
+            "StandardStorage".into(),
+        ];
+
+        let bucket = Bucket {
+            name:          "some-other-bucket-name".into(),
+            region:        None,
+            storage_types: Some(storage_types),
+            is_versioned:  None,
+            created:       None,
+        };
+
+        let ret = client.bucket_size(&bucket).await.unwrap();
+
+        let expected = BTreeMap::from([
+            ("StandardStorage".into(), MetricDatapoint {
+                statistic: "average".into(),
+                timestamp: Some(datapoint_timestamp()),
+                unit:      Some("Bytes".into()),
+                value:     123_456_789.0,
+            }),
+        ]);
+
+        assert_eq!(ret.bytes, 123_456_789);
+        assert_eq!(ret.datapoints, Some(expected));
+    }
+    #[tokio::test]
+    async fn test_bucket_size_unit() {
+        let tests = vec![
+            (
+                vec!["cloudwatch-get-metric-statistics.xml"],
+                false,
+                true,
+            ),
+            (
+                vec!["cloudwatch-get-metric-statistics-kilobytes.xml"],
+                false,
+                false,
+            ),
+            (
+                vec![
+                    "cloudwatch-get-metric-statistics.xml",
+                    "cloudwatch-get-metric-statistics.xml",
+                ],
+                true,
+                false,
+            ),
+        ];
+
+        for test in tests {
+            let data_files = test.0;
+            let count      = test.1;
+            let expected   = test.2;
+
+            let mut client = mock_client_with_files(data_files);
+
+            client.count = count;
+
+            let storage_types = vec![
+                "StandardStorage".into(),
+            ];
+
+            let bucket = Bucket {
+                name:          "some-other-bucket-name".into(),
+                region:        None,
+                storage_types: Some(storage_types),
+                is_versioned:  None,
+                created:       None,
+            };
+
+            let ret = client.bucket_size(&bucket).await;
+
+            assert_eq!(ret.is_ok(), expected);
+        }
+    }
 }
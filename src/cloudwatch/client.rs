@@ -13,18 +13,28 @@ use aws_sdk_cloudwatch::types::{
     DimensionFilter,
     Metric,
     StandardUnit,
-    Statistic,
 };
 use crate::common::{
+    is_wildcard,
     Bucket,
     ClientConfig,
+    MetricStatistic,
+    Region,
+    StorageTypes,
 };
+use crate::connection;
+use crate::credentials;
+use futures::future;
+use glob::Pattern;
 use std::time::{
     Duration,
     SystemTime,
 };
 use tracing::debug;
 
+#[cfg(feature = "s3")]
+use aws_sdk_s3::client::Client as S3Client;
+
 const ONE_DAY: Duration = Duration::from_secs(86_400);
 
 /// A `CloudWatch` `Client`
@@ -32,28 +42,156 @@ pub struct Client {
     /// The AWS SDK `CloudWatchClient`.
     pub client: CloudWatchClient,
 
-    /// Bucket name that was selected, if any.
-    pub bucket_name: Option<String>,
+    /// Bucket names that were selected, if any.
+    pub bucket_names: Vec<String>,
+
+    /// Whether object counts were requested.
+    pub count: bool,
+
+    /// Whether the raw datapoints used for bucket sizes were requested.
+    pub datapoints: bool,
+
+    /// Patterns matching the names of buckets to skip.
+    pub exclude: Vec<Pattern>,
+
+    /// Number of days to look back through for the latest datapoint.
+    pub lookback_days: u32,
+
+    /// `Region` that we're getting bucket metrics from.
+    pub region: Region,
+
+    /// The statistic of each daily period that we request.
+    pub statistic: MetricStatistic,
+
+    /// Storage types that bucket sizes are limited to, if any.
+    pub storage_types: Option<StorageTypes>,
+
+    /// S3 client used to verify that buckets still exist, if requested.
+    ///
+    /// This field will only be present when compiled with the `s3` feature.
+    #[cfg(feature = "s3")]
+    pub s3: Option<S3Client>,
 }
 
 impl Client {
     /// Return a new `Client` with the given `ClientConfig`.
     pub async fn new(config: ClientConfig) -> Self {
-        let bucket_name = config.bucket_name;
-        let region      = config.region;
+        let bucket_names    = config.bucket_names;
+        let count           = config.count;
+        let datapoints      = config.datapoints;
+        let exclude         = config.exclude;
+        let lookback_days   = config.cloudwatch_lookback_days;
+        let statistic       = config.cloudwatch_statistic;
+        let storage_types   = config.cloudwatch_storage_types;
+        let max_connections = config.max_connections;
+        let profile         = config.profile;
+        let proxy           = config.proxy;
+        let region          = config.region;
+        let role_arn        = config.role_arn;
+        let external_id     = config.external_id;
+
+        let throttle_counter = config.throttle_counter;
+
+        #[cfg(feature = "s3")]
+        let verify_exists = config.verify_exists;
 
         debug!("new: Creating CloudWatchClient in region '{}'", region.name());
 
         let config = aws_config::from_env()
-            .region(region.clone())
+            .region(region.clone());
+
+        let config = if let Some(profile) = &profile {
+            config.profile_name(profile)
+        }
+        else {
+            config
+        };
+
+        let http_client = connection::http_client(
+            proxy.as_deref(),
+            max_connections,
+        );
+
+        let config = if let Some(http_client) = http_client {
+            config.http_client(http_client)
+        }
+        else {
+            config
+        };
+
+        let config = config
             .load()
             .await;
 
-        let client = CloudWatchClient::new(&config);
+        let config = credentials::assume_role(
+            config,
+            role_arn.as_deref(),
+            external_id.as_deref(),
+        ).await;
+
+        // Throttled requests are counted so that we can suggest slowing
+        // down once sizing is done.
+        let client = CloudWatchClient::from_conf(
+            aws_sdk_cloudwatch::config::Builder::from(&config)
+                .interceptor(throttle_counter.clone())
+                .build()
+        );
+
+        // Bucket existence is checked with S3, using the same configuration.
+        #[cfg(feature = "s3")]
+        let s3 = verify_exists.then(|| {
+            S3Client::from_conf(
+                aws_sdk_s3::config::Builder::from(&config)
+                    .interceptor(throttle_counter)
+                    .build()
+            )
+        });
 
         Self {
             client,
-            bucket_name,
+            bucket_names,
+            count,
+            datapoints,
+            exclude,
+            lookback_days,
+            region,
+            statistic,
+            storage_types,
+
+            #[cfg(feature = "s3")]
+            s3,
+        }
+    }
+
+    /// Returns a `bool` indicating if the given `bucket` still exists in S3.
+    ///
+    /// `CloudWatch` metrics linger for a while after a bucket is deleted.
+    /// Buckets that we can't access are assumed to exist, only a `404` means
+    /// that the bucket is gone.
+    #[cfg(feature = "s3")]
+    pub async fn bucket_exists(
+        &self,
+        s3: &S3Client,
+        bucket: &str,
+    ) -> Result<bool> {
+        debug!("bucket_exists for '{}'", bucket);
+
+        let output = s3.head_bucket()
+            .bucket(bucket)
+            .send()
+            .await;
+
+        let Err(error) = output else {
+            return Ok(true)
+        };
+
+        let status = error.raw_response()
+            .map(|response| response.status().as_u16());
+
+        match status {
+            Some(404) => Ok(false),
+            Some(_)   => Ok(true),
+            None      => Err(error.into()),
         }
     }
 
@@ -67,52 +205,87 @@ impl Client {
     ) -> Result<Vec<GetMetricStatisticsOutput>> {
         debug!("get_metric_statistics: Processing {:?}", bucket);
 
-        // These are used repeatedly while looping, just prepare them once.
+        let storage_types = match &bucket.storage_types {
+            Some(st) => st.as_slice(),
+            None     => &[],
+        };
+
+        // Statistics for each storage type are requested concurrently, the
+        // outputs stay in the same order as the storage types.
+        let outputs = storage_types.iter().map(|storage_type| {
+            self.metric_statistics(
+                &bucket.name,
+                "BucketSizeBytes",
+                storage_type,
+                StandardUnit::Bytes,
+            )
+        });
+
+        future::try_join_all(outputs).await
+    }
+
+    /// Returns the `NumberOfObjects` `GetMetricStatisticsOutput` for the given
+    /// `Bucket`.
+    ///
+    /// Unlike `BucketSizeBytes`, `NumberOfObjects` is only reported for the
+    /// `AllStorageTypes` storage type, so there is only a single output.
+    pub async fn get_object_count_statistics(
+        &self,
+        bucket: &Bucket,
+    ) -> Result<GetMetricStatisticsOutput> {
+        debug!("get_object_count_statistics: Processing {:?}", bucket);
+
+        self.metric_statistics(
+            &bucket.name,
+            "NumberOfObjects",
+            "AllStorageTypes",
+            StandardUnit::Count,
+        ).await
+    }
+
+    /// Returns the daily statistics over the last `lookback_days` days for
+    /// the given S3 `metric_name`, `bucket_name` and `storage_type`.
+    async fn metric_statistics(
+        &self,
+        bucket_name: &str,
+        metric_name: &str,
+        storage_type: &str,
+        unit: StandardUnit,
+    ) -> Result<GetMetricStatisticsOutput> {
         let now = SystemTime::now();
-        let start_time = DateTime::from(now - (ONE_DAY * 2));
+        let start_time = DateTime::from(now - (ONE_DAY * self.lookback_days));
 
         let period = i32::try_from(ONE_DAY.as_secs())
             .context("period")?;
 
-        let storage_types = match &bucket.storage_types {
-            Some(st) => st.clone(),
-            None     => Vec::new(),
-        };
-
-        let mut outputs = Vec::new();
-
-        for storage_type in storage_types {
-            let dimensions = vec![
-                Dimension::builder()
-                    .name("BucketName")
-                    .value(bucket.name.clone())
-                    .build(),
-                Dimension::builder()
-                    .name("StorageType")
-                    .value(storage_type.clone())
-                    .build(),
-            ];
-
-            let input = self.client.get_metric_statistics()
-                .end_time(DateTime::from(now))
-                .metric_name("BucketSizeBytes")
-                .namespace("AWS/S3")
-                .period(period)
-                .set_dimensions(Some(dimensions))
-                .start_time(start_time)
-                .statistics(Statistic::Average)
-                .unit(StandardUnit::Bytes);
+        let dimensions = vec![
+            Dimension::builder()
+                .name("BucketName")
+                .value(bucket_name)
+                .build(),
+            Dimension::builder()
+                .name("StorageType")
+                .value(storage_type)
+                .build(),
+        ];
 
-            debug!("{:?}", input);
+        let input = self.client.get_metric_statistics()
+            .end_time(DateTime::from(now))
+            .metric_name(metric_name)
+            .namespace("AWS/S3")
+            .period(period)
+            .set_dimensions(Some(dimensions))
+            .start_time(start_time)
+            .statistics(self.statistic.statistic())
+            .unit(unit);
 
-            let output = input
-                .send()
-                .await?;
+        debug!("{:?}", input);
 
-            outputs.push(output);
-        }
+        let output = input
+            .send()
+            .await?;
 
-        Ok(outputs)
+        Ok(output)
     }
 
     /// Get list of buckets with `BucketSizeBytes` metrics.
@@ -137,44 +310,59 @@ impl Client {
     pub async fn list_metrics(&self) -> Result<Vec<Metric>> {
         debug!("list_metrics: Listing...");
 
-        let mut metrics    = Vec::new();
-        let mut next_token = None;
-
-        // If we selected a bucket to list, filter for it here.
-        let dimensions = match self.bucket_name.as_ref() {
-            Some(bucket_name) => {
-                let filter = DimensionFilter::builder()
-                    .name("BucketName")
-                    .value(bucket_name.clone())
-                    .build();
-
-                Some(vec![filter])
-            },
-            None => None,
-        };
-
-        // We loop until we've processed everything.
-        loop {
-            // Input for CloudWatch API
-            let output = self.client.list_metrics()
-                .namespace("AWS/S3")
-                .metric_name("BucketSizeBytes")
-                .set_dimensions(dimensions.clone())
-                .set_next_token(next_token)
-                .send()
-                .await?;
-
-            debug!("list_metrics: API returned: {:#?}", output);
-
-            // If we get any metrics, append them to our vec
-            let metric = output.metrics();
-            metrics.append(&mut metric.to_vec());
-
-            // If there was a next token, use it, otherwise the loop is done.
-            match output.next_token() {
-                Some(t) => next_token = Some(t.to_string()),
-                None    => break,
+        let mut metrics = Vec::new();
+
+        // If we selected buckets to list, filter for them here. Dimension
+        // filters must all match, so each bucket needs a listing of its own.
+        // Dimension filters can't match wildcards, so if any were given we
+        // list every bucket and leave the filtering to `buckets`.
+        let wildcards = self.bucket_names.iter()
+            .any(|bucket_name| is_wildcard(bucket_name));
+
+        let dimensions: Vec<Option<Vec<DimensionFilter>>> =
+            if self.bucket_names.is_empty() || wildcards {
+                vec![None]
+            }
+            else {
+                self.bucket_names.iter()
+                    .map(|bucket_name| {
+                        let filter = DimensionFilter::builder()
+                            .name("BucketName")
+                            .value(bucket_name.clone())
+                            .build();
+
+                        Some(vec![filter])
+                    })
+                    .collect()
             };
+
+        for dimensions in dimensions {
+            let mut next_token = None;
+
+            // We loop until we've processed everything.
+            loop {
+                // Input for CloudWatch API
+                let output = self.client.list_metrics()
+                    .namespace("AWS/S3")
+                    .metric_name("BucketSizeBytes")
+                    .set_dimensions(dimensions.clone())
+                    .set_next_token(next_token)
+                    .send()
+                    .await?;
+
+                debug!("list_metrics: API returned: {:#?}", output);
+
+                // If we get any metrics, append them to our vec
+                let metric = output.metrics();
+                metrics.append(&mut metric.to_vec());
+
+                // If there was a next token, use it, otherwise the loop is
+                // done.
+                match output.next_token() {
+                    Some(t) => next_token = Some(t.to_string()),
+                    None    => break,
+                };
+            }
         }
 
         debug!("list_metrics: Metrics collection: {:#?}", metrics);
@@ -229,6 +417,14 @@ mod tests {
                 ),
         ]);
 
+        mock_client_with_http(http_client)
+    }
+
+    // Create a mock CloudWatch client, making requests with the given
+    // http_client.
+    fn mock_client_with_http(
+        http_client: StaticReplayClient,
+    ) -> Client {
         let creds = Credentials::for_tests_with_session_token();
 
         let conf = CloudWatchConfig::builder()
@@ -242,7 +438,71 @@ mod tests {
 
         Client {
             client,
-            bucket_name: None,
+            bucket_names:  Vec::new(),
+            count:         false,
+            datapoints:    false,
+            exclude:       Vec::new(),
+            lookback_days: 2,
+            region:        Region::new().set_region("eu-west-1"),
+            statistic:     MetricStatistic::Average,
+            storage_types: None,
+
+            #[cfg(feature = "s3")]
+            s3: None,
+        }
+    }
+
+    // Create a mock S3 client, responding with the given status code.
+    #[cfg(feature = "s3")]
+    fn mock_s3_client(status: u16) -> S3Client {
+        use aws_sdk_s3::config::Config as S3Config;
+
+        let http_client = StaticReplayClient::new(vec![
+            ReplayEvent::new(
+                http::Request::builder()
+                    .body(SdkBody::from("request body"))
+                    .unwrap(),
+
+                http::Response::builder()
+                    .status(status)
+                    .body(SdkBody::empty())
+                    .unwrap(),
+            ),
+        ]);
+
+        let creds = Credentials::for_tests_with_session_token();
+
+        let conf = S3Config::builder()
+            .behavior_version_latest()
+            .credentials_provider(creds)
+            .http_client(http_client)
+            .region(aws_sdk_s3::config::Region::new("eu-west-1"))
+            .build();
+
+        S3Client::from_conf(conf)
+    }
+
+    #[cfg(feature = "s3")]
+    #[tokio::test]
+    async fn test_bucket_exists() {
+        let tests = vec![
+            (200, true),
+            (403, true),
+            (404, false),
+        ];
+
+        for test in tests {
+            let status   = test.0;
+            let expected = test.1;
+
+            let client = mock_client(None);
+            let s3     = mock_s3_client(status);
+
+            let ret = client.bucket_exists(&s3, "test-bucket")
+                .await
+                .unwrap();
+
+            assert_eq!(ret, expected, "status {status}");
         }
     }
 
@@ -260,6 +520,8 @@ mod tests {
             name:          "test-bucket".into(),
             region:        None,
             storage_types: Some(storage_types),
+            is_versioned:  None,
+            created:       None,
         };
 
         let ret = client.get_metric_statistics(&bucket)
@@ -289,6 +551,45 @@ mod tests {
         assert_eq!(ret, expected);
     }
 
+    #[tokio::test]
+    async fn test_get_object_count_statistics() {
+        let client = mock_client(
+            Some("cloudwatch-get-metric-statistics-count.xml"),
+        );
+
+        let bucket = Bucket {
+            name:          "test-bucket".into(),
+            region:        None,
+            storage_types: None,
+            is_versioned:  None,
+            created:       None,
+        };
+
+        let ret = client.get_object_count_statistics(&bucket)
+            .await
+            .unwrap();
+
+        let timestamp = DateTime::from_str(
+            "2020-03-01T20:59:00Z",
+            DateTimeFormat::DateTime,
+        ).unwrap();
+
+        let datapoints = vec![
+            Datapoint::builder()
+                .average(42.0)
+                .timestamp(timestamp)
+                .unit(StandardUnit::Count)
+                .build(),
+        ];
+
+        let expected = GetMetricStatisticsOutput::builder()
+            .set_datapoints(Some(datapoints))
+            .set_label(Some("NumberOfObjects".into()))
+            .build();
+
+        assert_eq!(ret, expected);
+    }
+
     #[tokio::test]
     async fn test_list_metrics() {
         let client = mock_client(
@@ -349,4 +650,101 @@ mod tests {
 
         assert_eq!(ret, expected);
     }
+
+    #[tokio::test]
+    async fn test_list_metrics_bucket_names() {
+        let bucket_names = vec![
+            "a-bucket-name",
+            "another-bucket-name",
+        ];
+
+        let data = fs::read_to_string(
+            Path::new("test-data").join("cloudwatch-list-metrics.xml"),
+        ).unwrap();
+
+        // Each bucket is listed separately.
+        let events = bucket_names.iter()
+            .map(|_| {
+                ReplayEvent::new(
+                    http::Request::builder()
+                        .body(SdkBody::from("request body"))
+                        .unwrap(),
+
+                    http::Response::builder()
+                        .status(200)
+                        .body(SdkBody::from(data.clone()))
+                        .unwrap(),
+                )
+            })
+            .collect();
+
+        let http_client = StaticReplayClient::new(events);
+
+        let mut client = mock_client_with_http(http_client.clone());
+
+        client.bucket_names = bucket_names.iter()
+            .map(|name| name.to_string())
+            .collect();
+
+        client.list_metrics().await.unwrap();
+
+        let requests: Vec<String> = http_client.actual_requests()
+            .map(|request| {
+                let body = request.body().bytes().unwrap();
+
+                String::from_utf8(body.to_vec()).unwrap()
+            })
+            .collect();
+
+        assert_eq!(requests.len(), bucket_names.len());
+
+        for (request, bucket_name) in requests.iter().zip(bucket_names) {
+            let filter = format!("Dimensions.member.1.Value={bucket_name}");
+
+            assert!(request.contains(&filter), "{request}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_metrics_wildcard() {
+        let data = fs::read_to_string(
+            Path::new("test-data").join("cloudwatch-list-metrics.xml"),
+        ).unwrap();
+
+        let http_client = StaticReplayClient::new(vec![
+            ReplayEvent::new(
+                http::Request::builder()
+                    .body(SdkBody::from("request body"))
+                    .unwrap(),
+
+                http::Response::builder()
+                    .status(200)
+                    .body(SdkBody::from(data))
+                    .unwrap(),
+            ),
+        ]);
+
+        let mut client = mock_client_with_http(http_client.clone());
+
+        client.bucket_names = vec![
+            "a-bucket-name".into(),
+            "another-*".into(),
+        ];
+
+        let ret = client.list_metrics().await.unwrap();
+
+        // Wildcards list every bucket in a single request, without any
+        // dimension filters.
+        let requests: Vec<String> = http_client.actual_requests()
+            .map(|request| {
+                let body = request.body().bytes().unwrap();
+
+                String::from_utf8(body.to_vec()).unwrap()
+            })
+            .collect();
+
+        assert_eq!(ret.len(), 3);
+        assert_eq!(requests.len(), 1);
+        assert!(!requests[0].contains("Dimensions"), "{}", requests[0]);
+    }
 }
@@ -5,6 +5,9 @@
 /// The `Bucket` struct
 mod bucket;
 
+/// `BucketSize` struct returned by `BucketSizer`s.
+mod bucket_size;
+
 /// The `BucketSizer` trait.
 mod bucket_sizer;
 
@@ -17,6 +20,13 @@ mod client_mode;
 /// `HumanSize` trait for `usize` used to output friendly bucket sizes.
 mod human_size;
 
+/// `OutputConfig` holds configuration for how bucket sizes are output.
+mod output_config;
+
+/// `OutputFormat` enum is used to select how the bucket sizes will be
+/// written.
+mod output_format;
+
 /// `Region` struct wraps a basic string and allows us to return appropriate
 /// AWS types when needed.
 mod region;
@@ -24,22 +34,57 @@ mod region;
 /// `SizeUnit` enum is used to select how the bucket sizes will be output.
 mod size_unit;
 
+/// `SortKey` enum is used to select what bucket sizes are sorted by.
+mod sort_key;
+
+/// `SortOrder` enum is used to select the direction bucket sizes are sorted
+/// in.
+mod sort_order;
+
+/// `Template` is a user supplied format string used for template output.
+mod template;
+
+/// `ThrottleCounter` counts the requests that AWS throttled.
+mod throttle_counter;
+
+/// `MetricStatistic` selects which `CloudWatch` statistic will be used for
+/// the size of the buckets.
+#[cfg(feature = "cloudwatch")]
+mod metric_statistic;
+
 /// `ObjectVersions` selects which S3 objects will be used when summing the
 /// size of the buckets.
 #[cfg(feature = "s3")]
 mod object_versions;
 
+/// `SizeAndCount` accumulates the size and number of S3 objects together.
+#[cfg(feature = "s3")]
+mod size_and_count;
+
 pub use bucket::*;
+pub use bucket_size::*;
 pub use bucket_sizer::*;
 pub use client_config::*;
 pub use client_mode::*;
 pub use human_size::*;
+pub use output_config::*;
+pub use output_format::*;
 pub use region::*;
 pub use size_unit::*;
+pub use sort_key::*;
+pub use sort_order::*;
+pub use template::*;
+pub use throttle_counter::*;
+
+#[cfg(feature = "cloudwatch")]
+pub use metric_statistic::*;
 
 #[cfg(feature = "s3")]
 pub use object_versions::*;
 
-/// `BucketNames` is a convenience type used by both the `CloudWatch` and S3
-/// clients.
+#[cfg(feature = "s3")]
+pub use size_and_count::*;
+
+/// `BucketNames` is a convenience type used by the `CloudWatch` client.
+#[cfg(feature = "cloudwatch")]
 pub type BucketNames = Vec<String>;
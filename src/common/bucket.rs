@@ -1,7 +1,12 @@
 // Definition of a bucket
 #![forbid(unsafe_code)]
 #![deny(missing_docs)]
+use glob::{
+    Pattern,
+    PatternError,
+};
 use super::Region;
+use std::time::SystemTime;
 
 /// Convenience type for a list of storage types
 pub type StorageTypes = Vec<String>;
@@ -16,14 +21,134 @@ pub struct Bucket {
 
     /// The region the S3 bucket lives in.
     ///
-    /// This will currently only be used in S3 mode.
+    /// In `CloudWatch` mode this is the region that the metrics came from.
     pub region: Option<Region>,
 
     /// The storage types the bucket is using.
     ///
     /// This will currently only be used in CloudWatch mode.
     pub storage_types: Option<StorageTypes>,
+
+    /// Whether the bucket has versioning enabled, or has had it suspended.
+    ///
+    /// This is only looked up in S3 mode when it's needed, it is `None`
+    /// otherwise or if the lookup failed.
+    pub is_versioned: Option<bool>,
+
+    /// The time that the bucket was created.
+    ///
+    /// This is only known in S3 mode, where it comes from `ListBuckets`, it
+    /// is `None` otherwise.
+    pub created: Option<SystemTime>,
+}
+
+impl Bucket {
+    /// Returns the ARN of the bucket.
+    ///
+    /// The partition is taken from the bucket's `region`, buckets without a
+    /// known region are assumed to be in the standard `aws` partition.
+    pub fn arn(&self) -> String {
+        let partition = self.region
+            .as_ref()
+            .map_or("aws", Region::partition);
+
+        format!("arn:{}:s3:::{}", partition, self.name)
+    }
 }
 
 /// Convenience type for a list of `Bucket`.
 pub type Buckets = Vec<Bucket>;
+
+/// Returns `true` if the given bucket `name` contains `*` or `?` wildcards.
+pub fn is_wildcard(name: &str) -> bool {
+    name.contains(['*', '?'])
+}
+
+/// Returns the glob `Pattern` for a bucket `name` given on the command line.
+///
+/// Only `*` and `?` are treated as wildcards, every other character must
+/// match literally.
+pub fn bucket_pattern(name: &str) -> Result<Pattern, PatternError> {
+    let pattern: String = name.chars()
+        .map(|c| {
+            if matches!(c, '*' | '?') {
+                c.to_string()
+            }
+            else {
+                Pattern::escape(&c.to_string())
+            }
+        })
+        .collect();
+
+    Pattern::new(&pattern)
+}
+
+/// Returns `true` if the bucket `name` matches any of the `selected` bucket
+/// names, which may contain wildcards.
+///
+/// Every bucket matches when no bucket names were selected.
+pub fn is_selected(selected: &[String], name: &str) -> bool {
+    selected.is_empty() || selected.iter().any(|selected| {
+        bucket_pattern(selected).is_ok_and(|pattern| pattern.matches(name))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_arn() {
+        let tests = vec![
+            (None,                  "arn:aws:s3:::test-bucket"),
+            (Some("eu-west-1"),     "arn:aws:s3:::test-bucket"),
+            (Some("cn-north-1"),    "arn:aws-cn:s3:::test-bucket"),
+            (Some("us-gov-west-1"), "arn:aws-us-gov:s3:::test-bucket"),
+        ];
+
+        for test in tests {
+            let region   = test.0;
+            let expected = test.1;
+
+            let bucket = Bucket {
+                name:          "test-bucket".into(),
+                region:        region.map(|r| Region::new().set_region(r)),
+                storage_types: None,
+                is_versioned:  None,
+                created:       None,
+            };
+
+            assert_eq!(bucket.arn(), expected);
+        }
+    }
+
+    #[test]
+    fn test_is_selected() {
+        let tests = vec![
+            (vec![],                  "a-bucket",  true),
+            (vec!["a-bucket"],        "a-bucket",  true),
+            (vec!["a-bucket"],        "b-bucket",  false),
+            (vec!["prod-*"],          "prod-logs", true),
+            (vec!["prod-*"],          "dev-logs",  false),
+            (vec!["prod-?"],          "prod-1",    true),
+            (vec!["prod-?"],          "prod-10",   false),
+            (vec!["[ab]-bucket"],     "a-bucket",  false),
+            (vec!["dev-*", "prod-*"], "prod-logs", true),
+        ];
+
+        for test in tests {
+            let selected = test.0;
+            let name     = test.1;
+            let expected = test.2;
+
+            let selected: Vec<String> = selected.into_iter()
+                .map(String::from)
+                .collect();
+
+            let ret = is_selected(&selected, name);
+
+            assert_eq!(ret, expected, "{selected:?} {name}");
+        }
+    }
+}
@@ -0,0 +1,320 @@
+// BucketSize
+#![forbid(unsafe_code)]
+#![deny(missing_docs)]
+use std::collections::BTreeMap;
+use std::ops::AddAssign;
+use std::time::{
+    Duration,
+    SystemTime,
+};
+
+/// Sizes of the different kinds of object versions within a bucket.
+///
+/// This is only produced in S3 mode when a breakdown of the `All` object
+/// versions was requested.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct VersionsBreakdown {
+    /// Size of current object versions in bytes.
+    pub current: u64,
+
+    /// Size of in-progress multipart uploads in bytes.
+    pub multipart: u64,
+
+    /// Size of non-current object versions in bytes.
+    pub noncurrent: u64,
+}
+
+impl VersionsBreakdown {
+    /// Returns the sum of all of the components of the breakdown.
+    #[cfg(feature = "s3")]
+    pub fn total(&self) -> u64 {
+        self.current + self.multipart + self.noncurrent
+    }
+}
+
+impl AddAssign<&Self> for VersionsBreakdown {
+    fn add_assign(&mut self, other: &Self) {
+        self.current    += other.current;
+        self.multipart  += other.multipart;
+        self.noncurrent += other.noncurrent;
+    }
+}
+
+/// Sizes of the object versions and in-progress multipart uploads within a
+/// bucket.
+///
+/// This is only produced in S3 mode when sizing `All` object versions without
+/// a full breakdown.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct MultipartSplit {
+    /// Size of in-progress multipart uploads in bytes.
+    pub multipart: u64,
+
+    /// Size of all object versions, current and non-current, in bytes.
+    pub versions: u64,
+}
+
+impl AddAssign<&Self> for MultipartSplit {
+    fn add_assign(&mut self, other: &Self) {
+        self.multipart += other.multipart;
+        self.versions  += other.versions;
+    }
+}
+
+/// The raw `CloudWatch` datapoint that the size of a storage type was taken
+/// from.
+///
+/// This is only produced in `CloudWatch` mode when datapoints were requested,
+/// to help with debugging unexpected sizes.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MetricDatapoint {
+    /// The statistic that `value` is, such as `average`.
+    pub statistic: String,
+
+    /// Time that the datapoint was recorded, if known.
+    pub timestamp: Option<SystemTime>,
+
+    /// The unit of the `value`, if known.
+    pub unit: Option<String>,
+
+    /// The value of the `statistic` of the metric.
+    pub value: f64,
+}
+
+/// The size of a bucket as returned by a `BucketSizer`.
+///
+/// This will always have a size in `bytes`, other fields are only populated
+/// when the `BucketSizer` and the selected options support them.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BucketSize {
+    /// The size of the bucket in bytes.
+    pub bytes: u64,
+
+    /// The number of objects in the bucket.
+    ///
+    /// This will be `None` if counting wasn't requested or if the count isn't
+    /// known.
+    pub count: Option<u64>,
+
+    /// The latest `CloudWatch` datapoint for each storage type.
+    ///
+    /// This will only be used in `CloudWatch` mode when datapoints were
+    /// requested.
+    pub datapoints: Option<BTreeMap<String, MetricDatapoint>>,
+
+    /// Time of the oldest of the latest `CloudWatch` datapoints that the size
+    /// was taken from, showing how stale the size may be.
+    ///
+    /// This will only be used in `CloudWatch` mode, and will be `None` if no
+    /// datapoint had a timestamp.
+    pub datapoint_timestamp: Option<SystemTime>,
+
+    /// Whether the size is an estimate rather than an exact figure.
+    ///
+    /// Sizes obtained from `CloudWatch` metrics are estimates, sizes obtained
+    /// by listing every object in S3 are exact.
+    pub estimated: bool,
+
+    /// Age of the oldest in-progress multipart upload in the bucket.
+    ///
+    /// This will be `None` if ages weren't requested or if there are no
+    /// in-progress multipart uploads.
+    pub multipart_age: Option<Duration>,
+
+    /// Split of `bytes` between object versions and in-progress multipart
+    /// uploads.
+    ///
+    /// This will currently only be used in S3 mode when sizing `All` object
+    /// versions without a full breakdown in `versions`.
+    pub multipart_split: Option<MultipartSplit>,
+
+    /// Time that the newest current object in the bucket was last modified.
+    ///
+    /// This will currently only be used in S3 mode when sizing `Current`
+    /// objects, and will be `None` if the bucket is empty.
+    pub newest_object: Option<SystemTime>,
+
+    /// Breakdown of `bytes` by storage class.
+    ///
+    /// In `CloudWatch` mode the storage classes are the `CloudWatch` storage
+    /// types. In S3 mode this is only used when a breakdown of current
+    /// objects was requested, the storage classes are then those of the
+    /// objects.
+    pub storage_classes: Option<BTreeMap<String, u64>>,
+
+    /// Breakdown of `bytes` by object version.
+    ///
+    /// This will currently only be used in S3 mode.
+    pub versions: Option<VersionsBreakdown>,
+}
+
+/// Conversion from a plain number of bytes.
+impl From<u64> for BucketSize {
+    fn from(bytes: u64) -> Self {
+        Self {
+            bytes,
+            ..Default::default()
+        }
+    }
+}
+
+/// Allows `BucketSize`s to be summed for producing totals.
+///
+/// Unknown counts are skipped, the total count is only unknown if no counts
+/// are known. The total multipart upload age is the oldest of all ages, the
+/// newest object is the newest of all objects, the datapoint timestamp is the
+/// oldest of all timestamps, and the total is an estimate if any of its parts
+/// are. Datapoints describe a single bucket, so aren't totalled.
+impl AddAssign<&Self> for BucketSize {
+    fn add_assign(&mut self, other: &Self) {
+        self.bytes     += other.bytes;
+        self.estimated |= other.estimated;
+
+        if let Some(other_count) = other.count {
+            *self.count.get_or_insert(0) += other_count;
+        }
+
+        if let Some(other_age) = other.multipart_age {
+            let age = self.multipart_age.get_or_insert(other_age);
+            *age = (*age).max(other_age);
+        }
+
+        if let Some(other_split) = &other.multipart_split {
+            self.multipart_split
+                .get_or_insert_with(MultipartSplit::default)
+                .add_assign(other_split);
+        }
+
+        self.newest_object = self.newest_object.max(other.newest_object);
+
+        self.datapoint_timestamp = [
+            self.datapoint_timestamp,
+            other.datapoint_timestamp,
+        ].into_iter().flatten().min();
+
+        if let Some(other_classes) = &other.storage_classes {
+            let classes = self.storage_classes.get_or_insert_with(BTreeMap::new);
+
+            for (class, bytes) in other_classes {
+                *classes.entry(class.clone()).or_insert(0) += bytes;
+            }
+        }
+
+        if let Some(other_versions) = &other.versions {
+            self.versions
+                .get_or_insert_with(VersionsBreakdown::default)
+                .add_assign(other_versions);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_bucket_size_add_assign() {
+        let newest = SystemTime::UNIX_EPOCH + Duration::from_secs(60);
+
+        let sizes = vec![
+            BucketSize {
+                bytes:         6,
+                count:         Some(3),
+                multipart_age: Some(Duration::from_secs(60)),
+                newest_object: Some(SystemTime::UNIX_EPOCH),
+                versions:      Some(VersionsBreakdown {
+                    current:    1,
+                    multipart:  2,
+                    noncurrent: 3,
+                }),
+                ..Default::default()
+            },
+            BucketSize {
+                bytes:               10,
+                datapoint_timestamp: Some(newest),
+                datapoints:          Some(BTreeMap::from([
+                    ("StandardStorage".into(), MetricDatapoint {
+                        value: 6.0,
+                        ..Default::default()
+                    }),
+                ])),
+                estimated:           true,
+                storage_classes:     Some(BTreeMap::from([
+                    ("StandardIAStorage".into(), 4),
+                    ("StandardStorage".into(),   6),
+                ])),
+                ..Default::default()
+            },
+            BucketSize {
+                bytes:               100,
+                datapoint_timestamp: Some(SystemTime::UNIX_EPOCH),
+                multipart_split:     Some(MultipartSplit {
+                    multipart: 40,
+                    versions:  60,
+                }),
+                storage_classes:     Some(BTreeMap::from([
+                    ("StandardStorage".into(), 100),
+                ])),
+                ..Default::default()
+            },
+            BucketSize {
+                bytes:         60,
+                count:         Some(0),
+                multipart_age: Some(Duration::from_secs(120)),
+                newest_object: Some(newest),
+                versions:      Some(VersionsBreakdown {
+                    current:    10,
+                    multipart:  20,
+                    noncurrent: 30,
+                }),
+                ..Default::default()
+            },
+        ];
+
+        let mut total = BucketSize::default();
+
+        for size in &sizes {
+            total += size;
+        }
+
+        let expected = BucketSize {
+            bytes:               176,
+            count:               Some(3),
+            datapoint_timestamp: Some(SystemTime::UNIX_EPOCH),
+            datapoints:          None,
+            estimated:           true,
+            multipart_age:       Some(Duration::from_secs(120)),
+            multipart_split:     Some(MultipartSplit {
+                multipart: 40,
+                versions:  60,
+            }),
+            newest_object:       Some(newest),
+            storage_classes:     Some(BTreeMap::from([
+                ("StandardIAStorage".into(), 4),
+                ("StandardStorage".into(),   106),
+            ])),
+            versions:            Some(VersionsBreakdown {
+                current:    11,
+                multipart:  22,
+                noncurrent: 33,
+            }),
+        };
+
+        assert_eq!(total, expected);
+    }
+
+    #[test]
+    fn test_bucket_size_add_assign_unknown_count() {
+        let mut total = BucketSize::default();
+
+        total += &BucketSize::from(1);
+        total += &BucketSize::from(2);
+
+        assert_eq!(total.count, None);
+        assert_eq!(total.multipart_age, None);
+        assert_eq!(total.newest_object, None);
+        assert_eq!(total.datapoint_timestamp, None);
+        assert!(!total.estimated);
+    }
+}
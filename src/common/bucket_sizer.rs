@@ -3,11 +3,21 @@
 #![deny(missing_docs)]
 use anyhow::Result;
 use async_trait::async_trait;
+use futures::stream::{
+    self,
+    BoxStream,
+    StreamExt,
+    TryStreamExt,
+};
 use super::{
     Bucket,
     Buckets,
+    BucketSize,
 };
 
+/// Convenience type for a stream of `Bucket`s, yielded as they're discovered.
+pub type BucketStream<'a> = BoxStream<'a, Result<Bucket>>;
+
 /// `BucketSizer` represents the required methods to list S3 buckets and find
 /// their sizes.
 ///
@@ -17,6 +27,18 @@ pub trait BucketSizer {
     /// Returns a list of bucket names.
     async fn buckets(&self) -> Result<Buckets>;
 
-    /// Returns the size of the given `bucket` in bytes.
-    async fn bucket_size(&self, bucket: &Bucket) -> Result<u64>;
+    /// Returns a stream of buckets, yielding each bucket as it's discovered.
+    ///
+    /// By default every bucket is found with `buckets` before any are
+    /// yielded, clients that can discover buckets a few at a time should
+    /// override this so that the full list never needs to be held in memory.
+    fn bucket_stream(&self) -> BucketStream<'_> {
+        stream::once(self.buckets())
+            .map_ok(|buckets| stream::iter(buckets.into_iter().map(Ok)))
+            .try_flatten()
+            .boxed()
+    }
+
+    /// Returns the size of the given `bucket`.
+    async fn bucket_size(&self, bucket: &Bucket) -> Result<BucketSize>;
 }
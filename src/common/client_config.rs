@@ -1,9 +1,19 @@
 // ClientConfig
 #![forbid(unsafe_code)]
 #![deny(missing_docs)]
+use glob::Pattern;
 use super::{
     ClientMode,
     Region,
+    ThrottleCounter,
+};
+use std::path::PathBuf;
+use std::time::Duration;
+
+#[cfg(feature = "cloudwatch")]
+use super::{
+    MetricStatistic,
+    StorageTypes,
 };
 
 #[cfg(feature = "s3")]
@@ -12,22 +22,198 @@ use super::ObjectVersions;
 /// Client configuration.
 #[derive(Debug)]
 pub struct ClientConfig {
-    /// The bucket name that the client should report the size of.
+    /// Size buckets in every region, rather than only those in the client
+    /// `region`.
+    ///
+    /// This only has an effect when running in S3 mode and the field will only
+    /// be present when compiled with the `s3` feature.
+    #[cfg(feature = "s3")]
+    pub all_regions: bool,
+
+    /// Assume that all buckets are in the client `region`, skipping bucket
+    /// location discovery.
+    ///
+    /// This only has an effect when running in S3 mode and the field will only
+    /// be present when compiled with the `s3` feature.
+    #[cfg(feature = "s3")]
+    pub assume_region: bool,
+
+    /// The bucket names that the client should report the sizes of.
     ///
-    /// If this isn't given, all discovered S3 buckets will have their sizes
+    /// If this is empty, all discovered S3 buckets will have their sizes
     /// reported.
-    pub bucket_name: Option<String>,
+    pub bucket_names: Vec<String>,
+
+    /// Report the sizes of current objects, non-current objects and
+    /// in-progress multipart uploads separately.
+    ///
+    /// This only has an effect when running in S3 mode with `All` object
+    /// versions and the field will only be present when compiled with the
+    /// `s3` feature.
+    #[cfg(feature = "s3")]
+    pub breakdown_versions: bool,
+
+    /// The file that bucket sizes are cached in between runs, if any.
+    pub cache: Option<PathBuf>,
+
+    /// Cached bucket sizes younger than this are reused rather than sizing
+    /// the bucket again.
+    ///
+    /// If this isn't given, bucket sizes are only recorded in the `cache`.
+    pub cache_max_age: Option<Duration>,
+
+    /// The maximum time that sizing a single bucket may take.
+    ///
+    /// If this isn't given, sizing a bucket may take as long as it needs.
+    pub bucket_timeout: Option<Duration>,
+
+    /// The number of days of `CloudWatch` metrics to look back through for
+    /// the latest datapoint.
+    ///
+    /// This only has an effect when running in `CloudWatch` mode and the field
+    /// will only be present when compiled with the `cloudwatch` feature.
+    #[cfg(feature = "cloudwatch")]
+    pub cloudwatch_lookback_days: u32,
+
+    /// The statistic of each day's `CloudWatch` metrics that is used for
+    /// bucket sizes and object counts.
+    ///
+    /// This only has an effect when running in `CloudWatch` mode and the field
+    /// will only be present when compiled with the `cloudwatch` feature.
+    #[cfg(feature = "cloudwatch")]
+    pub cloudwatch_statistic: MetricStatistic,
+
+    /// The `CloudWatch` storage types that are summed for each bucket.
+    ///
+    /// If this isn't given, every storage type is summed.
+    ///
+    /// This only has an effect when running in `CloudWatch` mode and the field
+    /// will only be present when compiled with the `cloudwatch` feature.
+    #[cfg(feature = "cloudwatch")]
+    pub cloudwatch_storage_types: Option<StorageTypes>,
+
+    /// The maximum number of buckets that are sized at once.
+    ///
+    /// Buckets are always output in the order that they were discovered,
+    /// however many are sized at once.
+    pub concurrency: usize,
+
+    /// Ask for confirmation before sizing more than this many buckets.
+    ///
+    /// This only has an effect when running in S3 mode and the field will only
+    /// be present when compiled with the `s3` feature.
+    #[cfg(feature = "s3")]
+    pub confirm_threshold: Option<usize>,
+
+    /// Skip buckets that fail to be sized instead of failing entirely.
+    pub continue_on_error: bool,
+
+    /// Whether the number of objects in each bucket should be counted.
+    ///
+    /// In S3 mode objects are always counted as they're listed, so this only
+    /// has an effect in `CloudWatch` and Storage Lens modes.
+    pub count: bool,
+
+    /// Report the latest raw `CloudWatch` datapoint used for each storage
+    /// type.
+    ///
+    /// This only has an effect when running in `CloudWatch` mode and the field
+    /// will only be present when compiled with the `cloudwatch` feature.
+    #[cfg(feature = "cloudwatch")]
+    pub datapoints: bool,
+
+    /// Glob patterns matching the names of buckets that should be skipped.
+    ///
+    /// Matching is case-sensitive and skipped buckets aren't sized, so they
+    /// don't count towards the total.
+    pub exclude: Vec<Pattern>,
+
+    /// The external ID to pass when assuming the `role_arn`, if any.
+    pub external_id: Option<String>,
+
+    /// Fall back to sizing current objects when listing object versions is
+    /// unsupported, rather than failing.
+    ///
+    /// This is always done when using a custom `endpoint`, this enables it
+    /// for other S3 compatible storage.
+    ///
+    /// This only has an effect when running in S3 mode and the field will only
+    /// be present when compiled with the `s3` feature.
+    #[cfg(feature = "s3")]
+    pub fallback_on_unsupported: bool,
+
+    /// Fail once every other bucket has been output if any buckets failed to
+    /// be sized.
+    ///
+    /// This is only useful along with `continue_on_error`, which skips the
+    /// buckets that fail.
+    pub keep_going: bool,
+
+    /// The maximum number of idle HTTP connections that our AWS client keeps
+    /// open to each host for reuse.
+    ///
+    /// If this isn't given, the SDK's default connection pool is used.
+    pub max_connections: Option<usize>,
+
+    /// Maximum number of times that a throttled S3 listing request is
+    /// retried, on top of the retries made by the AWS SDK.
+    ///
+    /// This only has an effect when running in S3 mode and the field will only
+    /// be present when compiled with the `s3` feature.
+    #[cfg(feature = "s3")]
+    pub max_retries: u32,
+
+    /// Only report buckets that are missing a lifecycle rule expiring
+    /// non-current object versions.
+    ///
+    /// This only has an effect when running in S3 mode and the field will only
+    /// be present when compiled with the `s3` feature.
+    #[cfg(feature = "s3")]
+    pub missing_lifecycle: bool,
+
+    /// Find the age of the oldest in-progress multipart upload in each bucket.
+    ///
+    /// This only has an effect when running in S3 mode with `All` or
+    /// `Multipart` object versions and the field will only be present when
+    /// compiled with the `s3` feature.
+    #[cfg(feature = "s3")]
+    pub multipart_age: bool,
+
+    /// Only size in-progress multipart uploads initiated longer ago than this,
+    /// if given.
+    ///
+    /// This only has an effect when running in S3 mode with `All` or
+    /// `Multipart` object versions and the field will only be present when
+    /// compiled with the `s3` feature.
+    #[cfg(feature = "s3")]
+    pub multipart_older_than: Option<Duration>,
 
     /// The mode that `s3du` will run in.
     ///
     /// This selects which AWS client will be used.
     pub mode: ClientMode,
 
+    /// The profile from the AWS config files that our AWS client should use,
+    /// if any.
+    ///
+    /// If this isn't given, the SDK's usual profile selection applies.
+    pub profile: Option<String>,
+
+    /// The HTTP proxy that our AWS client should make requests through, if
+    /// any.
+    pub proxy: Option<String>,
+
     /// The region that our AWS client should be created in.
     ///
     /// This will affect bucket discovery.
     pub region: Region,
 
+    /// The ARN of an IAM role that our AWS client should assume, if any.
+    ///
+    /// The role is assumed using the credentials that would otherwise have
+    /// been used, and all requests are then made as that role.
+    pub role_arn: Option<String>,
+
     /// The S3 object versions that should be used when calculating the bucket
     /// size.
     ///
@@ -36,30 +222,202 @@ pub struct ClientConfig {
     #[cfg(feature = "s3")]
     pub object_versions: ObjectVersions,
 
+    /// List the top level prefixes of each bucket concurrently when sizing
+    /// current objects.
+    ///
+    /// This only has an effect when running in S3 mode and the field will only
+    /// be present when compiled with the `s3` feature.
+    #[cfg(feature = "s3")]
+    pub parallel_prefixes: bool,
+
+    /// Sum the object sizes in each page of a listing in parallel, using the
+    /// `rayon` thread pool.
+    ///
+    /// Disabling this sums sizes on a single thread, which is useful for
+    /// reproducible profiling and where spawning extra threads is
+    /// undesirable.
+    ///
+    /// This only has an effect when running in S3 mode and the field will only
+    /// be present when compiled with the `s3` feature.
+    #[cfg(feature = "s3")]
+    pub parallel_sum: bool,
+
+    /// Only size objects with keys beginning with this prefix, if given.
+    ///
+    /// This only has an effect when running in S3 mode and the field will only
+    /// be present when compiled with the `s3` feature.
+    #[cfg(feature = "s3")]
+    pub prefix: Option<String>,
+
+    /// Look up the locations of all buckets concurrently before filtering
+    /// them, rather than one at a time.
+    ///
+    /// This only has an effect when running in S3 mode and the field will only
+    /// be present when compiled with the `s3` feature.
+    #[cfg(feature = "s3")]
+    pub prewarm: bool,
+
+    /// Use path style addressing, such as `http://host/bucket`, rather than
+    /// virtual hosted style addressing for the custom `endpoint`.
+    ///
+    /// This only has an effect when running in S3 mode and the field will only
+    /// be present when compiled with the `s3` feature.
+    #[cfg(feature = "s3")]
+    pub path_style: bool,
+
+    /// Delay before the first retry of a throttled S3 listing request, which
+    /// doubles for each further retry.
+    ///
+    /// This only has an effect when running in S3 mode and the field will only
+    /// be present when compiled with the `s3` feature.
+    #[cfg(feature = "s3")]
+    pub retry_base_delay: Duration,
+
+    /// Agree to pay for listing objects in requester pays buckets.
+    ///
+    /// This only has an effect when running in S3 mode and the field will only
+    /// be present when compiled with the `s3` feature.
+    #[cfg(feature = "s3")]
+    pub request_payer: bool,
+
+    /// Report extra diagnostics, such as warnings about potential problems
+    /// found while sizing buckets.
+    ///
+    /// This only has an effect when running in S3 mode and the field will only
+    /// be present when compiled with the `s3` feature.
+    #[cfg(feature = "s3")]
+    pub stats: bool,
+
+    /// Only size objects in these storage classes, such as `GLACIER`.
+    ///
+    /// If this is empty, objects in every storage class are sized.
+    ///
+    /// This only has an effect when running in S3 mode and the field will only
+    /// be present when compiled with the `s3` feature.
+    #[cfg(feature = "s3")]
+    pub storage_class_filter: Vec<String>,
+
+    /// Break down the size of current objects in each bucket by the storage
+    /// class of each object.
+    ///
+    /// This only has an effect when running in S3 mode with `Current` object
+    /// versions and the field will only be present when compiled with the
+    /// `s3` feature.
+    #[cfg(feature = "s3")]
+    pub storage_classes: bool,
+
+    /// The S3 URI of the Storage Lens metrics export to read bucket sizes
+    /// from.
+    ///
+    /// This may be the export's `manifest.json`, or a prefix under which the
+    /// latest manifest will be found.
+    ///
+    /// This only has an effect when running in Storage Lens mode and the
+    /// field will only be present when compiled with the `s3` feature.
+    #[cfg(feature = "s3")]
+    pub storage_lens_export: Option<String>,
+
+    /// Counts the requests that AWS throttled, shared by every AWS client
+    /// created with this configuration.
+    pub throttle_counter: ThrottleCounter,
+
+    /// Check that buckets found in `CloudWatch` metrics still exist in S3,
+    /// skipping any that don't.
+    ///
+    /// This only has an effect when running in `CloudWatch` mode and the field
+    /// will only be present when compiled with both the `cloudwatch` and `s3`
+    /// features.
+    #[cfg(all(feature = "cloudwatch", feature = "s3"))]
+    pub verify_exists: bool,
+
+    /// Look up the versioning status of each bucket when sizing current
+    /// objects, warning about versioned buckets whose non-current versions
+    /// aren't counted.
+    ///
+    /// This costs an extra request per bucket, so is off by default.
+    ///
+    /// This only has an effect when running in S3 mode and the field will only
+    /// be present when compiled with the `s3` feature.
+    #[cfg(feature = "s3")]
+    pub warn_versioned: bool,
+
     /// The S3 Endpoint that we're going to connect to for bucket operations.
     ///
+    /// When this is set, operations that some S3 compatible storage doesn't
+    /// implement will be handled leniently.
+    ///
     /// This only has an effect when running in S3 mode and the field will only
     /// be present when compiled with the `s3` feature.
     #[cfg(feature = "s3")]
     pub endpoint: Option<String>,
 }
 
+#[cfg(any(feature = "cloudwatch", feature = "s3"))]
 impl Default for ClientConfig {
     /// Returns a default `ClientConfig`.
     ///
     /// If compiled with the `cloudwatch` feature, `CloudWatch` will be the
     /// default `ClientMode`, otherwise `S3` will be the default.
     ///
-    /// If compiled without the `s3` feature, the `endpoint` and
-    /// `object_versions` fields will be absent.
+    /// If compiled without the `s3` feature, the `all_regions`,
+    /// `assume_region`, `breakdown_versions`, `confirm_threshold`, `endpoint`,
+    /// `fallback_on_unsupported`, `max_retries`, `missing_lifecycle`,
+    /// `multipart_age`, `multipart_older_than`, `object_versions`,
+    /// `parallel_prefixes`, `parallel_sum`, `path_style`, `prefix`, `prewarm`,
+    /// `request_payer`, `retry_base_delay`, `stats`, `storage_class_filter`,
+    /// `storage_classes`, `storage_lens_export`, `verify_exists` and
+    /// `warn_versioned` fields will be absent.
+    /// The `cloudwatch_lookback_days`, `cloudwatch_statistic`,
+    /// `cloudwatch_storage_types`, `datapoints` and `verify_exists` fields will
+    /// be absent if compiled without the `cloudwatch` feature.
     ///
     /// ```rust
     /// ClientConfig {
-    ///     bucket_name:     None,
-    ///     endpoint:        None,
-    ///     mode:            ClientMode::CloudWatch,
-    ///     object_versions: ObjectVersions::Current,
-    ///     region:          Region::new(),
+    ///     all_regions:              false,
+    ///     assume_region:            false,
+    ///     breakdown_versions:       false,
+    ///     bucket_names:             Vec::new(),
+    ///     bucket_timeout:           None,
+    ///     cache:                    None,
+    ///     cache_max_age:            None,
+    ///     cloudwatch_lookback_days: 2,
+    ///     cloudwatch_statistic:     MetricStatistic::Average,
+    ///     cloudwatch_storage_types: None,
+    ///     concurrency:              8,
+    ///     confirm_threshold:        None,
+    ///     continue_on_error:        false,
+    ///     count:                    false,
+    ///     datapoints:               false,
+    ///     endpoint:                 None,
+    ///     exclude:                  Vec::new(),
+    ///     external_id:              None,
+    ///     fallback_on_unsupported:  false,
+    ///     keep_going:               false,
+    ///     max_connections:          None,
+    ///     max_retries:              3,
+    ///     missing_lifecycle:        false,
+    ///     mode:                     ClientMode::CloudWatch,
+    ///     multipart_age:            false,
+    ///     multipart_older_than:     None,
+    ///     object_versions:          ObjectVersions::Current,
+    ///     parallel_prefixes:        false,
+    ///     parallel_sum:             true,
+    ///     path_style:               false,
+    ///     prefix:                   None,
+    ///     prewarm:                  false,
+    ///     profile:                  None,
+    ///     proxy:                    None,
+    ///     region:                   Region::new(),
+    ///     request_payer:            false,
+    ///     retry_base_delay:         Duration::from_millis(100),
+    ///     role_arn:                 None,
+    ///     stats:                    false,
+    ///     storage_class_filter:     Vec::new(),
+    ///     storage_classes:          false,
+    ///     storage_lens_export:      None,
+    ///     throttle_counter:         ThrottleCounter::default(),
+    ///     verify_exists:            false,
+    ///     warn_versioned:           false,
     /// }
     /// ```
     fn default() -> Self {
@@ -77,13 +435,105 @@ impl Default for ClientConfig {
         Self {
             mode,
             region,
-            bucket_name: None,
+            bucket_names:      Vec::new(),
+            bucket_timeout:    None,
+            cache:             None,
+            cache_max_age:     None,
+            concurrency:       8,
+            continue_on_error: false,
+            count:             false,
+            exclude:           Vec::new(),
+            external_id:       None,
+            keep_going:        false,
+            max_connections:   None,
+            profile:           None,
+            proxy:             None,
+            role_arn:          None,
+            throttle_counter:  ThrottleCounter::default(),
+
+            #[cfg(feature = "cloudwatch")]
+            cloudwatch_lookback_days: 2,
+
+            #[cfg(feature = "cloudwatch")]
+            cloudwatch_statistic: MetricStatistic::Average,
+
+            #[cfg(feature = "cloudwatch")]
+            cloudwatch_storage_types: None,
+
+            #[cfg(feature = "cloudwatch")]
+            datapoints: false,
+
+            #[cfg(feature = "s3")]
+            all_regions: false,
+
+            #[cfg(feature = "s3")]
+            assume_region: false,
+
+            #[cfg(feature = "s3")]
+            breakdown_versions: false,
+
+            #[cfg(feature = "s3")]
+            confirm_threshold: None,
 
             #[cfg(feature = "s3")]
             endpoint: None,
 
+            #[cfg(feature = "s3")]
+            fallback_on_unsupported: false,
+
+            #[cfg(feature = "s3")]
+            max_retries: 3,
+
+            #[cfg(feature = "s3")]
+            missing_lifecycle: false,
+
+            #[cfg(feature = "s3")]
+            multipart_age: false,
+
+            #[cfg(feature = "s3")]
+            multipart_older_than: None,
+
             #[cfg(feature = "s3")]
             object_versions: ObjectVersions::Current,
+
+            #[cfg(feature = "s3")]
+            parallel_prefixes: false,
+
+            #[cfg(feature = "s3")]
+            parallel_sum: true,
+
+            #[cfg(feature = "s3")]
+            path_style: false,
+
+            #[cfg(feature = "s3")]
+            prefix: None,
+
+            #[cfg(feature = "s3")]
+            prewarm: false,
+
+            #[cfg(feature = "s3")]
+            request_payer: false,
+
+            #[cfg(feature = "s3")]
+            retry_base_delay: Duration::from_millis(100),
+
+            #[cfg(feature = "s3")]
+            stats: false,
+
+            #[cfg(feature = "s3")]
+            storage_class_filter: Vec::new(),
+
+            #[cfg(feature = "s3")]
+            storage_classes: false,
+
+            #[cfg(feature = "s3")]
+            storage_lens_export: None,
+
+            #[cfg(all(feature = "cloudwatch", feature = "s3"))]
+            verify_exists: false,
+
+            #[cfg(feature = "s3")]
+            warn_versioned: false,
         }
     }
 }
@@ -15,6 +15,26 @@ pub enum ClientMode {
     /// S3 mode is available when compiled with the `s3` feature.
     #[cfg(feature = "s3")]
     S3,
+
+    /// Storage Lens mode reads an S3 Storage Lens metrics export, so is only
+    /// available when compiled with the `s3` feature.
+    #[cfg(feature = "s3")]
+    StorageLens,
+}
+
+#[cfg(any(feature = "cloudwatch", feature = "s3"))]
+impl ClientMode {
+    /// Returns the name of the mode, as given on the command line.
+    pub fn name(&self) -> &'static str {
+        match self {
+            #[cfg(feature = "cloudwatch")]
+            Self::CloudWatch  => "cloudwatch",
+            #[cfg(feature = "s3")]
+            Self::S3          => "s3",
+            #[cfg(feature = "s3")]
+            Self::StorageLens => "storage-lens",
+        }
+    }
 }
 
 /// This is used to work out which mode we're in after parsing the CLI.
@@ -25,10 +45,12 @@ impl FromStr for ClientMode {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             #[cfg(feature = "cloudwatch")]
-            "cloudwatch" => Ok(Self::CloudWatch),
+            "cloudwatch"   => Ok(Self::CloudWatch),
+            #[cfg(feature = "s3")]
+            "s3"           => Ok(Self::S3),
             #[cfg(feature = "s3")]
-            "s3"         => Ok(Self::S3),
-            _            => Err("no match"),
+            "storage-lens" => Ok(Self::StorageLens),
+            _              => Err("no match"),
         }
     }
 }
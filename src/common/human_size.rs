@@ -5,6 +5,71 @@ use humansize::format_size;
 use super::SizeUnit;
 use tracing::debug;
 
+/// Returns `size` with each group of three digits separated by a comma, for
+/// example `123,456,789`.
+fn group_digits(size: u64) -> String {
+    let digits      = size.to_string();
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+
+        grouped.push(digit);
+    }
+
+    grouped
+}
+
+/// Parses a size given as a whole number of bytes with an optional unit, such
+/// as `1024`, `500MB`, `1GiB` or `4K`.
+///
+/// Units are case insensitive. As with `du(1)`, a single letter such as `K` or
+/// `G` and the `iB` units such as `KiB` are multiples of 1024, while the `B`
+/// units such as `KB` are multiples of 1000. This is the inverse of the
+/// `humansize` formatting, apart from fractional sizes.
+pub fn parse_human_size(s: &str) -> Result<u64, String> {
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+
+    let unit = unit.to_ascii_lowercase();
+
+    let (prefix, base): (&str, u64) = match unit.as_str() {
+        "" | "b" => ("", 1024),
+        unit     => {
+            if let Some(prefix) = unit.strip_suffix("ib") {
+                (prefix, 1024)
+            }
+            else if let Some(prefix) = unit.strip_suffix('b') {
+                (prefix, 1000)
+            }
+            else {
+                (unit, 1024)
+            }
+        },
+    };
+
+    let exponent = match prefix {
+        ""  => 0,
+        "k" => 1,
+        "m" => 2,
+        "g" => 3,
+        "t" => 4,
+        "p" => 5,
+        "e" => 6,
+        _   => return Err(format!("Unknown size unit: {s}")),
+    };
+
+    let number: u64 = number.parse()
+        .map_err(|_| format!("Could not parse size: {s}"))?;
+
+    let multiplier = base.pow(exponent);
+
+    number.checked_mul(multiplier)
+        .ok_or_else(|| format!("Size is too large: {s}"))
+}
+
 /// `HumanSize` trait.
 pub trait HumanSize {
     fn humansize(&self, unit: &SizeUnit) -> String;
@@ -19,10 +84,26 @@ impl HumanSize for u64 {
         // Unwrap should be fine here, usize cannot be negative, so file_size
         // shouldn't error.
         match unit {
-            SizeUnit::Bytes => self.to_string(),
+            SizeUnit::Bytes { group_digits: false } => self.to_string(),
+            SizeUnit::Bytes { group_digits: true }  => group_digits(*self),
             SizeUnit::Binary(unit) | SizeUnit::Decimal(unit) => {
                 format_size(*self, unit)
             },
+            // There's no space before the suffix, so that the output is still
+            // sortable by `sort -h`.
+            SizeUnit::Fixed { size, suffix, places, .. } => {
+                // Precision is only lost for sizes far beyond any bucket.
+                #[allow(clippy::cast_precision_loss)]
+                let value = *self as f64 / *size as f64;
+
+                format!("{value:.places$}{suffix}")
+            },
+            // Partial blocks are rounded up, as du(1) does.
+            SizeUnit::Blocks { size, suffix } => {
+                let blocks = self.div_ceil(*size);
+
+                format!("{}{}", blocks, suffix.as_deref().unwrap_or_default())
+            },
         }
     }
 }
@@ -52,4 +133,152 @@ mod tests {
             assert_eq!(ret, expected);
         }
     }
+
+    #[test]
+    fn test_humansize_precision() {
+        let tests = vec![
+            (1_600,     "binary",  0, "2KiB"),
+            (1_600,     "binary",  1, "1.6KiB"),
+            (1_600,     "binary",  2, "1.56KiB"),
+            (1_024,     "binary",  2, "1.00KiB"),
+            (1_234_567, "decimal", 0, "1MB"),
+            (1_234_567, "decimal", 1, "1.2MB"),
+            (1_234_567, "decimal", 2, "1.23MB"),
+            (1_600,     "bytes",   2, "1600"),
+        ];
+
+        for test in tests {
+            let size: u64 = test.0;
+            let places    = test.2;
+            let expected  = test.3;
+
+            let unit = SizeUnit::from_str(test.1)
+                .unwrap()
+                .precision(places);
+
+            let ret = size.humansize(&unit);
+
+            assert_eq!(ret, expected);
+        }
+    }
+
+    #[test]
+    fn test_humansize_fixed() {
+        let tests = vec![
+            (0,                 "gib", None,    "0.00GiB"),
+            (1_073_741_824,     "gib", None,    "1.00GiB"),
+            (1_610_612_736,     "gib", None,    "1.50GiB"),
+            (1_024,             "gib", None,    "0.00GiB"),
+            (5_497_558_138_880, "gib", None,    "5120.00GiB"),
+            (1_536,             "kib", None,    "1.50KiB"),
+            (3_145_728,         "mib", Some(0), "3MiB"),
+            (1_099_511_627_776, "tib", Some(3), "1.000TiB"),
+            (1_500,             "kb",  None,    "1.50kB"),
+            (1_234_567,         "mb",  Some(1), "1.2MB"),
+            (1_000_000_000,     "gb",  None,    "1.00GB"),
+            (2_500_000_000_000, "tb",  None,    "2.50TB"),
+        ];
+
+        for test in tests {
+            let size: u64 = test.0;
+            let places    = test.2;
+            let expected  = test.3;
+
+            let unit = SizeUnit::from_str(test.1).unwrap();
+
+            let unit = match places {
+                Some(places) => unit.precision(places),
+                None         => unit,
+            };
+
+            let ret = size.humansize(&unit);
+
+            assert_eq!(ret, expected);
+        }
+    }
+
+    #[test]
+    fn test_humansize_blocks() {
+        let tests = vec![
+            (0,     1,    None,      "0"),
+            (1,     1,    None,      "1"),
+            (1,     1024, None,      "1"),
+            (1024,  1024, None,      "1"),
+            (1025,  1024, None,      "2"),
+            (3_000, 1000, Some("K"), "3K"),
+        ];
+
+        for test in tests {
+            let size: u64 = test.0;
+            let expected  = test.3;
+
+            let unit = SizeUnit::Blocks {
+                size:   test.1,
+                suffix: test.2.map(ToOwned::to_owned),
+            };
+
+            let ret = size.humansize(&unit);
+
+            assert_eq!(ret, expected);
+        }
+    }
+
+    #[test]
+    fn test_parse_human_size() {
+        let tests = vec![
+            ("0",       Ok(0)),
+            ("1024",    Ok(1_024)),
+            ("1B",      Ok(1)),
+            ("4K",      Ok(4_096)),
+            ("4k",      Ok(4_096)),
+            ("1KiB",    Ok(1_024)),
+            ("1KB",     Ok(1_000)),
+            ("500MB",   Ok(500_000_000)),
+            ("1GiB",    Ok(1_073_741_824)),
+            ("1G",      Ok(1_073_741_824)),
+            ("2TB",     Ok(2_000_000_000_000)),
+            ("1E",      Ok(1_152_921_504_606_846_976)),
+            ("",        Err("Could not parse size: ")),
+            ("K",       Err("Could not parse size: K")),
+            ("1.5GiB",  Err("Unknown size unit: 1.5GiB")),
+            ("1X",      Err("Unknown size unit: 1X")),
+            ("-1",      Err("Unknown size unit: -1")),
+            ("16E",     Err("Size is too large: 16E")),
+        ];
+
+        for test in tests {
+            let input    = test.0;
+            let expected = test.1.map_err(ToOwned::to_owned);
+
+            let ret = parse_human_size(input);
+
+            assert_eq!(ret, expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_humansize_group_digits() {
+        let tests = vec![
+            (0,           "0"),
+            (999,         "999"),
+            (1_000,       "1,000"),
+            (123_456,     "123,456"),
+            (1_234_567,   "1,234,567"),
+            (123_456_789, "123,456,789"),
+            (u64::MAX,    "18,446,744,073,709,551,615"),
+        ];
+
+        let unit = SizeUnit::from_str("bytes")
+            .unwrap()
+            .group_digits(true);
+
+        for test in tests {
+            let size: u64 = test.0;
+            let expected  = test.1;
+
+            let ret = size.humansize(&unit);
+
+            assert_eq!(ret, expected);
+        }
+    }
 }
@@ -0,0 +1,120 @@
+// MetricStatistic
+#![forbid(unsafe_code)]
+#![deny(missing_docs)]
+use anyhow::Result;
+use aws_sdk_cloudwatch::types::{
+    Datapoint,
+    Statistic,
+};
+use std::fmt;
+use std::str::FromStr;
+
+/// `MetricStatistic` represents which statistic of each daily period we're
+/// going to request when operating in `CloudWatch` mode.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum MetricStatistic {
+    /// The average value over the period.
+    #[default]
+    Average,
+
+    /// The highest value over the period.
+    Maximum,
+
+    /// The lowest value over the period.
+    Minimum,
+}
+
+impl MetricStatistic {
+    /// Returns the `CloudWatch` `Statistic` to request.
+    pub fn statistic(self) -> Statistic {
+        match self {
+            Self::Average => Statistic::Average,
+            Self::Maximum => Statistic::Maximum,
+            Self::Minimum => Statistic::Minimum,
+        }
+    }
+
+    /// Returns the value of this statistic from the given `datapoint`, if
+    /// present.
+    pub fn value(self, datapoint: &Datapoint) -> Option<f64> {
+        match self {
+            Self::Average => datapoint.average(),
+            Self::Maximum => datapoint.maximum(),
+            Self::Minimum => datapoint.minimum(),
+        }
+    }
+}
+
+/// This converts from the string argument we receive from the command line to
+/// our enum type.
+impl FromStr for MetricStatistic {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "average" => Ok(Self::Average),
+            "maximum" => Ok(Self::Maximum),
+            "minimum" => Ok(Self::Minimum),
+            _         => Err("no match"),
+        }
+    }
+}
+
+/// This converts back to the string argument used on the command line, so
+/// that the statistic can be shown in output.
+impl fmt::Display for MetricStatistic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let statistic = match self {
+            Self::Average => "average",
+            Self::Maximum => "maximum",
+            Self::Minimum => "minimum",
+        };
+
+        write!(f, "{statistic}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_display_round_trip() {
+        let tests = vec![
+            MetricStatistic::Average,
+            MetricStatistic::Maximum,
+            MetricStatistic::Minimum,
+        ];
+
+        for statistic in tests {
+            let ret = MetricStatistic::from_str(&statistic.to_string());
+
+            assert_eq!(ret, Ok(statistic));
+        }
+    }
+
+    #[test]
+    fn test_value() {
+        let datapoint = Datapoint::builder()
+            .average(2.0)
+            .maximum(3.0)
+            .minimum(1.0)
+            .build();
+
+        let tests = vec![
+            (MetricStatistic::Average, Some(2.0)),
+            (MetricStatistic::Maximum, Some(3.0)),
+            (MetricStatistic::Minimum, Some(1.0)),
+        ];
+
+        for test in tests {
+            let statistic = test.0;
+            let expected  = test.1;
+
+            let ret = statistic.value(&datapoint);
+
+            assert_eq!(ret, expected, "{statistic}");
+        }
+    }
+}
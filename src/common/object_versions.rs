@@ -2,11 +2,12 @@
 #![forbid(unsafe_code)]
 #![deny(missing_docs)]
 use anyhow::Result;
+use std::fmt;
 use std::str::FromStr;
 
 /// `ObjectVersions` represents which objects we're going to sum when
 /// operating in S3 mode.
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum ObjectVersions {
     /// Sum size of all object versions (both `Current` and `NonCurrent`)
     All,
@@ -14,6 +15,9 @@ pub enum ObjectVersions {
     /// Sum only size of current objects
     Current,
 
+    /// Count only delete markers, which have no size
+    DeleteMarkers,
+
     /// Sum only size of in-progress multipart uploads
     Multipart,
 
@@ -28,11 +32,51 @@ impl FromStr for ObjectVersions {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
-            "all"         => Ok(Self::All),
-            "current"     => Ok(Self::Current),
-            "multipart"   => Ok(Self::Multipart),
-            "non-current" => Ok(Self::NonCurrent),
-            _             => Err("no match"),
+            "all"            => Ok(Self::All),
+            "current"        => Ok(Self::Current),
+            "delete-markers" => Ok(Self::DeleteMarkers),
+            "multipart"      => Ok(Self::Multipart),
+            "non-current"    => Ok(Self::NonCurrent),
+            _                => Err("no match"),
+        }
+    }
+}
+
+/// This converts back to the string argument used on the command line, so
+/// that the mode can be shown in output.
+impl fmt::Display for ObjectVersions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let versions = match self {
+            Self::All           => "all",
+            Self::Current       => "current",
+            Self::DeleteMarkers => "delete-markers",
+            Self::Multipart     => "multipart",
+            Self::NonCurrent    => "non-current",
+        };
+
+        write!(f, "{versions}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_display_round_trip() {
+        let tests = vec![
+            ObjectVersions::All,
+            ObjectVersions::Current,
+            ObjectVersions::DeleteMarkers,
+            ObjectVersions::Multipart,
+            ObjectVersions::NonCurrent,
+        ];
+
+        for versions in tests {
+            let ret = ObjectVersions::from_str(&versions.to_string());
+
+            assert_eq!(ret, Ok(versions));
         }
     }
 }
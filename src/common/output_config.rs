@@ -0,0 +1,168 @@
+// OutputConfig
+#![forbid(unsafe_code)]
+#![deny(missing_docs)]
+use super::{
+    OutputFormat,
+    SizeUnit,
+    SortKey,
+    SortOrder,
+    Template,
+};
+
+/// Output configuration.
+#[derive(Debug)]
+pub struct OutputConfig {
+    /// The ID of the AWS account that the buckets belong to, if known.
+    ///
+    /// This only has an effect on the `Json` output format.
+    pub account_id: Option<String>,
+
+    /// Output the size of each storage class in each bucket on its own
+    /// indented line.
+    ///
+    /// This only has an effect on the `Plain` output format.
+    pub breakdown: bool,
+
+    /// Output the total size of the buckets in each region.
+    ///
+    /// This only has an effect on the `Json` output format.
+    pub by_region: bool,
+
+    /// Output the total size of each storage class.
+    ///
+    /// This only has an effect on the `Json` output format.
+    pub by_storage_class: bool,
+
+    /// Output the number of objects in each bucket.
+    pub count: bool,
+
+    /// Output the time of the `CloudWatch` datapoints that each bucket size
+    /// was taken from.
+    pub datapoint_timestamp: bool,
+
+    /// The format that bucket sizes will be output in.
+    pub format: OutputFormat,
+
+    /// The mode that bucket sizes were found in.
+    ///
+    /// This only has an effect on the `Json` output format.
+    pub mode: Option<String>,
+
+    /// Output the age of the oldest in-progress multipart upload in each
+    /// bucket.
+    pub multipart_age: bool,
+
+    /// The S3 object versions that were sized, if sizes come from S3.
+    ///
+    /// This only has an effect on the `Json` output format.
+    pub object_versions: Option<String>,
+
+    /// The direction that buckets are sorted in.
+    ///
+    /// If this isn't given, the default order of the `sort` key is used.
+    pub order: Option<SortOrder>,
+
+    /// The region that buckets were sized in.
+    ///
+    /// This only has an effect on the `Json` output format.
+    pub region: Option<String>,
+
+    /// What buckets are sorted by before being output, if anything.
+    ///
+    /// Buckets are output in the order they're sized when this isn't given.
+    pub sort: Option<SortKey>,
+
+    /// Output the size of each storage class in each bucket.
+    ///
+    /// This only has an effect on the `Json` output format.
+    pub storage_classes: bool,
+
+    /// Only output the total size of all buckets, in the style of `du -s`.
+    pub summarize: bool,
+
+    /// The template used for each bucket.
+    ///
+    /// This is required by, and only has an effect on, the `Template` output
+    /// format.
+    pub template: Option<Template>,
+
+    /// The template used for the total of all buckets, if any.
+    ///
+    /// This only has an effect on the `Template` output format, where the
+    /// total is only output if this is given.
+    pub template_total: Option<Template>,
+
+    /// Only output buckets of at least this many bytes, if given.
+    ///
+    /// The total still includes every bucket.
+    pub threshold: Option<u64>,
+
+    /// Only output this many of the largest buckets, if given.
+    ///
+    /// The total still includes every bucket.
+    pub top: Option<usize>,
+
+    /// Output a final line with the total size of all buckets.
+    ///
+    /// This only has an effect on the `Markdown` and `Plain` output formats,
+    /// the `Json` format always includes the total.
+    pub total: bool,
+
+    /// The unit that bucket sizes will be displayed in.
+    pub unit: SizeUnit,
+}
+
+impl Default for OutputConfig {
+    /// Returns a default `OutputConfig`.
+    ///
+    /// ```rust
+    /// OutputConfig {
+    ///     account_id:          None,
+    ///     breakdown:           false,
+    ///     by_region:           false,
+    ///     by_storage_class:    false,
+    ///     count:               false,
+    ///     datapoint_timestamp: false,
+    ///     format:              OutputFormat::Plain,
+    ///     mode:                None,
+    ///     multipart_age:       false,
+    ///     object_versions:     None,
+    ///     order:               None,
+    ///     region:              None,
+    ///     sort:                None,
+    ///     storage_classes:     false,
+    ///     summarize:           false,
+    ///     template:            None,
+    ///     template_total:      None,
+    ///     threshold:           None,
+    ///     top:                 None,
+    ///     total:               true,
+    ///     unit:                SizeUnit::Binary(..),
+    /// }
+    /// ```
+    fn default() -> Self {
+        Self {
+            account_id:          None,
+            breakdown:           false,
+            by_region:           false,
+            by_storage_class:    false,
+            count:               false,
+            datapoint_timestamp: false,
+            format:              OutputFormat::Plain,
+            mode:                None,
+            multipart_age:       false,
+            object_versions:     None,
+            order:               None,
+            region:              None,
+            sort:                None,
+            storage_classes:     false,
+            summarize:           false,
+            template:            None,
+            template_total:      None,
+            threshold:           None,
+            top:                 None,
+            total:               true,
+            unit:                SizeUnit::default(),
+        }
+    }
+}
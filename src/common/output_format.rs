@@ -0,0 +1,42 @@
+// OutputFormat
+#![forbid(unsafe_code)]
+#![deny(missing_docs)]
+use anyhow::Result;
+use std::str::FromStr;
+
+/// `OutputFormat` represents how the bucket sizes will be written out.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum OutputFormat {
+    /// A single JSON document containing all buckets and the total.
+    Json,
+
+    /// A Markdown table, for pasting into issues and wikis.
+    Markdown,
+
+    /// Tab separated output in the style of `du(1)`.
+    Plain,
+
+    /// The Prometheus text exposition format, for the `node_exporter`
+    /// textfile collector.
+    Prometheus,
+
+    /// Output formatted with user supplied templates.
+    Template,
+}
+
+/// This converts from the string argument we receive from the command line to
+/// our enum type.
+impl FromStr for OutputFormat {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json"       => Ok(Self::Json),
+            "markdown"   => Ok(Self::Markdown),
+            "plain"      => Ok(Self::Plain),
+            "prometheus" => Ok(Self::Prometheus),
+            "template"   => Ok(Self::Template),
+            _            => Err("no match"),
+        }
+    }
+}
@@ -39,6 +39,23 @@ impl Region {
         }
     }
 
+    // Returns the AWS partition that the region is in, as used in ARNs.
+    // Regions that we don't recognise are assumed to be in the standard
+    // partition.
+    pub fn partition(&self) -> &'static str {
+        let name = self.name();
+
+        if name.starts_with("cn-") {
+            "aws-cn"
+        }
+        else if name.starts_with("us-gov-") {
+            "aws-us-gov"
+        }
+        else {
+            "aws"
+        }
+    }
+
     pub fn set_region(mut self, region: &str) -> Self {
         debug!("Region set to: {:?}", region);
 
@@ -52,7 +69,7 @@ impl ProvideRegion for Region {
     // Takes our region string and returns a proper AWS Region, this should
     // allow us to pass our Region into AWS SDK functions expecting an AWS
     // Region.
-    fn region(&self) -> future::ProvideRegion {
+    fn region(&self) -> future::ProvideRegion<'_> {
         future::ProvideRegion::ready(self.region.clone())
     }
 }
@@ -61,7 +78,35 @@ impl ProvideRegion for &Region {
     // Takes our region string and returns a proper AWS Region, this should
     // allow us to pass our Region into AWS SDK functions expecting an AWS
     // Region.
-    fn region(&self) -> future::ProvideRegion {
+    fn region(&self) -> future::ProvideRegion<'_> {
         future::ProvideRegion::ready(self.region.clone())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_partition() {
+        let tests = vec![
+            ("eu-west-1",      "aws"),
+            ("us-east-1",      "aws"),
+            ("cn-north-1",     "aws-cn"),
+            ("cn-northwest-1", "aws-cn"),
+            ("us-gov-east-1",  "aws-us-gov"),
+            ("us-gov-west-1",  "aws-us-gov"),
+            ("custom",         "aws"),
+        ];
+
+        for test in tests {
+            let region   = test.0;
+            let expected = test.1;
+
+            let region = Region::new().set_region(region);
+
+            assert_eq!(region.partition(), expected);
+        }
+    }
+}
@@ -0,0 +1,141 @@
+// SizeAndCount
+#![forbid(unsafe_code)]
+#![deny(missing_docs)]
+use std::iter::Sum;
+use std::ops::Add;
+
+/// The total size of a number of objects, along with how many objects there
+/// were.
+///
+/// This allows sizes and counts to be accumulated together in a single pass,
+/// including in parallel with `rayon`, where each thread produces a partial
+/// `SizeAndCount` that is then combined with the others.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct SizeAndCount {
+    /// Total size of the objects in bytes.
+    pub bytes: u64,
+
+    /// Number of objects.
+    pub count: u64,
+}
+
+impl SizeAndCount {
+    /// Returns the sum of `self` and `other`, or `None` if either the size or
+    /// the count would overflow.
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        let size_and_count = Self {
+            bytes: self.bytes.checked_add(other.bytes)?,
+            count: self.count.checked_add(other.count)?,
+        };
+
+        Some(size_and_count)
+    }
+}
+
+/// Conversion from the size of a single object.
+impl From<u64> for SizeAndCount {
+    fn from(bytes: u64) -> Self {
+        Self {
+            bytes,
+            count: 1,
+        }
+    }
+}
+
+/// As with integer addition, this will panic on overflow in debug builds.
+/// Use `checked_add` when summing untrusted sizes.
+impl Add for SizeAndCount {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            bytes: self.bytes + other.bytes,
+            count: self.count + other.count,
+        }
+    }
+}
+
+/// Allows `par_iter().map(...).sum()` to produce a `SizeAndCount`.
+///
+/// `rayon` sums each thread's items and then sums the partial results, so
+/// both the items and the partial sums need to be summable.
+impl Sum for SizeAndCount {
+    fn sum<I>(iter: I) -> Self
+    where
+        I: Iterator<Item = Self>,
+    {
+        iter.fold(Self::default(), Add::add)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rayon::prelude::*;
+
+    // A large synthetic listing, with object sizes from a simple linear
+    // congruential generator so that the test is deterministic.
+    fn synthetic_sizes(count: usize) -> Vec<u64> {
+        let mut state: u64 = 0x5eed;
+
+        (0..count)
+            .map(|_| {
+                state = state
+                    .wrapping_mul(6_364_136_223_846_793_005)
+                    .wrapping_add(1_442_695_040_888_963_407);
+
+                // Keep sizes below 5TiB, the largest S3 object size.
+                state % (5 * 1024 * 1024 * 1024 * 1024)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_checked_add() {
+        let tests = vec![
+            (
+                SizeAndCount { bytes: 1, count: 1 },
+                SizeAndCount { bytes: 2, count: 3 },
+                Some(SizeAndCount { bytes: 3, count: 4 }),
+            ),
+            (
+                SizeAndCount { bytes: u64::MAX, count: 1 },
+                SizeAndCount { bytes: 1, count: 1 },
+                None,
+            ),
+            (
+                SizeAndCount { bytes: 1, count: u64::MAX },
+                SizeAndCount { bytes: 1, count: 1 },
+                None,
+            ),
+        ];
+
+        for test in tests {
+            let a        = test.0;
+            let b        = test.1;
+            let expected = test.2;
+
+            assert_eq!(a.checked_add(b), expected);
+        }
+    }
+
+    #[test]
+    fn test_par_iter_sum() {
+        let sizes = synthetic_sizes(250_000);
+
+        let mut expected = SizeAndCount::default();
+
+        for size in &sizes {
+            expected.bytes += size;
+            expected.count += 1;
+        }
+
+        let ret: SizeAndCount = sizes.par_iter()
+            .map(|size| SizeAndCount::from(*size))
+            .sum();
+
+        assert_eq!(ret, expected);
+        assert_eq!(ret.count, 250_000);
+    }
+}
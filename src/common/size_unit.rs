@@ -9,19 +9,120 @@ use humansize::{
 };
 use std::str::FromStr;
 
+/// Fixed units that bucket sizes may be shown in, as their name given on the
+/// command line, their size in bytes and the suffix shown after each size.
+const FIXED_UNITS: &[(&str, u64, &str)] = &[
+    ("kib", 1 << 10, "KiB"),
+    ("mib", 1 << 20, "MiB"),
+    ("gib", 1 << 30, "GiB"),
+    ("tib", 1 << 40, "TiB"),
+    ("kb",  1_000,             "kB"),
+    ("mb",  1_000_000,         "MB"),
+    ("gb",  1_000_000_000,     "GB"),
+    ("tb",  1_000_000_000_000, "TB"),
+];
+
+/// The number of decimal places shown for fixed units, unless a precision is
+/// given.
+const DEFAULT_FIXED_PLACES: usize = 2;
+
 /// `SizeUnit` represents how we want the bucket sizes to be displayed.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum SizeUnit {
     /// Represent bucket sizes as human readable using SI units (multiples of
     /// 1024).
     Binary(FormatSizeOptions),
 
-    /// Represent bucket sizes as the number of bytes.
-    Bytes,
+    /// Represent bucket sizes as the number of blocks of `size` bytes,
+    /// rounded up, in the style of `du -B`.
+    Blocks {
+        /// Size of each block in bytes.
+        size: u64,
+
+        /// Suffix following each number of blocks, if any.
+        suffix: Option<String>,
+    },
+
+    /// Represent bucket sizes as the number of bytes, optionally with the
+    /// digits grouped in thousands.
+    Bytes {
+        /// Separate each group of three digits with a comma.
+        group_digits: bool,
+    },
 
     /// Represent bucket sizes as human readable using non-SI units (multiples
     /// of 1000).
     Decimal(FormatSizeOptions),
+
+    /// Represent bucket sizes in a single fixed unit, such as GiB, with a
+    /// fixed number of decimal places so that every size is comparable.
+    Fixed {
+        /// Name of the unit, as given on the command line, such as `gib`.
+        name: &'static str,
+
+        /// Size of the unit in bytes.
+        size: u64,
+
+        /// Suffix following each size, such as `GiB`.
+        suffix: &'static str,
+
+        /// Number of decimal places shown.
+        places: usize,
+    },
+}
+
+impl SizeUnit {
+    /// Return the name of the `SizeUnit`, as given on the command line.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Binary(_)          => "binary",
+            Self::Blocks { .. }      => "blocks",
+            Self::Bytes { .. }       => "bytes",
+            Self::Decimal(_)         => "decimal",
+            Self::Fixed { name, .. } => name,
+        }
+    }
+
+    /// Return the `SizeUnit` with grouping of digits enabled or disabled.
+    ///
+    /// This only has an effect on `Bytes`, the other units never display
+    /// enough digits to need grouping.
+    pub fn group_digits(self, group_digits: bool) -> Self {
+        match self {
+            Self::Bytes { .. } => Self::Bytes { group_digits },
+            unit               => unit,
+        }
+    }
+
+    /// Return the `SizeUnit` showing exactly `places` decimal places.
+    ///
+    /// This only has an effect on `Binary`, `Decimal` and `Fixed`, `Bytes`
+    /// never have decimal places. Without this, `Binary` and `Decimal` show up
+    /// to 2 decimal places, with any trailing zeroes removed, while `Fixed`
+    /// always shows 2 decimal places.
+    pub fn precision(self, places: usize) -> Self {
+        let precision = |options: FormatSizeOptions| {
+            options
+                .decimal_places(places)
+                .decimal_zeroes(places)
+        };
+
+        match self {
+            Self::Binary(options)  => Self::Binary(precision(options)),
+            Self::Decimal(options) => Self::Decimal(precision(options)),
+            Self::Fixed { name, size, suffix, .. } => {
+                Self::Fixed { name, size, suffix, places }
+            },
+            unit                   => unit,
+        }
+    }
+}
+
+/// The default `SizeUnit` is `Binary`.
+impl Default for SizeUnit {
+    fn default() -> Self {
+        Self::Binary(BINARY.space_after_value(false))
+    }
 }
 
 /// This converts from the string arguments we receive on the command line to
@@ -34,9 +135,21 @@ impl FromStr for SizeUnit {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "binary"  => Ok(Self::Binary(BINARY.space_after_value(false))),
-            "bytes"   => Ok(Self::Bytes),
+            "bytes"   => Ok(Self::Bytes { group_digits: false }),
             "decimal" => Ok(Self::Decimal(DECIMAL.space_after_value(false))),
-            _         => Err("no match"),
+            _         => {
+                FIXED_UNITS.iter()
+                    .find(|(name, _, _)| *name == s)
+                    .map(|&(name, size, suffix)| {
+                        Self::Fixed {
+                            name,
+                            size,
+                            suffix,
+                            places: DEFAULT_FIXED_PLACES,
+                        }
+                    })
+                    .ok_or("no match")
+            },
         }
     }
 }
@@ -0,0 +1,42 @@
+// SortKey
+#![forbid(unsafe_code)]
+#![deny(missing_docs)]
+use anyhow::Result;
+use std::str::FromStr;
+use super::SortOrder;
+
+/// `SortKey` represents what buckets will be sorted by before being output.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SortKey {
+    /// Sort buckets by their name.
+    Name,
+
+    /// Sort buckets by their size in bytes.
+    Size,
+}
+
+impl SortKey {
+    /// Returns the `SortOrder` used when none is given.
+    ///
+    /// Names are sorted alphabetically, while sizes are sorted biggest first.
+    pub fn default_order(self) -> SortOrder {
+        match self {
+            Self::Name => SortOrder::Ascending,
+            Self::Size => SortOrder::Descending,
+        }
+    }
+}
+
+/// This converts from the string argument we receive from the command line to
+/// our enum type.
+impl FromStr for SortKey {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "name" => Ok(Self::Name),
+            "size" => Ok(Self::Size),
+            _      => Err("no match"),
+        }
+    }
+}
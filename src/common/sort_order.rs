@@ -0,0 +1,29 @@
+// SortOrder
+#![forbid(unsafe_code)]
+#![deny(missing_docs)]
+use anyhow::Result;
+use std::str::FromStr;
+
+/// `SortOrder` represents the direction that buckets will be sorted in.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SortOrder {
+    /// Smallest, or alphabetically first, buckets first.
+    Ascending,
+
+    /// Biggest, or alphabetically last, buckets first.
+    Descending,
+}
+
+/// This converts from the string argument we receive from the command line to
+/// our enum type.
+impl FromStr for SortOrder {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "asc"  => Ok(Self::Ascending),
+            "desc" => Ok(Self::Descending),
+            _      => Err("no match"),
+        }
+    }
+}
@@ -0,0 +1,231 @@
+// Template
+#![forbid(unsafe_code)]
+#![deny(missing_docs)]
+use std::fmt;
+
+/// A value that can be substituted into a `Template`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Placeholder {
+    /// The bucket name.
+    Bucket,
+
+    /// The size in bytes.
+    Bytes,
+
+    /// The number of objects, or `-` if unknown.
+    Count,
+
+    /// The size in the selected unit.
+    Human,
+
+    /// The bucket region, or `-` if unknown.
+    Region,
+
+    /// The total size of all buckets in the selected unit.
+    Total,
+}
+
+impl Placeholder {
+    /// Placeholders that are valid in a per bucket template.
+    pub const BUCKET: &'static [Self] = &[
+        Self::Bucket,
+        Self::Bytes,
+        Self::Count,
+        Self::Human,
+        Self::Region,
+    ];
+
+    /// Placeholders that are valid in a total template.
+    pub const TOTAL: &'static [Self] = &[
+        Self::Bytes,
+        Self::Count,
+        Self::Human,
+        Self::Total,
+    ];
+
+    /// Returns the `Placeholder` with the given `name`, if there is one.
+    fn from_name(name: &str) -> Option<Self> {
+        let placeholder = match name {
+            "bucket" => Self::Bucket,
+            "bytes"  => Self::Bytes,
+            "count"  => Self::Count,
+            "human"  => Self::Human,
+            "region" => Self::Region,
+            "total"  => Self::Total,
+            _        => return None,
+        };
+
+        Some(placeholder)
+    }
+}
+
+impl fmt::Display for Placeholder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Bucket => "bucket",
+            Self::Bytes  => "bytes",
+            Self::Count  => "count",
+            Self::Human  => "human",
+            Self::Region => "region",
+            Self::Total  => "total",
+        };
+
+        write!(f, "{{{name}}}")
+    }
+}
+
+/// A piece of a `Template`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Token {
+    /// Text that is output as is.
+    Literal(String),
+
+    /// A value that is substituted when the template is rendered.
+    Placeholder(Placeholder),
+}
+
+/// A user supplied format string, such as `{bucket}\t{bytes}`.
+///
+/// Placeholders are written in braces, and literal braces are written as `{{`
+/// and `}}`. The escapes `\t`, `\n` and `\\` are also recognised, so that
+/// tabs and newlines can easily be given on the command line.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Template {
+    /// The parsed pieces of the template.
+    tokens: Vec<Token>,
+}
+
+impl Template {
+    /// Parse the template `s`, which may only use the `allowed` placeholders.
+    pub fn parse(s: &str, allowed: &[Placeholder]) -> Result<Self, String> {
+        let mut tokens  = Vec::new();
+        let mut literal = String::new();
+        let mut chars   = s.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.next_if_eq(&'{').is_some() => literal.push('{'),
+                '}' if chars.next_if_eq(&'}').is_some() => literal.push('}'),
+                '{' => {
+                    let mut name = String::new();
+
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c)   => name.push(c),
+                            None      => {
+                                return Err(format!(
+                                    "Unclosed placeholder '{{{name}'",
+                                ));
+                            },
+                        }
+                    }
+
+                    let placeholder = Placeholder::from_name(&name)
+                        .ok_or_else(|| {
+                            format!("Unknown placeholder '{{{name}}}'")
+                        })?;
+
+                    if !allowed.contains(&placeholder) {
+                        return Err(format!(
+                            "Placeholder '{placeholder}' can't be used here",
+                        ));
+                    }
+
+                    if !literal.is_empty() {
+                        tokens.push(Token::Literal(literal.split_off(0)));
+                    }
+
+                    tokens.push(Token::Placeholder(placeholder));
+                },
+                '}' => {
+                    return Err("Unmatched '}', use '}}' for a literal brace".into());
+                },
+                '\\' => match chars.next() {
+                    Some('t')  => literal.push('\t'),
+                    Some('n')  => literal.push('\n'),
+                    Some('\\') => literal.push('\\'),
+                    Some(c)    => {
+                        literal.push('\\');
+                        literal.push(c);
+                    },
+                    None       => literal.push('\\'),
+                },
+                c => literal.push(c),
+            }
+        }
+
+        if !literal.is_empty() {
+            tokens.push(Token::Literal(literal));
+        }
+
+        Ok(Self { tokens })
+    }
+
+    /// Render the template, substituting each placeholder with the result of
+    /// `value`.
+    pub fn render<F>(&self, value: F) -> String
+    where
+        F: Fn(Placeholder) -> String,
+    {
+        self.tokens
+            .iter()
+            .map(|token| match token {
+                Token::Literal(literal)         => literal.clone(),
+                Token::Placeholder(placeholder) => value(*placeholder),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn render(template: &Template) -> String {
+        template.render(|placeholder| placeholder.to_string().to_uppercase())
+    }
+
+    #[test]
+    fn test_parse() {
+        let tests = vec![
+            ("{bucket}\\t{bytes}",   "{BUCKET}\t{BYTES}"),
+            ("size: {human}\\n",     "size: {HUMAN}\n"),
+            ("{{{region}}}",         "{{REGION}}"),
+            ("a\\\\b \\x",           "a\\b \\x"),
+            ("no placeholders",      "no placeholders"),
+            ("",                     ""),
+        ];
+
+        for test in tests {
+            let input    = test.0;
+            let expected = test.1;
+
+            let template = Template::parse(input, Placeholder::BUCKET).unwrap();
+
+            assert_eq!(render(&template), expected, "template {input:?}");
+        }
+    }
+
+    #[test]
+    fn test_parse_err() {
+        let tests = vec![
+            ("{nope}",     Placeholder::BUCKET, "Unknown placeholder '{nope}'"),
+            ("{bucket",    Placeholder::BUCKET, "Unclosed placeholder '{bucket'"),
+            ("bucket}",    Placeholder::BUCKET, "Unmatched '}', use '}}' for a literal brace"),
+            ("{total}",    Placeholder::BUCKET, "Placeholder '{total}' can't be used here"),
+            ("{bucket}",   Placeholder::TOTAL,  "Placeholder '{bucket}' can't be used here"),
+        ];
+
+        for test in tests {
+            let input    = test.0;
+            let allowed  = test.1;
+            let expected = test.2;
+
+            let ret = Template::parse(input, allowed);
+
+            assert_eq!(ret.unwrap_err(), expected, "template {input:?}");
+        }
+    }
+}
@@ -0,0 +1,224 @@
+// ThrottleCounter
+#![forbid(unsafe_code)]
+#![deny(missing_docs)]
+use aws_smithy_runtime_api::box_error::BoxError;
+use aws_smithy_runtime_api::client::interceptors::Intercept;
+use aws_smithy_runtime_api::client::interceptors::context::{
+    AfterDeserializationInterceptorContextRef,
+};
+use aws_smithy_runtime_api::client::runtime_components::RuntimeComponents;
+use aws_smithy_types::config_bag::ConfigBag;
+use std::sync::Arc;
+use std::sync::atomic::{
+    AtomicUsize,
+    Ordering,
+};
+use tracing::debug;
+
+/// Error codes that AWS services use to tell us to slow down.
+const THROTTLING_CODES: &[&str] = &[
+    "RequestLimitExceeded",
+    "RequestThrottled",
+    "RequestThrottledException",
+    "SlowDown",
+    "ThrottledException",
+    "Throttling",
+    "ThrottlingException",
+    "TooManyRequestsException",
+];
+
+/// HTTP status used by some services for throttling, regardless of the
+/// error code.
+const TOO_MANY_REQUESTS: u16 = 429;
+
+/// Counts the responses from AWS that throttled our requests.
+///
+/// This is registered as an interceptor on each of our AWS SDK clients, every
+/// attempt at a request is counted, including those that are later retried
+/// successfully. Clones share the same count.
+#[derive(Clone, Debug, Default)]
+pub struct ThrottleCounter(Arc<AtomicUsize>);
+
+impl ThrottleCounter {
+    /// Returns the number of throttled responses seen so far.
+    pub fn count(&self) -> usize {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl Intercept for ThrottleCounter {
+    fn name(&self) -> &'static str {
+        "ThrottleCounter"
+    }
+
+    fn read_after_deserialization(
+        &self,
+        context:             &AfterDeserializationInterceptorContextRef<'_>,
+        _runtime_components: &RuntimeComponents,
+        _cfg:                &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        let response = context.response();
+        let status   = response.status().as_u16();
+
+        // Bodies of non-streaming responses have been read into memory by
+        // the time that they're deserialized.
+        let body = response.body().bytes().unwrap_or_default();
+
+        if is_throttled(status, body) {
+            debug!("read_after_deserialization: Throttled with {}", status);
+
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the error code from an XML error response `body`, if any.
+fn error_code(body: &[u8]) -> Option<&str> {
+    let body  = std::str::from_utf8(body).ok()?;
+    let start = body.find("<Code>")? + "<Code>".len();
+    let end   = body[start..].find("</Code>")? + start;
+
+    Some(body[start..end].trim())
+}
+
+/// Returns whether a response with the given `status` and `body` throttled
+/// a request.
+fn is_throttled(status: u16, body: &[u8]) -> bool {
+    if status == TOO_MANY_REQUESTS {
+        return true;
+    }
+
+    // Successful responses may contain anything, including object keys that
+    // look like error codes.
+    if status < 400 {
+        return false;
+    }
+
+    error_code(body).is_some_and(|code| THROTTLING_CODES.contains(&code))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_credential_types::Credentials;
+    use aws_sdk_sts::config::retry::RetryConfig;
+    use aws_sdk_sts::config::{
+        Config as StsConfig,
+        Region,
+    };
+    use aws_sdk_sts::Client as StsClient;
+    use aws_smithy_runtime::client::http::test_util::{
+        ReplayEvent,
+        StaticReplayClient,
+    };
+    use aws_smithy_types::body::SdkBody;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_is_throttled() {
+        let tests = vec![
+            (
+                200,
+                "<Contents><Key>SlowDown</Key></Contents>",
+                false,
+            ),
+            (
+                400,
+                "<ErrorResponse><Error><Code>Throttling</Code></Error></ErrorResponse>",
+                true,
+            ),
+            (
+                403,
+                "<Error><Code>AccessDenied</Code></Error>",
+                false,
+            ),
+            (
+                429,
+                "",
+                true,
+            ),
+            (
+                503,
+                "<Error><Code>SlowDown</Code></Error>",
+                true,
+            ),
+            (
+                503,
+                "<Error><Code>ServiceUnavailable</Code></Error>",
+                false,
+            ),
+            (
+                503,
+                "",
+                false,
+            ),
+        ];
+
+        for test in tests {
+            let status   = test.0;
+            let body     = test.1;
+            let expected = test.2;
+
+            let ret = is_throttled(status, body.as_bytes());
+
+            assert_eq!(ret, expected, "{} {}", status, body);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_throttle_counter() {
+        let tests = vec![
+            (
+                400,
+                "<ErrorResponse><Error><Type>Sender</Type><Code>Throttling</Code><Message>Rate exceeded</Message></Error></ErrorResponse>",
+                1,
+            ),
+            (
+                403,
+                "<ErrorResponse><Error><Type>Sender</Type><Code>AccessDenied</Code><Message>Access denied</Message></Error></ErrorResponse>",
+                0,
+            ),
+        ];
+
+        for test in tests {
+            let status   = test.0;
+            let body     = test.1;
+            let expected = test.2;
+
+            let http_client = StaticReplayClient::new(vec![
+                ReplayEvent::new(
+                    http::Request::builder()
+                        .body(SdkBody::from("request body"))
+                        .unwrap(),
+
+                    http::Response::builder()
+                        .status(status)
+                        .body(SdkBody::from(body))
+                        .unwrap(),
+                ),
+            ]);
+
+            let counter = ThrottleCounter::default();
+
+            let conf = StsConfig::builder()
+                .behavior_version_latest()
+                .credentials_provider(Credentials::for_tests())
+                .http_client(http_client)
+                .interceptor(counter.clone())
+                .region(Region::new("eu-west-1"))
+                .retry_config(RetryConfig::disabled())
+                .build();
+
+            let client = StsClient::from_conf(conf);
+
+            let ret = client.get_caller_identity()
+                .send()
+                .await;
+
+            assert!(ret.is_err());
+            assert_eq!(counter.count(), expected);
+        }
+    }
+}
@@ -0,0 +1,72 @@
+// connection: HTTP clients for the AWS SDK with our connection settings
+#![forbid(unsafe_code)]
+#![deny(missing_docs)]
+use aws_smithy_runtime::client::http::hyper_014::HyperClientBuilder;
+use aws_types::sdk_config::SharedHttpClient;
+use hyper::client::HttpConnector;
+use hyper_rustls::HttpsConnectorBuilder;
+use crate::proxy;
+
+/// Return an HTTP client for the AWS SDK that makes requests via the given
+/// `proxy`, and keeps at most `max_connections` idle connections open to each
+/// host for reuse.
+///
+/// If neither is given, `None` is returned and the SDK's default HTTP client
+/// should be used.
+pub fn http_client(
+    proxy: Option<&str>,
+    max_connections: Option<usize>,
+) -> Option<SharedHttpClient> {
+    if proxy.is_none() && max_connections.is_none() {
+        return None;
+    }
+
+    let mut hyper_builder = hyper::Client::builder();
+
+    if let Some(max_connections) = max_connections {
+        hyper_builder.pool_max_idle_per_host(max_connections);
+    }
+
+    let builder = HyperClientBuilder::new()
+        .hyper_builder(hyper_builder);
+
+    let client = if let Some(proxy) = proxy {
+        builder.build(proxy::connector(proxy))
+    }
+    else {
+        let connector = HttpsConnectorBuilder::new()
+            .with_native_roots()
+            .https_or_http()
+            .enable_http1()
+            .wrap_connector(HttpConnector::new());
+
+        builder.build(connector)
+    };
+
+    Some(client)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_http_client() {
+        let tests = vec![
+            (None,                                  None,     false),
+            (None,                                  Some(64), true),
+            (Some("http://proxy.example.com:3128"), None,     true),
+            (Some("http://proxy.example.com:3128"), Some(64), true),
+        ];
+
+        for test in tests {
+            let proxy           = test.0;
+            let max_connections = test.1;
+            let expected        = test.2;
+
+            let ret = http_client(proxy, max_connections);
+
+            assert_eq!(ret.is_some(), expected);
+        }
+    }
+}
@@ -0,0 +1,155 @@
+// credentials: Credentials for the AWS SDK, assuming a role if requested
+#![forbid(unsafe_code)]
+#![deny(missing_docs)]
+use aws_config::sts::AssumeRoleProvider;
+use aws_types::SdkConfig;
+use aws_types::sdk_config::SharedCredentialsProvider;
+use tracing::debug;
+
+/// The session name used when assuming a role.
+const SESSION_NAME: &str = "s3du";
+
+/// Returns the given `sdk_config` with its credentials replaced by those of
+/// the role at `role_arn`, if one is given.
+///
+/// The role is assumed with the credentials that `sdk_config` already has,
+/// passing the `external_id` if one is given. The assumed credentials are
+/// refreshed as they expire.
+pub async fn assume_role(
+    sdk_config: SdkConfig,
+    role_arn: Option<&str>,
+    external_id: Option<&str>,
+) -> SdkConfig {
+    let Some(role_arn) = role_arn else {
+        return sdk_config
+    };
+
+    debug!("assume_role: Assuming role '{}'", role_arn);
+
+    let provider = AssumeRoleProvider::builder(role_arn)
+        .session_name(SESSION_NAME);
+
+    let provider = if let Some(external_id) = external_id {
+        provider.external_id(external_id)
+    }
+    else {
+        provider
+    };
+
+    // Any custom endpoint is for S3, STS should be reached at its usual
+    // endpoint.
+    let mut sts_config = sdk_config.clone().into_builder();
+    sts_config.set_endpoint_url(None);
+
+    let provider = provider
+        .configure(&sts_config.build())
+        .build()
+        .await;
+
+    sdk_config.into_builder()
+        .credentials_provider(SharedCredentialsProvider::new(provider))
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_credential_types::Credentials;
+    use aws_credential_types::provider::ProvideCredentials;
+    use aws_smithy_runtime::client::http::test_util::{
+        ReplayEvent,
+        StaticReplayClient,
+    };
+    use aws_smithy_types::body::SdkBody;
+    use aws_types::region::Region;
+    use pretty_assertions::assert_eq;
+    use std::fs;
+    use std::path::Path;
+
+    // Return an SdkConfig with test credentials, answering the single request
+    // made with the given data_file.
+    async fn sdk_config(data_file: &str) -> (SdkConfig, StaticReplayClient) {
+        let path = Path::new("test-data").join(data_file);
+        let data = fs::read_to_string(path).unwrap();
+
+        let http_client = StaticReplayClient::new(vec![
+            ReplayEvent::new(
+                http::Request::builder()
+                    .body(SdkBody::from("request body"))
+                    .unwrap(),
+
+                http::Response::builder()
+                    .status(200)
+                    .body(SdkBody::from(data))
+                    .unwrap(),
+            ),
+        ]);
+
+        let creds = Credentials::for_tests_with_session_token();
+
+        let sdk_config = aws_config::from_env()
+            .credentials_provider(creds)
+            .http_client(http_client.clone())
+            .region(Region::new("eu-west-1"))
+            .load()
+            .await;
+
+        (sdk_config, http_client)
+    }
+
+    #[tokio::test]
+    async fn test_assume_role() {
+        let (sdk_config, http_client) = sdk_config("sts-assume-role.xml")
+            .await;
+
+        let sdk_config = assume_role(
+            sdk_config,
+            Some("arn:aws:iam::123456789012:role/s3du"),
+            Some("an-external-id"),
+        ).await;
+
+        let creds = sdk_config.credentials_provider()
+            .unwrap()
+            .provide_credentials()
+            .await
+            .unwrap();
+
+        assert_eq!(creds.access_key_id(), "ASSUMEDACCESSKEYID");
+
+        let request = http_client.actual_requests()
+            .next()
+            .unwrap();
+
+        let body = String::from_utf8(
+            request.body().bytes().unwrap().to_vec(),
+        ).unwrap();
+
+        let expected = vec![
+            "RoleArn=arn%3Aaws%3Aiam%3A%3A123456789012%3Arole%2Fs3du",
+            "RoleSessionName=s3du",
+            "ExternalId=an-external-id",
+        ];
+
+        for param in expected {
+            assert!(body.contains(param), "{param} in {body}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_assume_role_none() {
+        let (sdk_config, http_client) = sdk_config("sts-assume-role.xml")
+            .await;
+
+        let sdk_config = assume_role(sdk_config, None, None).await;
+
+        let creds = sdk_config.credentials_provider()
+            .unwrap()
+            .provide_credentials()
+            .await
+            .unwrap();
+
+        // Our own credentials are used, without a request to STS.
+        assert_eq!(creds.access_key_id(), "ANOTREAL");
+        assert_eq!(http_client.actual_requests().count(), 0);
+    }
+}
@@ -1,51 +1,213 @@
 //! s3du: A tool for informing you of the used space in AWS S3 buckets.
 #![forbid(unsafe_code)]
 #![deny(missing_docs)]
-use anyhow::Result;
+// Without either feature there are no client modes to choose from, fail the
+// build early with a useful message instead of a pile of missing symbols.
+// Everything that needs a mode, including `main`, is left out so that the
+// message below is the only error.
+#![cfg_attr(
+    not(any(feature = "cloudwatch", feature = "s3")),
+    no_main,
+    allow(unused),
+)]
+
+#[cfg(not(any(feature = "cloudwatch", feature = "s3")))]
+compile_error!(
+    "s3du requires at least one of the \"cloudwatch\" or \"s3\" features"
+);
+
+use anyhow::{
+    anyhow,
+    Context,
+    Result,
+};
+use futures::stream::{
+    self,
+    LocalBoxStream,
+    StreamExt,
+    TryStreamExt,
+};
+use glob::Pattern;
+use std::fs::File;
+use std::io::{
+    self,
+    BufWriter,
+    Write,
+};
+use std::num::NonZeroUsize;
+use std::path::{
+    Path,
+    PathBuf,
+};
 use std::str::FromStr;
+use std::time::Duration;
 use tracing::{
     debug,
     info,
+    warn,
 };
 
+#[cfg(feature = "cloudwatch")]
+use std::num::NonZeroU32;
+
+/// Self-test against the bundled test fixtures.
+#[cfg(feature = "benchmark")]
+mod benchmark;
+
+/// Caching of bucket sizes between runs.
+mod cache;
+use cache::SizeCache;
+
 /// Command line parsing.
 mod cli;
 
 /// Common types and traits.
 mod common;
 use common::{
+    Bucket,
+    BucketSize,
     BucketSizer,
+    BucketStream,
     ClientConfig,
     ClientMode,
-    HumanSize,
+    OutputConfig,
+    OutputFormat,
     Region,
     SizeUnit,
+    SortKey,
+    SortOrder,
+    Template,
+    ThrottleCounter,
 };
 
+#[cfg(feature = "cloudwatch")]
+use common::MetricStatistic;
+
 #[cfg(feature = "s3")]
-use common::ObjectVersions;
+use common::{
+    is_wildcard,
+    ObjectVersions,
+};
 
 /// `CloudWatch` Client.
 #[cfg(feature = "cloudwatch")]
 mod cloudwatch;
 
+/// HTTP clients for the AWS SDK.
+mod connection;
+
+/// Credentials for the AWS SDK.
+mod credentials;
+
+/// Output of bucket sizes.
+mod output;
+
+/// Interactive confirmation prompts.
+mod prompt;
+
+/// HTTP proxy support.
+mod proxy;
+
 /// S3 Client.
 #[cfg(feature = "s3")]
 mod s3;
 
+/// S3 Storage Lens Client.
+#[cfg(feature = "s3")]
+mod storage_lens;
+
+/// STS Client, used to find the AWS account ID.
+mod sts;
+
+/// Convenience type for a stream of buckets with their sizes, or the error
+/// that sizing them failed with.
+type SizedBuckets<'a> =
+    LocalBoxStream<'a, Result<(Bucket, Result<BucketSize>)>>;
+
 /// `Client` struct wraps a `Box<dyn BucketSizer>`.
-struct Client(Box<dyn BucketSizer>);
+struct Client {
+    /// The `BucketSizer` for the selected `ClientMode`.
+    sizer: Box<dyn BucketSizer>,
+
+    /// Maximum time that sizing a single bucket may take, if any.
+    bucket_timeout: Option<Duration>,
+
+    /// Bucket sizes cached between runs, if a cache file was given.
+    cache: Option<SizeCache>,
+
+    /// Maximum number of buckets that are sized at once.
+    concurrency: usize,
+
+    /// Whether buckets that fail to be sized are skipped.
+    continue_on_error: bool,
+
+    /// Whether to fail once every other bucket has been output, if any
+    /// buckets were skipped.
+    keep_going: bool,
+
+    /// Ask for confirmation before sizing more than this many buckets, if
+    /// set.
+    confirm_threshold: Option<usize>,
+
+    /// The settings for the STS client used to find the AWS account ID for
+    /// structured output, which is only created when it's needed.
+    ///
+    /// This is `None` when a custom endpoint is in use, as there is no AWS
+    /// account to find.
+    sts: Option<sts::Config>,
+
+    /// Counts the requests that AWS throttled while sizing.
+    throttle_counter: ThrottleCounter,
+}
 
 /// `Client` implementation.
+#[cfg(any(feature = "cloudwatch", feature = "s3"))]
 impl Client {
     /// Return the appropriate AWS client with the given `ClientConfig`.
     async fn new(config: ClientConfig) -> Self {
-        let mode   = &config.mode;
-        let region = &config.region;
+        let bucket_timeout    = config.bucket_timeout;
+        let concurrency       = config.concurrency;
+        let continue_on_error = config.continue_on_error;
+        let keep_going        = config.keep_going;
+        let mode              = &config.mode;
+        let region            = &config.region;
+        let throttle_counter  = config.throttle_counter.clone();
+
+        // Sizes are cached per mode, and per object versions in S3 mode, so
+        // that different queries don't collide.
+        #[cfg(feature = "s3")]
+        let object_versions = (*mode == ClientMode::S3)
+            .then(|| config.object_versions.to_string());
+
+        #[cfg(not(feature = "s3"))]
+        let object_versions = None;
+
+        let cache = config.cache.clone().map(|path| {
+            SizeCache::load(
+                path,
+                config.cache_max_age,
+                mode.name(),
+                object_versions,
+            )
+        });
+
+        #[cfg(feature = "s3")]
+        let confirm_threshold = config.confirm_threshold;
+
+        #[cfg(not(feature = "s3"))]
+        let confirm_threshold = None;
+
+        #[cfg(feature = "s3")]
+        let custom_endpoint = config.endpoint.is_some();
+
+        #[cfg(not(feature = "s3"))]
+        let custom_endpoint = false;
+
+        let sts = (!custom_endpoint).then(|| sts::Config::from(&config));
 
         info!("Client in region {} for mode {:?}", region.name(), mode);
 
-        let client: Box<dyn BucketSizer> = match mode {
+        let sizer: Box<dyn BucketSizer> = match mode {
             #[cfg(feature = "cloudwatch")]
             ClientMode::CloudWatch => {
                 let client = cloudwatch::Client::new(config);
@@ -56,69 +218,466 @@ impl Client {
                 let client = s3::Client::new(config);
                 Box::new(client.await)
             },
+            #[cfg(feature = "s3")]
+            ClientMode::StorageLens => {
+                let client = storage_lens::Client::new(config);
+                Box::new(client.await)
+            },
         };
 
-        Client(client)
+        Self {
+            sizer,
+            bucket_timeout,
+            cache,
+            concurrency,
+            confirm_threshold,
+            continue_on_error,
+            keep_going,
+            sts,
+            throttle_counter,
+        }
     }
 
-    /// Perform the actual get and output of the bucket sizes.
-    async fn du(&self, unit: SizeUnit) -> Result<()> {
-        // List all of our buckets
-        let buckets = self.0.buckets().await?;
+    /// Return the size of the given `bucket`, giving up if the
+    /// `bucket_timeout` is exceeded.
+    ///
+    /// If a fresh enough size is cached, it's returned without sizing the
+    /// bucket, otherwise the new size is recorded in the cache.
+    async fn bucket_size(&self, bucket: &Bucket) -> Result<BucketSize> {
+        let cached = self.cache.as_ref()
+            .and_then(|cache| cache.get(&bucket.name));
+
+        if let Some(size) = cached {
+            return Ok(size);
+        }
+
+        let size = self.sizer.bucket_size(bucket);
 
-        debug!("du: Got buckets: {:?}", buckets);
+        let size = if let Some(timeout) = self.bucket_timeout {
+            tokio::time::timeout(timeout, size)
+                .await
+                .map_err(|_| {
+                    anyhow!(
+                        "Sizing '{}' timed out after {}s",
+                        bucket.name,
+                        timeout.as_secs(),
+                    )
+                })??
+        }
+        else {
+            size.await?
+        };
+
+        if let Some(cache) = &self.cache {
+            cache.insert(&bucket.name, &size);
+        }
+
+        Ok(size)
+    }
+
+    /// Perform the actual get and output of the bucket sizes to `out`.
+    ///
+    /// If AWS throttled any of our requests, a suggestion for avoiding this
+    /// is logged once we're done, whether or not sizing succeeded.
+    async fn du(
+        &self,
+        config: OutputConfig,
+        out: &mut dyn Write,
+    ) -> Result<()> {
+        let ret = self.size_buckets(config, out).await;
+
+        if let Some(suggestion) = self.throttling_suggestion() {
+            warn!("{}", suggestion);
+        }
+
+        ret
+    }
+
+    /// Output the name and region of each bucket that would be sized to
+    /// `out`, without sizing any of them.
+    ///
+    /// Buckets are output as they're discovered, with a `-` for any region
+    /// that isn't known.
+    async fn list_buckets(&self, out: &mut dyn Write) -> Result<()> {
+        let mut buckets = self.sizer.bucket_stream();
+
+        while let Some(bucket) = buckets.try_next().await? {
+            let region = bucket.region
+                .as_ref()
+                .map_or("-", |region| region.name());
+
+            writeln!(out, "{}\t{}", region, bucket.name)?;
+        }
+
+        out.flush()?;
+
+        Ok(())
+    }
+
+    /// Return a suggestion for avoiding throttling, if any of our requests
+    /// were throttled.
+    fn throttling_suggestion(&self) -> Option<String> {
+        let throttled = self.throttle_counter.count();
+
+        if throttled == 0 {
+            return None;
+        }
+
+        let suggestion = format!(
+            "AWS throttled {} request(s), retries may have slowed sizing or \
+             caused it to fail. Try making fewer concurrent requests, with a \
+             lower --concurrency or without --parallel-prefixes or \
+             --prewarm.",
+            throttled,
+        );
+
+        Some(suggestion)
+    }
+
+    /// Size each bucket, output the sizes to `out`, and then save any cache.
+    ///
+    /// If `keep_going` is set, this fails once every other bucket has been
+    /// output if any buckets were skipped.
+    async fn size_buckets(
+        &self,
+        config: OutputConfig,
+        out: &mut dyn Write,
+    ) -> Result<()> {
+        let sizes = self.sizes().await?;
+        let total = self.render(config, out, sizes).await?;
+
+        if let Some(cache) = &self.cache {
+            cache.save()?;
+        }
+
+        // The other buckets have been output, now we can report that some
+        // were skipped.
+        if self.keep_going && total.error_count > 0 {
+            return Err(anyhow!(
+                "Failed to size {} bucket(s)",
+                total.error_count,
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Returns a stream of each bucket with its size, or the error that
+    /// sizing it failed with, in discovery order.
+    ///
+    /// Buckets are sized as the stream is polled, up to `concurrency` at
+    /// once, so that sizes can be output as soon as they're known.
+    async fn sizes(&self) -> Result<SizedBuckets<'_>> {
+        let buckets = self.sizer.bucket_stream();
+
+        // Sizing many buckets can issue a huge number of requests, make sure
+        // that this was intended. Only enough buckets to cross the threshold
+        // are discovered before asking, the rest are sized as they're
+        // discovered either way.
+        let buckets = if let Some(threshold) = self.confirm_threshold {
+            let (first, rest) = discover_up_to(buckets, threshold).await?;
+
+            debug!("sizes: Got first buckets: {:?}", first);
+
+            if first.len() > threshold && !prompt::confirm_buckets(threshold)? {
+                return Err(anyhow!(
+                    "Not sizing more than {} buckets",
+                    threshold,
+                ));
+            }
+
+            stream::iter(first.into_iter().map(Ok))
+                .chain(rest)
+                .boxed()
+        }
+        else {
+            buckets
+        };
+
+        // Up to `concurrency` buckets are sized at once, but they're returned
+        // in discovery order, which also limits how many finished sizes can
+        // be waiting behind a slow bucket.
+        let sized = buckets
+            .map_ok(move |bucket| async move {
+                let size = self.bucket_size(&bucket).await;
+
+                Ok((bucket, size))
+            })
+            .try_buffered(self.concurrency);
+
+        Ok(sized.boxed_local())
+    }
+
+    /// Output the `sizes` of each bucket to `out`, followed by their total,
+    /// returning the total.
+    ///
+    /// Buckets that failed to be sized are skipped if `continue_on_error` is
+    /// set, otherwise the first failure is returned.
+    async fn render(
+        &self,
+        mut config: OutputConfig,
+        out: &mut dyn Write,
+        mut sizes: SizedBuckets<'_>,
+    ) -> Result<output::Total> {
+        // The account ID is only looked up for structured output, which is
+        // the only place that it's displayed, so the STS client is only
+        // created here.
+        if config.format == OutputFormat::Json {
+            if let Some(sts) = &self.sts {
+                let sts = sts::Client::new(sts).await;
+
+                config.account_id = sts.account_id().await;
+            }
+        }
+
+        let mut renderer = output::renderer(&config);
 
         // Track total size of all buckets.
-        let mut total_size: u64 = 0;
+        let mut total = output::Total::new(
+            config.by_region,
+            config.by_storage_class,
+        );
+
+        // Sized buckets waiting to be sorted, or limited to the largest.
+        let mut sorted = Vec::new();
+
+        // Sorted or limited buckets can only be output once every bucket is
+        // sized.
+        let deferred = config.sort.is_some() || config.top.is_some();
 
         // For each bucket name, get the size
-        for bucket in buckets {
-            let size = self.0.bucket_size(&bucket).await?;
+        while let Some((bucket, size)) = sizes.try_next().await? {
+            let size = match size {
+                Ok(size) => size,
+                Err(e) if self.continue_on_error => {
+                    warn!("Skipping '{}': {:#}", bucket.name, e);
+                    total.add_error();
 
-            total_size += size;
+                    continue;
+                },
+                Err(e) => return Err(e),
+            };
 
-            let size = size.humansize(&unit);
+            total.add(&bucket, &size);
 
-            println!("{size}\t{bucket}", bucket=bucket.name);
+            // When summarizing, only the total is output.
+            if config.summarize {
+                continue;
+            }
+
+            // Buckets below the threshold are only included in the total.
+            if config.threshold.is_some_and(|threshold| size.bytes < threshold) {
+                continue;
+            }
+
+            if deferred {
+                sorted.push((bucket, size));
+            }
+            else {
+                renderer.bucket(out, &bucket, &size)?;
+            }
         }
 
-        let total_size = total_size.humansize(&unit);
+        // The largest buckets are output first, unless another sort was
+        // requested.
+        if let Some(top) = config.top {
+            output::sort_buckets(
+                &mut sorted,
+                SortKey::Size,
+                SortOrder::Descending,
+            );
+
+            sorted.truncate(top);
+        }
 
-        // Display the total size the same way du(1) would, the total size
-        // followed by a `.`.
-        println!("{total_size}\t.");
+        if let Some(key) = config.sort {
+            let order = config.order.unwrap_or_else(|| key.default_order());
 
-        Ok(())
+            output::sort_buckets(&mut sorted, key, order);
+        }
+
+        for (bucket, size) in &sorted {
+            renderer.bucket(out, bucket, size)?;
+        }
+
+        renderer.total(out, &total)?;
+
+        out.flush()?;
+
+        Ok(total)
     }
 }
 
+/// Returns the first buckets discovered by `buckets`, up to one more than
+/// `limit`, along with the rest of the stream.
+///
+/// This is enough to know whether there are more than `limit` buckets without
+/// discovering every bucket first.
+async fn discover_up_to(
+    mut buckets: BucketStream<'_>,
+    limit: usize,
+) -> Result<(Vec<Bucket>, BucketStream<'_>)> {
+    let mut first = Vec::new();
+
+    while first.len() <= limit {
+        match buckets.try_next().await? {
+            Some(bucket) => first.push(bucket),
+            None         => break,
+        }
+    }
+
+    Ok((first, buckets))
+}
+
+/// Returns the writer that bucket sizes are output to, either the file at
+/// `path` or standard output if no `path` is given.
+///
+/// An existing file at `path` is truncated.
+fn output_writer(path: Option<&Path>) -> Result<Box<dyn Write>> {
+    let Some(path) = path else {
+        return Ok(Box::new(io::stdout()))
+    };
+
+    let file = File::create(path)
+        .with_context(|| format!("Failed to create '{}'", path.display()))?;
+
+    Ok(Box::new(BufWriter::new(file)))
+}
+
 /// Entry point
+#[cfg(any(feature = "cloudwatch", feature = "s3"))]
 #[tokio::main]
 async fn main() -> Result<()> {
-    tracing_subscriber::fmt::init();
+    // Logs are written to stderr so they don't mix with our output.
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .init();
 
     // Parse the CLI
     let matches = cli::parse_args();
 
-    // Get the bucket name, if any.
-    let bucket_name = matches.get_one::<String>("BUCKET").cloned();
+    // The benchmark doesn't use any of the other options.
+    #[cfg(feature = "benchmark")]
+    if matches.get_flag("BENCHMARK") {
+        return benchmark::run(&mut io::stdout()).await;
+    }
+
+    // Get the bucket names, if any.
+    let bucket_names: Vec<String> = matches.get_many::<String>("BUCKET")
+        .map(|names| names.cloned().collect())
+        .unwrap_or_default();
+
+    // Get the patterns of bucket names to skip, if any.
+    let exclude: Vec<Pattern> = matches.get_many::<Pattern>("EXCLUDE")
+        .map(|patterns| patterns.cloned().collect())
+        .unwrap_or_default();
+
+    // Keeping going skips failed buckets in the same way as continuing on
+    // error, but still fails in the end.
+    let keep_going        = matches.get_flag("KEEP_GOING");
+    let continue_on_error = keep_going
+        || matches.get_flag("CONTINUE_ON_ERROR");
 
     // Get the client mode
     let mode: ClientMode = {
-        let mode = matches.get_one::<String>("MODE")
-            .expect("client mode");
+        let mode = cli::client_mode(&matches);
 
-        ClientMode::from_str(mode.as_str())
+        ClientMode::from_str(mode)
             .expect("client mode")
     };
 
-    // Get the unit size to display
-    let unit: SizeUnit = {
-        let unit = matches.get_one::<String>("UNIT")
-            .expect("size unit");
+    // Get the unit size to display, a block size takes priority as the unit
+    // always has a default.
+    let unit: SizeUnit = match matches.get_one::<SizeUnit>("BLOCK_SIZE") {
+        Some(unit) => unit.clone(),
+        None       => {
+            let unit = cli::size_unit(&matches);
+
+            let unit = SizeUnit::from_str(unit)
+                .expect("size unit")
+                .group_digits(matches.get_flag("GROUP_DIGITS"));
+
+            match matches.get_one::<usize>("PRECISION") {
+                Some(places) => unit.precision(*places),
+                None         => unit,
+            }
+        },
+    };
+
+    // Get the output format
+    let format: OutputFormat = {
+        let format = matches.get_one::<String>("FORMAT")
+            .expect("output format");
+
+        OutputFormat::from_str(format.as_str())
+            .expect("output format")
+    };
+
+    // Get the sort key and direction, if any
+    let sort = matches.get_one::<String>("SORT")
+        .map(|sort| SortKey::from_str(sort).expect("sort key"));
+
+    let order = matches.get_one::<String>("ORDER")
+        .map(|order| SortOrder::from_str(order).expect("sort order"));
+
+    let count = matches.get_flag("COUNT");
+
+    // Multipart upload ages are only available in S3 mode.
+    #[cfg(feature = "s3")]
+    let multipart_age = mode == ClientMode::S3
+        && matches.get_flag("SHOW_MULTIPART_AGE");
+
+    #[cfg(not(feature = "s3"))]
+    let multipart_age = false;
+
+    // Storage type breakdowns are only available in CloudWatch mode.
+    #[cfg(feature = "cloudwatch")]
+    let breakdown = mode == ClientMode::CloudWatch
+        && matches.get_flag("BREAKDOWN");
+
+    #[cfg(not(feature = "cloudwatch"))]
+    let breakdown = false;
 
-        SizeUnit::from_str(unit.as_str())
-            .expect("size unit")
+    // Datapoint timestamps are only available in CloudWatch mode.
+    #[cfg(feature = "cloudwatch")]
+    let datapoint_timestamp = mode == ClientMode::CloudWatch
+        && matches.get_flag("SHOW_TIMESTAMP");
+
+    #[cfg(not(feature = "cloudwatch"))]
+    let datapoint_timestamp = false;
+
+    let by_storage_class = matches.get_flag("SUM_BY_STORAGE_CLASS");
+    let storage_classes  = matches.get_flag("STORAGE_CLASS_BREAKDOWN");
+
+    // Templates are only used by the template format, make sure that they
+    // weren't given by mistake.
+    if format != OutputFormat::Template && matches.contains_id("TEMPLATE") {
+        eprintln!("Error: Templates require '--format template'");
+        ::std::process::exit(1);
+    }
+
+    // The S3 object versions are added to this below, which won't happen if
+    // compiled without the "s3" feature.
+    #[allow(unused_mut)]
+    let mut output_config = OutputConfig {
+        breakdown,
+        by_storage_class,
+        count,
+        datapoint_timestamp,
+        format,
+        multipart_age,
+        order,
+        sort,
+        storage_classes,
+        unit,
+        by_region:      matches.get_flag("SUMMARY_BY_REGION"),
+        summarize:      matches.get_flag("SUMMARIZE"),
+        template:       matches.get_one::<Template>("TEMPLATE").cloned(),
+        template_total: matches.get_one::<Template>("TEMPLATE_TOTAL").cloned(),
+        threshold:      matches.get_one::<u64>("THRESHOLD").copied(),
+        top:            matches.get_one::<NonZeroUsize>("TOP").map(|n| n.get()),
+        total:          cli::show_total(&matches),
+        ..Default::default()
     };
 
     // Here we get the region, if a custom endpoint is set, that is used,
@@ -128,7 +687,9 @@ async fn main() -> Result<()> {
     #[cfg(feature = "s3")]
     let region = if matches.contains_id("ENDPOINT") {
         if mode == ClientMode::S3 {
-            let region = matches.get_one::<String>("REGION").unwrap();
+            // Custom endpoints may not need a region, so one is derived if
+            // none was given.
+            let region = cli::endpoint_region(&matches);
 
             Region::new().set_region(region)
         }
@@ -150,13 +711,38 @@ async fn main() -> Result<()> {
         Region::new().set_region(region)
     };
 
-    // This warning will trigger if compiled without the "s3" feature. We're
-    // aware, allow it.
+    let bucket_timeout = matches.get_one::<Duration>("PER_BUCKET_TIMEOUT")
+        .copied();
+
+    let cache_max_age = matches.get_one::<Duration>("MAX_AGE")
+        .copied();
+
+    // This should be safe, the concurrency has a default value.
+    let concurrency = matches.get_one::<NonZeroUsize>("CONCURRENCY")
+        .expect("concurrency")
+        .get();
+
+    // These warnings will trigger if compiled without the "s3" feature. We're
+    // aware, allow them.
     #[allow(unused_mut)]
+    #[allow(clippy::needless_update)]
     let mut config = ClientConfig {
-        bucket_name,
+        bucket_names,
+        bucket_timeout,
+        cache_max_age,
+        concurrency,
+        continue_on_error,
+        count,
+        exclude,
+        keep_going,
         mode,
         region,
+        cache:             matches.get_one::<PathBuf>("CACHE").cloned(),
+        external_id:       matches.get_one::<String>("EXTERNAL_ID").cloned(),
+        max_connections:   matches.get_one::<usize>("MAX_CONNECTIONS").copied(),
+        profile:           matches.get_one::<String>("PROFILE").cloned(),
+        proxy:             matches.get_one::<String>("PROXY").cloned(),
+        role_arn:          matches.get_one::<String>("ROLE_ARN").cloned(),
         ..Default::default()
     };
 
@@ -171,15 +757,810 @@ async fn main() -> Result<()> {
             // This should be safe, due to validation of the above.
             let versions = ObjectVersions::from_str(versions).unwrap();
 
+            // Storage classes are taken from the listing of current objects,
+            // the other object versions are listed without them.
+            if by_storage_class || storage_classes {
+                if versions != ObjectVersions::Current {
+                    eprintln!("Error: Storage classes are only supported when sizing current objects in S3 mode");
+                    ::std::process::exit(1);
+                }
+
+                config.storage_classes = true;
+            }
+
             config.object_versions = versions;
 
+            // Delete markers have no size, so their count is always shown.
+            if versions == ObjectVersions::DeleteMarkers {
+                output_config.count = true;
+            }
+
+            // Record which versions were sized alongside the sizes.
+            output_config.object_versions = Some(versions.to_string());
+
+            // A breakdown only makes sense when we're sizing all versions.
+            config.breakdown_versions = matches.get_flag("BREAKDOWN_VERSIONS");
+
+            if config.breakdown_versions
+                && config.object_versions != ObjectVersions::All
+            {
+                eprintln!("Error: Version breakdown requires '--object-versions all'");
+                ::std::process::exit(1);
+            }
+
+            // Multipart uploads are only listed when sizing all versions or
+            // multipart uploads.
+            config.multipart_age = multipart_age;
+
+            if config.multipart_age
+                && !matches!(
+                    config.object_versions,
+                    ObjectVersions::All | ObjectVersions::Multipart,
+                )
+            {
+                eprintln!("Error: Multipart upload ages require '--object-versions all' or '--object-versions multipart'");
+                ::std::process::exit(1);
+            }
+
+            // Only size multipart uploads older than the given number of
+            // days, if requested.
+            config.multipart_older_than = matches
+                .get_one::<u64>("MULTIPART_OLDER_THAN")
+                .map(|days| Duration::from_secs(days.saturating_mul(86_400)));
+
+            if config.multipart_older_than.is_some()
+                && !matches!(
+                    config.object_versions,
+                    ObjectVersions::All | ObjectVersions::Multipart,
+                )
+            {
+                eprintln!("Error: '--multipart-older-than' requires '--object-versions all' or '--object-versions multipart'");
+                ::std::process::exit(1);
+            }
+
+            // List top level prefixes concurrently if requested.
+            config.parallel_prefixes = matches.get_flag("PARALLEL_PREFIXES");
+
+            // Sum object sizes on a single thread if requested.
+            config.parallel_sum = !matches.get_flag("NO_PARALLEL_SUM");
+
+            // Retry throttled listing requests with exponential backoff.
+            // These should be safe, both have default values.
+            config.max_retries = *matches.get_one::<u32>("MAX_RETRIES")
+                .expect("max retries");
+
+            config.retry_base_delay = matches
+                .get_one::<u64>("RETRY_BASE_DELAY_MS")
+                .map(|ms| Duration::from_millis(*ms))
+                .expect("retry base delay");
+
+            // Agree to pay for requests to requester pays buckets, if asked.
+            config.request_payer = matches.contains_id("REQUEST_PAYER");
+
+            // Only size objects under the given prefix, if any.
+            config.prefix = matches.get_one::<String>("PREFIX").cloned();
+
+            // Only size objects in the given storage classes, if any.
+            config.storage_class_filter = matches
+                .get_many::<String>("STORAGE_CLASS")
+                .unwrap_or_default()
+                .cloned()
+                .collect();
+
+            // Look up bucket locations concurrently if requested.
+            config.prewarm = matches.get_flag("PREWARM");
+
+            // Only keep buckets missing non-current expiration if requested.
+            config.missing_lifecycle = matches.get_flag("MISSING_LIFECYCLE");
+
+            // Warn about potential problems found while sizing if requested.
+            config.stats = matches.get_flag("STATS");
+
+            // Warn about uncounted non-current versions if requested.
+            config.warn_versioned = matches.get_flag("WARN_VERSIONED");
+
+            // Tolerate unsupported version listings if requested.
+            config.fallback_on_unsupported = matches
+                .get_flag("FALLBACK_ON_UNSUPPORTED");
+
+            // Skip bucket location lookups if requested.
+            config.assume_region = matches.get_flag("ASSUME_REGION");
+
+            // Size buckets in every region if requested.
+            config.all_regions = matches.get_flag("ALL_REGIONS");
+
             // Set the endpoint
             config.endpoint = matches.get_one::<String>("ENDPOINT").cloned();
+
+            // Path style addressing, which the CLI only allows with an
+            // endpoint.
+            config.path_style = matches.get_flag("PATH_STYLE");
+
+            // Scanning every bucket may be expensive, confirm it unless told
+            // not to or the scan was narrowed down. Wildcards could match any
+            // number of buckets.
+            let all_buckets = config.bucket_names.is_empty()
+                || config.bucket_names.iter().any(|name| is_wildcard(name));
+
+            let narrowed = cli::is_narrowed(&matches);
+
+            if all_buckets && !narrowed && !matches.get_flag("YES") {
+                let threshold = matches.get_one::<usize>("CONFIRM_THRESHOLD")
+                    .copied();
+
+                config.confirm_threshold = threshold;
+            }
+        }
+        else if let Err(e) = cli::s3_only_options(&matches) {
+            eprintln!("Error: {e}");
+            ::std::process::exit(1);
+        }
+    }
+
+    // Storage Lens mode reads sizes from an export rather than from the
+    // buckets themselves.
+    #[cfg(feature = "s3")]
+    {
+        if config.mode == ClientMode::StorageLens {
+            config.storage_lens_export = matches
+                .get_one::<String>("STORAGE_LENS_EXPORT")
+                .cloned();
         }
     }
 
+    // Raw datapoints are only reported in CloudWatch mode.
+    #[cfg(feature = "cloudwatch")]
+    {
+        config.datapoints = config.mode == ClientMode::CloudWatch
+            && matches.get_flag("DATAPOINTS");
+
+        // This should be safe, the lookback has a default value.
+        config.cloudwatch_lookback_days = matches
+            .get_one::<NonZeroU32>("CLOUDWATCH_LOOKBACK_DAYS")
+            .expect("cloudwatch lookback days")
+            .get();
+
+        // This should be safe, the statistic has a default value and was
+        // validated in the CLI parser.
+        let statistic = matches.get_one::<String>("STATISTIC")
+            .expect("statistic");
+
+        config.cloudwatch_statistic = MetricStatistic::from_str(statistic)
+            .expect("statistic");
+
+        // Storage types were validated in the CLI parser.
+        config.cloudwatch_storage_types = matches
+            .get_many::<String>("STORAGE_TYPE")
+            .map(|types| types.cloned().collect());
+    }
+
+    // Checking that CloudWatch buckets still exist requires S3.
+    #[cfg(all(feature = "cloudwatch", feature = "s3"))]
+    {
+        config.verify_exists = config.mode == ClientMode::CloudWatch
+            && matches.get_flag("VERIFY_EXISTS");
+    }
+
+    // Record how the sizes were found alongside them.
+    output_config.mode   = Some(config.mode.name().into());
+    output_config.region = Some(config.region.name().into());
+
+    // Only list the buckets that would be sized, if requested.
+    let list_only = matches.get_flag("LIST_ONLY");
+
+    // Open the output before sizing, so that an unwritable file fails early.
+    let output = matches.get_one::<PathBuf>("OUTPUT")
+        .map(PathBuf::as_path);
+
+    let mut out = output_writer(output)?;
+
     // The region here will come from CLI args in the future
     let client = Client::new(config).await;
 
-    client.du(unit).await
+    let ret = if list_only {
+        client.list_buckets(&mut out).await
+    }
+    else {
+        client.du(output_config, &mut out).await
+    };
+
+    match ret {
+        // Our output was closed, for example by quitting a pager, so there's
+        // nobody left to tell.
+        Err(e) if output::is_broken_pipe(&e) => {
+            debug!("Output closed early: {}", e);
+
+            Ok(())
+        },
+        ret => ret,
+    }
+}
+
+#[cfg(all(test, any(feature = "cloudwatch", feature = "s3")))]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use common::{
+        Buckets,
+        Placeholder,
+    };
+    use pretty_assertions::assert_eq;
+
+    // A BucketSizer returning fixed buckets and sizes, in discovery order.
+    struct StubSizer(&'static [(&'static str, u64)]);
+
+    const STUB_BUCKETS: &[(&str, u64)] = &[
+        ("b-bucket", 30),
+        ("c-bucket", 10),
+        ("a-bucket", 20),
+        ("d-bucket", 10),
+    ];
+
+    #[async_trait]
+    impl BucketSizer for StubSizer {
+        async fn buckets(&self) -> Result<Buckets> {
+            let buckets = self.0.iter()
+                .map(|(name, _)| Bucket {
+                    name:          (*name).into(),
+                    region:        None,
+                    storage_types: None,
+                    is_versioned:  None,
+                    created:       None,
+                })
+                .collect();
+
+            Ok(buckets)
+        }
+
+        async fn bucket_size(&self, bucket: &Bucket) -> Result<BucketSize> {
+            let (_, bytes) = self.0.iter()
+                .find(|(name, _)| *name == bucket.name)
+                .expect("stub bucket");
+
+            Ok(BucketSize::from(*bytes))
+        }
+    }
+
+    // A StubSizer that can only discover buckets one at a time, as S3 mode
+    // does for full-account scans.
+    struct StreamingSizer(StubSizer);
+
+    #[async_trait]
+    impl BucketSizer for StreamingSizer {
+        async fn buckets(&self) -> Result<Buckets> {
+            panic!("every bucket was discovered up front");
+        }
+
+        fn bucket_stream(&self) -> BucketStream<'_> {
+            stream::iter(self.0.0)
+                .map(|(name, _)| Ok(bucket(name)))
+                .boxed()
+        }
+
+        async fn bucket_size(&self, bucket: &Bucket) -> Result<BucketSize> {
+            self.0.bucket_size(bucket).await
+        }
+    }
+
+    fn bucket(name: &str) -> Bucket {
+        Bucket {
+            name:          name.into(),
+            region:        None,
+            storage_types: None,
+            is_versioned:  None,
+            created:       None,
+        }
+    }
+
+    // A StubSizer that takes as many milliseconds to size each bucket as the
+    // bucket has bytes, so that buckets finish sizing out of order.
+    struct SlowSizer(StubSizer);
+
+    #[async_trait]
+    impl BucketSizer for SlowSizer {
+        async fn buckets(&self) -> Result<Buckets> {
+            self.0.buckets().await
+        }
+
+        async fn bucket_size(&self, bucket: &Bucket) -> Result<BucketSize> {
+            let size = self.0.bucket_size(bucket).await?;
+
+            tokio::time::sleep(Duration::from_millis(size.bytes)).await;
+
+            Ok(size)
+        }
+    }
+
+    fn stub_client(buckets: &'static [(&'static str, u64)]) -> Client {
+        Client {
+            sizer:             Box::new(StubSizer(buckets)),
+            bucket_timeout:    None,
+            cache:             None,
+            concurrency:       1,
+            confirm_threshold: None,
+            continue_on_error: false,
+            keep_going:        false,
+            sts:               None,
+            throttle_counter:  ThrottleCounter::default(),
+        }
+    }
+
+    #[test]
+    fn test_output_writer_truncates() {
+        let path = std::env::temp_dir()
+            .join(format!("s3du-test-output-{}", std::process::id()));
+
+        std::fs::write(&path, "a much longer previous report\n").unwrap();
+
+        {
+            let mut out = output_writer(Some(&path)).unwrap();
+
+            writeln!(out, "10\t.").unwrap();
+        }
+
+        let ret = std::fs::read_to_string(&path).unwrap();
+
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(ret, "10\t.\n");
+    }
+
+    #[tokio::test]
+    async fn test_du_concurrency_order() {
+        let expected = vec![
+            "30\tb-bucket",
+            "10\tc-bucket",
+            "20\ta-bucket",
+            "10\td-bucket",
+            "70\t.",
+        ];
+
+        for concurrency in [1, 4] {
+            let client = Client {
+                concurrency,
+                sizer: Box::new(SlowSizer(StubSizer(STUB_BUCKETS))),
+                ..stub_client(STUB_BUCKETS)
+            };
+
+            let config = OutputConfig {
+                unit: SizeUnit::from_str("bytes").unwrap(),
+                ..Default::default()
+            };
+
+            let mut out = Vec::new();
+
+            client.du(config, &mut out).await.unwrap();
+
+            let out = String::from_utf8(out).unwrap();
+            let ret: Vec<&str> = out.lines().collect();
+
+            assert_eq!(ret, expected, "{concurrency}");
+        }
+    }
+
+    // A StubSizer that fails to size the named bucket.
+    struct FailingSizer(StubSizer, &'static str);
+
+    #[async_trait]
+    impl BucketSizer for FailingSizer {
+        async fn buckets(&self) -> Result<Buckets> {
+            self.0.buckets().await
+        }
+
+        async fn bucket_size(&self, bucket: &Bucket) -> Result<BucketSize> {
+            if bucket.name == self.1 {
+                return Err(anyhow!("Access Denied"));
+            }
+
+            self.0.bucket_size(bucket).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_du_keep_going() {
+        let expected = vec![
+            "30\tb-bucket",
+            "20\ta-bucket",
+            "10\td-bucket",
+            "60\t.",
+        ];
+
+        let tests = vec![
+            (false, true),
+            (true,  false),
+        ];
+
+        for test in tests {
+            let keep_going = test.0;
+            let is_ok      = test.1;
+
+            let sizer = FailingSizer(StubSizer(STUB_BUCKETS), "c-bucket");
+
+            let client = Client {
+                keep_going,
+                continue_on_error: true,
+                sizer:             Box::new(sizer),
+                ..stub_client(STUB_BUCKETS)
+            };
+
+            let config = OutputConfig {
+                unit: SizeUnit::from_str("bytes").unwrap(),
+                ..Default::default()
+            };
+
+            let mut out = Vec::new();
+
+            let ret = client.du(config, &mut out).await;
+
+            let out = String::from_utf8(out).unwrap();
+            let lines: Vec<&str> = out.lines().collect();
+
+            // Every other bucket is output either way.
+            assert_eq!(lines, expected, "{keep_going}");
+            assert_eq!(ret.is_ok(), is_ok, "{keep_going}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sizes() {
+        let expected = vec![
+            ("b-bucket", Some(30)),
+            ("c-bucket", None),
+            ("a-bucket", Some(20)),
+            ("d-bucket", Some(10)),
+        ];
+
+        for concurrency in [1, 4] {
+            let sizer = FailingSizer(StubSizer(STUB_BUCKETS), "c-bucket");
+
+            let client = Client {
+                concurrency,
+                sizer: Box::new(sizer),
+                ..stub_client(STUB_BUCKETS)
+            };
+
+            let sizes: Vec<(Bucket, Result<BucketSize>)> = client.sizes()
+                .await
+                .unwrap()
+                .try_collect()
+                .await
+                .unwrap();
+
+            let ret: Vec<(&str, Option<u64>)> = sizes.iter()
+                .map(|(bucket, size)| {
+                    let bytes = size.as_ref().ok().map(|size| size.bytes);
+
+                    (bucket.name.as_str(), bytes)
+                })
+                .collect();
+
+            assert_eq!(ret, expected, "{concurrency}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_discover_up_to() {
+        let tests = vec![
+            (0,   1),
+            (2,   3),
+            (100, 100),
+        ];
+
+        for test in tests {
+            let limit    = test.0;
+            let expected = test.1;
+
+            // Only the first 100 buckets exist.
+            let buckets = stream::iter(0..100)
+                .map(|index| Ok(bucket(&format!("bucket-{index}"))))
+                .boxed();
+
+            let (first, rest) = discover_up_to(buckets, limit).await.unwrap();
+
+            let rest: Vec<Bucket> = rest.try_collect().await.unwrap();
+
+            assert_eq!(first.len(), expected, "{limit}");
+            assert_eq!(first.len() + rest.len(), 100, "{limit}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sizes_confirm_threshold_streams() {
+        // The default for full-account scans in S3 mode, which must not need
+        // every bucket to be discovered before sizing starts.
+        let sizer = StreamingSizer(StubSizer(STUB_BUCKETS));
+
+        let client = Client {
+            confirm_threshold: Some(100),
+            sizer:             Box::new(sizer),
+            ..stub_client(STUB_BUCKETS)
+        };
+
+        let sizes: Vec<(Bucket, Result<BucketSize>)> = client.sizes()
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+
+        let ret: Vec<&str> = sizes.iter()
+            .map(|(bucket, _)| bucket.name.as_str())
+            .collect();
+
+        assert_eq!(ret, vec!["b-bucket", "c-bucket", "a-bucket", "d-bucket"]);
+    }
+
+    #[tokio::test]
+    async fn test_list_buckets() {
+        let sizer = FailingSizer(StubSizer(STUB_BUCKETS), "c-bucket");
+
+        let client = Client {
+            sizer: Box::new(sizer),
+            ..stub_client(STUB_BUCKETS)
+        };
+
+        let mut out = Vec::new();
+
+        // No buckets are sized, so the failing bucket is still listed.
+        client.list_buckets(&mut out).await.unwrap();
+
+        let expected = concat!(
+            "-\tb-bucket\n",
+            "-\tc-bucket\n",
+            "-\ta-bucket\n",
+            "-\td-bucket\n",
+        );
+
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+    }
+
+    #[tokio::test]
+    async fn test_du_cache() {
+        // The buckets have grown since the first run.
+        const GROWN_BUCKETS: &[(&str, u64)] = &[
+            ("b-bucket", 300),
+            ("c-bucket", 100),
+            ("a-bucket", 200),
+            ("d-bucket", 100),
+        ];
+
+        let path = std::env::temp_dir()
+            .join(format!("s3du-test-du-cache-{}", std::process::id()));
+
+        let tests = vec![
+            (STUB_BUCKETS,  None,                           "70\t."),
+            (GROWN_BUCKETS, Some(Duration::from_secs(60)),  "70\t."),
+            (GROWN_BUCKETS, None,                           "700\t."),
+        ];
+
+        for test in tests {
+            let buckets  = test.0;
+            let max_age  = test.1;
+            let expected = test.2;
+
+            let cache = SizeCache::load(path.clone(), max_age, "s3", None);
+
+            let client = Client {
+                cache: Some(cache),
+                ..stub_client(buckets)
+            };
+
+            let config = OutputConfig {
+                unit: SizeUnit::from_str("bytes").unwrap(),
+                ..Default::default()
+            };
+
+            let mut out = Vec::new();
+
+            client.du(config, &mut out).await.unwrap();
+
+            let out = String::from_utf8(out).unwrap();
+
+            assert_eq!(out.lines().last(), Some(expected), "{max_age:?}");
+        }
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_du_sort() {
+        let tests = vec![
+            (None,                 None,                        "b c a d"),
+            (Some(SortKey::Name),  None,                        "a b c d"),
+            (Some(SortKey::Name),  Some(SortOrder::Ascending),  "a b c d"),
+            (Some(SortKey::Name),  Some(SortOrder::Descending), "d c b a"),
+            (Some(SortKey::Size),  None,                        "b a c d"),
+            (Some(SortKey::Size),  Some(SortOrder::Ascending),  "c d a b"),
+            (Some(SortKey::Size),  Some(SortOrder::Descending), "b a c d"),
+        ];
+
+        for test in tests {
+            let sort     = test.0;
+            let order    = test.1;
+            let expected = test.2;
+
+            let config = OutputConfig {
+                order,
+                sort,
+                unit: SizeUnit::from_str("bytes").unwrap(),
+                ..Default::default()
+            };
+
+            let mut out = Vec::new();
+
+            stub_client(STUB_BUCKETS).du(config, &mut out).await.unwrap();
+
+            let out = String::from_utf8(out).unwrap();
+            let mut lines: Vec<&str> = out.lines().collect();
+
+            // The total is always last, whatever the order.
+            assert_eq!(lines.pop(), Some("70\t."));
+
+            let ret: Vec<&str> = lines.iter()
+                .map(|line| {
+                    let (_, name) = line.split_once('\t').unwrap();
+                    name.trim_end_matches("-bucket")
+                })
+                .collect();
+
+            assert_eq!(ret.join(" "), expected, "{sort:?} {order:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_du_top() {
+        let tests = vec![
+            (Some(1), None,                 "b"),
+            (Some(2), None,                 "b a"),
+            (Some(3), None,                 "b a c"),
+            (Some(9), None,                 "b a c d"),
+            (Some(2), Some(SortKey::Name),  "a b"),
+            (None,    None,                 "b c a d"),
+        ];
+
+        for test in tests {
+            let top      = test.0;
+            let sort     = test.1;
+            let expected = test.2;
+
+            let config = OutputConfig {
+                sort,
+                top,
+                unit: SizeUnit::from_str("bytes").unwrap(),
+                ..Default::default()
+            };
+
+            let mut out = Vec::new();
+
+            stub_client(STUB_BUCKETS).du(config, &mut out).await.unwrap();
+
+            let out = String::from_utf8(out).unwrap();
+            let mut lines: Vec<&str> = out.lines().collect();
+
+            // The total always includes every bucket.
+            assert_eq!(lines.pop(), Some("70\t."));
+
+            let ret: Vec<&str> = lines.iter()
+                .map(|line| {
+                    let (_, name) = line.split_once('\t').unwrap();
+                    name.trim_end_matches("-bucket")
+                })
+                .collect();
+
+            assert_eq!(ret.join(" "), expected, "{top:?} {sort:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_du_threshold() {
+        let tests = vec![
+            (None,     None,    "b c a d"),
+            (Some(0),  None,    "b c a d"),
+            (Some(20), None,    "b a"),
+            (Some(21), None,    "b"),
+            (Some(31), None,    ""),
+            (Some(10), Some(2), "b a"),
+        ];
+
+        for test in tests {
+            let threshold = test.0;
+            let top       = test.1;
+            let expected  = test.2;
+
+            let config = OutputConfig {
+                threshold,
+                top,
+                unit: SizeUnit::from_str("bytes").unwrap(),
+                ..Default::default()
+            };
+
+            let mut out = Vec::new();
+
+            stub_client(STUB_BUCKETS).du(config, &mut out).await.unwrap();
+
+            let out = String::from_utf8(out).unwrap();
+            let mut lines: Vec<&str> = out.lines().collect();
+
+            // The total always includes every bucket.
+            assert_eq!(lines.pop(), Some("70\t."));
+
+            let ret: Vec<&str> = lines.iter()
+                .map(|line| {
+                    let (_, name) = line.split_once('\t').unwrap();
+                    name.trim_end_matches("-bucket")
+                })
+                .collect();
+
+            assert_eq!(ret.join(" "), expected, "{threshold:?} {top:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_du_no_buckets() {
+        let template = Template::parse("{bucket}", Placeholder::BUCKET)
+            .unwrap();
+
+        let total_template = Template::parse("{bytes}", Placeholder::TOTAL)
+            .unwrap();
+
+        let tests = vec![
+            (OutputFormat::Json,       None,           None),
+            (OutputFormat::Markdown,   None,           None),
+            (OutputFormat::Prometheus, None,           None),
+            (OutputFormat::Template,   Some(template), Some(total_template)),
+        ];
+
+        for test in tests {
+            let format         = test.0;
+            let template       = test.1;
+            let template_total = test.2;
+
+            let config = OutputConfig {
+                template,
+                template_total,
+                format,
+                unit:   SizeUnit::from_str("bytes").unwrap(),
+                ..Default::default()
+            };
+
+            let mut out = Vec::new();
+
+            stub_client(&[]).du(config, &mut out).await.unwrap();
+
+            let out = String::from_utf8(out).unwrap();
+
+            match format {
+                OutputFormat::Json => {
+                    let ret: serde_json::Value = serde_json::from_str(&out)
+                        .unwrap();
+
+                    assert_eq!(ret["status"], "ok");
+                    assert_eq!(ret["buckets"], serde_json::json!([]));
+                    assert_eq!(ret["total"]["bytes"], 0);
+                    assert_eq!(ret["total"]["bucket_count"], 0);
+                },
+                OutputFormat::Markdown => {
+                    let ret: Vec<&str> = out.lines().collect();
+
+                    assert_eq!(ret, vec![
+                        "| Bucket | Size |",
+                        "| --- | ---: |",
+                        "| **Total** | 0 |",
+                    ]);
+                },
+                OutputFormat::Prometheus => {
+                    let ret: Vec<&str> = out.lines().collect();
+
+                    assert_eq!(ret, vec![
+                        "# HELP s3du_bucket_size_bytes_total Total size of all S3 buckets in bytes.",
+                        "# TYPE s3du_bucket_size_bytes_total gauge",
+                        "s3du_bucket_size_bytes_total 0",
+                    ]);
+                },
+                _ => {
+                    assert_eq!(out, "0\n");
+                },
+            }
+        }
+    }
 }
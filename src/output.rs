@@ -0,0 +1,382 @@
+// Output of bucket sizes in the various supported formats
+#![forbid(unsafe_code)]
+#![deny(missing_docs)]
+use anyhow::Result;
+use aws_smithy_types::date_time::{
+    DateTime,
+    Format,
+};
+use crate::common::{
+    Bucket,
+    BucketSize,
+    OutputConfig,
+    OutputFormat,
+    SortKey,
+    SortOrder,
+};
+use std::collections::BTreeMap;
+use std::io::{
+    self,
+    Write,
+};
+use std::time::{
+    Duration,
+    SystemTime,
+};
+
+/// The number of seconds in a day, used when displaying ages.
+const ONE_DAY_SECS: u64 = 86_400;
+
+/// JSON output.
+mod json;
+
+/// Markdown table output.
+mod markdown;
+
+/// Plain, `du(1)` style, output.
+mod plain;
+
+/// Prometheus text exposition format output.
+mod prometheus;
+
+/// Output using user supplied templates.
+mod template;
+
+pub use json::*;
+pub use markdown::*;
+pub use plain::*;
+pub use prometheus::*;
+pub use template::*;
+
+/// Optional columns that a `Renderer` should output for each bucket.
+///
+/// These are output even when a bucket has no value for them, so that
+/// consumers always see the same columns.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Columns {
+    /// Output the size of each storage class on its own line.
+    pub breakdown: bool,
+
+    /// Output the number of objects.
+    pub count: bool,
+
+    /// Output the time of the `CloudWatch` datapoints that each size was
+    /// taken from.
+    pub datapoint_timestamp: bool,
+
+    /// Output the age of the oldest in-progress multipart upload.
+    pub multipart_age: bool,
+
+    /// Output the size of each storage class.
+    pub storage_classes: bool,
+}
+
+/// Totals of all sized buckets, rendered once every bucket has been sized.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Total {
+    /// Total size of all buckets.
+    pub size: BucketSize,
+
+    /// Number of buckets included in the total.
+    pub bucket_count: usize,
+
+    /// Number of buckets that failed to be sized and were skipped.
+    pub error_count: usize,
+
+    /// Total size of the buckets in each region, if requested.
+    ///
+    /// Buckets without a known region are totalled under `unknown`.
+    pub by_region: Option<BTreeMap<String, u64>>,
+
+    /// Total size of each storage class, if requested.
+    pub by_storage_class: Option<BTreeMap<String, u64>>,
+}
+
+impl Total {
+    /// Return a new, empty, `Total` that will also total the buckets
+    /// `by_region` and `by_storage_class` if requested.
+    pub fn new(by_region: bool, by_storage_class: bool) -> Self {
+        Self {
+            by_region:        by_region.then(BTreeMap::new),
+            by_storage_class: by_storage_class.then(BTreeMap::new),
+            ..Default::default()
+        }
+    }
+
+    /// Add the `size` of the given `bucket` to the totals.
+    pub fn add(&mut self, bucket: &Bucket, size: &BucketSize) {
+        self.size         += size;
+        self.bucket_count += 1;
+
+        if let Some(by_region) = &mut self.by_region {
+            let region = bucket.region
+                .as_ref()
+                .map_or("unknown", |region| region.name());
+
+            *by_region.entry(region.to_string()).or_insert(0) += size.bytes;
+        }
+
+        if let Some(by_storage_class) = &mut self.by_storage_class {
+            for (class, bytes) in size.storage_classes.iter().flatten() {
+                *by_storage_class.entry(class.clone()).or_insert(0) += bytes;
+            }
+        }
+    }
+
+    /// Record that a bucket failed to be sized and was skipped.
+    pub fn add_error(&mut self) {
+        self.error_count += 1;
+    }
+}
+
+/// Returns a `bool` indicating if the given `error` was caused by our output
+/// being closed early, such as when piping into `head(1)`.
+pub fn is_broken_pipe(error: &anyhow::Error) -> bool {
+    error.chain()
+        .filter_map(|e| e.downcast_ref::<io::Error>())
+        .any(|e| e.kind() == io::ErrorKind::BrokenPipe)
+}
+
+/// Sort the sized `buckets` by the given `key`, in the given `order`.
+///
+/// Buckets with equal keys are sorted by name, so that output is stable
+/// between runs.
+pub fn sort_buckets(
+    buckets: &mut [(Bucket, BucketSize)],
+    key: SortKey,
+    order: SortOrder,
+) {
+    buckets.sort_by(|(a, a_size), (b, b_size)| {
+        let ordering = match key {
+            SortKey::Name => a.name.cmp(&b.name),
+            SortKey::Size => a_size.bytes.cmp(&b_size.bytes),
+        };
+
+        let ordering = match order {
+            SortOrder::Ascending  => ordering,
+            SortOrder::Descending => ordering.reverse(),
+        };
+
+        ordering.then_with(|| a.name.cmp(&b.name))
+    });
+}
+
+/// Returns the given `age` in whole days.
+fn age_days(age: Duration) -> u64 {
+    age.as_secs() / ONE_DAY_SECS
+}
+
+/// Returns the given `value` as a `String`, or `-` if there is no value.
+fn or_dash(value: Option<u64>) -> String {
+    value.map_or_else(|| "-".to_string(), |value| value.to_string())
+}
+
+/// Returns the given `time` in RFC 3339 format, or `-` if there is no time.
+fn timestamp_or_dash(time: Option<SystemTime>) -> String {
+    time.and_then(|time| DateTime::from(time).fmt(Format::DateTime).ok())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+/// `Renderer` represents the methods required to output bucket sizes.
+///
+/// Buckets are passed to the renderer one at a time as they are sized, and
+/// `total` is called exactly once when all buckets have been processed.
+pub trait Renderer {
+    /// Render the `size` of a single `bucket` to `out`.
+    fn bucket(
+        &mut self,
+        out: &mut dyn Write,
+        bucket: &Bucket,
+        size: &BucketSize,
+    ) -> Result<()>;
+
+    /// Render the `total` size of all buckets to `out`, completing the
+    /// output.
+    fn total(&mut self, out: &mut dyn Write, total: &Total) -> Result<()>;
+}
+
+/// Return the appropriate `Renderer` for the given `OutputConfig`.
+pub fn renderer(config: &OutputConfig) -> Box<dyn Renderer> {
+    let columns = Columns {
+        breakdown:           config.breakdown,
+        count:               config.count,
+        datapoint_timestamp: config.datapoint_timestamp,
+        multipart_age:       config.multipart_age,
+        storage_classes:     config.storage_classes,
+    };
+
+    let unit = config.unit.clone();
+
+    match config.format {
+        OutputFormat::Json       => {
+            let renderer = JsonRenderer::new(unit, columns)
+                .account_id(config.account_id.clone())
+                .object_versions(config.object_versions.clone())
+                .query(config.mode.clone(), config.region.clone());
+
+            Box::new(renderer)
+        },
+        OutputFormat::Markdown   => {
+            Box::new(MarkdownRenderer::new(unit, columns, config.total))
+        },
+        OutputFormat::Plain      => {
+            Box::new(PlainRenderer::new(unit, columns, config.total))
+        },
+        OutputFormat::Prometheus => Box::new(PrometheusRenderer::new()),
+        OutputFormat::Template   => {
+            // The CLI requires a template when this format is selected.
+            let template = config.template
+                .clone()
+                .expect("template output requires a template");
+
+            let renderer = TemplateRenderer::new(
+                unit,
+                template,
+                config.template_total.clone(),
+            );
+
+            Box::new(renderer)
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{
+        Region,
+        SizeUnit,
+    };
+    use pretty_assertions::assert_eq;
+    use std::str::FromStr;
+
+    // A writer whose reader has gone away.
+    struct ClosedPipe;
+
+    impl Write for ClosedPipe {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            Err(io::ErrorKind::BrokenPipe.into())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Err(io::ErrorKind::BrokenPipe.into())
+        }
+    }
+
+    #[test]
+    fn test_is_broken_pipe() {
+        let bucket = Bucket {
+            name:          "a-bucket".into(),
+            region:        None,
+            storage_types: None,
+            is_versioned:  None,
+            created:       None,
+        };
+
+        let mut renderer = PlainRenderer::new(
+            SizeUnit::from_str("binary").unwrap(),
+            Columns::default(),
+            true,
+        );
+
+        let ret = renderer
+            .bucket(&mut ClosedPipe, &bucket, &BucketSize::from(1))
+            .unwrap_err();
+
+        assert!(is_broken_pipe(&ret));
+
+        let tests = vec![
+            (anyhow::Error::from(io::Error::from(io::ErrorKind::BrokenPipe)), true),
+            (anyhow::Error::from(io::Error::from(io::ErrorKind::NotFound)),   false),
+            (anyhow::anyhow!("Not an I/O error"),                             false),
+        ];
+
+        for test in tests {
+            let error    = test.0;
+            let expected = test.1;
+
+            assert_eq!(is_broken_pipe(&error), expected, "{error}");
+        }
+    }
+
+    #[test]
+    fn test_total_add() {
+        let buckets = vec![
+            (
+                Bucket {
+                    name:          "a-bucket".into(),
+                    region:        Some(Region::new().set_region("eu-west-1")),
+                    storage_types: None,
+                    is_versioned:  None,
+                    created:       None,
+                },
+                BucketSize {
+                    bytes:           10,
+                    storage_classes: Some(BTreeMap::from([
+                        ("StandardIAStorage".into(), 4),
+                        ("StandardStorage".into(),   6),
+                    ])),
+                    ..Default::default()
+                },
+            ),
+            (
+                Bucket {
+                    name:          "b-bucket".into(),
+                    region:        Some(Region::new().set_region("eu-west-1")),
+                    storage_types: None,
+                    is_versioned:  None,
+                    created:       None,
+                },
+                BucketSize {
+                    bytes:           100,
+                    storage_classes: Some(BTreeMap::from([
+                        ("StandardStorage".into(), 100),
+                    ])),
+                    ..Default::default()
+                },
+            ),
+            (
+                Bucket {
+                    name:          "c-bucket".into(),
+                    region:        None,
+                    storage_types: None,
+                    is_versioned:  None,
+                    created:       None,
+                },
+                BucketSize::from(1),
+            ),
+        ];
+
+        let tests = vec![
+            (false, false, None, None),
+            (
+                true,
+                true,
+                Some(BTreeMap::from([
+                    ("eu-west-1".into(), 110),
+                    ("unknown".into(),   1),
+                ])),
+                Some(BTreeMap::from([
+                    ("StandardIAStorage".into(), 4),
+                    ("StandardStorage".into(),   106),
+                ])),
+            ),
+        ];
+
+        for test in tests {
+            let by_region        = test.0;
+            let by_storage_class = test.1;
+
+            let mut total = Total::new(by_region, by_storage_class);
+
+            for (bucket, size) in &buckets {
+                total.add(bucket, size);
+            }
+
+            assert_eq!(total.size.bytes, 111);
+            assert_eq!(total.bucket_count, 3);
+            assert_eq!(total.by_region, test.2);
+            assert_eq!(total.by_storage_class, test.3);
+        }
+    }
+}
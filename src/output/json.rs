@@ -0,0 +1,1284 @@
+// JSON output
+#![forbid(unsafe_code)]
+#![deny(missing_docs)]
+use anyhow::Result;
+use aws_smithy_types::date_time::{
+    DateTime,
+    Format,
+};
+use crate::common::{
+    Bucket,
+    BucketSize,
+    HumanSize,
+    MetricDatapoint,
+    SizeUnit,
+};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::Write;
+use super::{
+    age_days,
+    Columns,
+    Renderer,
+    Total,
+};
+
+/// A raw `CloudWatch` datapoint in the JSON output.
+#[derive(Debug, Serialize)]
+struct JsonDatapoint {
+    /// The statistic that the value is, such as `average`.
+    statistic: String,
+
+    /// The value of the statistic of the metric.
+    value: f64,
+
+    /// Time that the datapoint was recorded, in RFC 3339 format, if known.
+    timestamp: Option<String>,
+
+    /// The unit of the value, if known.
+    unit: Option<String>,
+}
+
+impl TryFrom<&MetricDatapoint> for JsonDatapoint {
+    type Error = anyhow::Error;
+
+    fn try_from(datapoint: &MetricDatapoint) -> Result<Self> {
+        let timestamp = datapoint.timestamp
+            .map(|timestamp| DateTime::from(timestamp).fmt(Format::DateTime))
+            .transpose()?;
+
+        let datapoint = Self {
+            statistic: datapoint.statistic.clone(),
+            value:     datapoint.value,
+            timestamp,
+            unit:      datapoint.unit.clone(),
+        };
+
+        Ok(datapoint)
+    }
+}
+
+/// A single bucket in the JSON output.
+#[derive(Debug, Serialize)]
+struct JsonBucket {
+    /// Name of the bucket.
+    bucket: String,
+
+    /// ARN of the bucket.
+    arn: String,
+
+    /// Time that the bucket was created, in RFC 3339 format.
+    ///
+    /// This is currently only known in S3 mode and is output as `null`
+    /// otherwise.
+    created: Option<String>,
+
+    /// Size of the bucket in bytes.
+    bytes: u64,
+
+    /// Whether the size is an estimate.
+    estimated: bool,
+
+    /// Size of the bucket in the selected unit.
+    human: String,
+
+    /// The S3 object versions that were sized, if sizes came from S3.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    object_versions: Option<String>,
+
+    /// Number of objects in the bucket, if counting was requested.
+    ///
+    /// A count that was requested but isn't known is output as `null`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    object_count: Option<Option<u64>>,
+
+    /// Average size of the objects in the bucket in bytes, rounded down, if
+    /// counting was requested.
+    ///
+    /// This is output as `null` if the count isn't known or is 0.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bytes_per_object: Option<Option<u64>>,
+
+    /// Region of the bucket, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    region: Option<String>,
+
+    /// Storage types used by the bucket, if known.
+    ///
+    /// This is currently only known in `CloudWatch` mode.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    storage_types: Option<Vec<String>>,
+
+    /// Size of each storage class used by the bucket, if a breakdown was
+    /// requested.
+    ///
+    /// A breakdown that was requested but isn't known is output as `null`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    storage_classes: Option<Option<BTreeMap<String, u64>>>,
+
+    /// The latest raw `CloudWatch` datapoint used for each storage type, if
+    /// datapoints were requested.
+    ///
+    /// This is currently only known in `CloudWatch` mode.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    datapoints: Option<BTreeMap<String, JsonDatapoint>>,
+
+    /// Whether the bucket has versioning enabled or suspended, if known.
+    ///
+    /// This is currently only looked up in S3 mode when sizing non-current
+    /// object versions, or current objects with `--warn-versioned`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    versioned: Option<bool>,
+
+    /// Size of current object versions, if a breakdown was requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    current_bytes: Option<u64>,
+
+    /// Size of non-current object versions, if a breakdown was requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    noncurrent_bytes: Option<u64>,
+
+    /// Size of all object versions, current and non-current, when `All`
+    /// object versions were sized without a breakdown.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    versions_bytes: Option<u64>,
+
+    /// Size of in-progress multipart uploads, if a breakdown was requested or
+    /// `All` object versions were sized.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    multipart_bytes: Option<u64>,
+
+    /// Age in days of the oldest in-progress multipart upload, if ages were
+    /// requested.
+    ///
+    /// This is output as `null` if there are no in-progress uploads.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    oldest_multipart_age_days: Option<Option<u64>>,
+
+    /// Time that the newest current object was last modified, in RFC 3339
+    /// format, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    newest_object: Option<String>,
+
+    /// Time of the `CloudWatch` datapoints that the size was taken from, in
+    /// RFC 3339 format, if timestamps were requested.
+    ///
+    /// This is output as `null` if the time isn't known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    datapoint_timestamp: Option<Option<String>>,
+}
+
+/// The overall status of a run in the JSON output.
+#[derive(Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum JsonStatus {
+    /// Every bucket was sized.
+    Ok,
+
+    /// Some buckets were sized, but others failed and were skipped.
+    Partial,
+
+    /// Every bucket failed and was skipped.
+    Failed,
+}
+
+impl From<&Total> for JsonStatus {
+    fn from(total: &Total) -> Self {
+        match (total.bucket_count, total.error_count) {
+            (_, 0) => Self::Ok,
+            (0, _) => Self::Failed,
+            _      => Self::Partial,
+        }
+    }
+}
+
+/// The total of all buckets in the JSON output.
+#[derive(Debug, Serialize)]
+struct JsonTotal {
+    /// Total size of all buckets in bytes.
+    bytes: u64,
+
+    /// Total size of all buckets in the selected unit.
+    human: String,
+
+    /// Name of the unit used for `human` sizes.
+    unit: &'static str,
+
+    /// Number of buckets included in the total.
+    bucket_count: usize,
+}
+
+/// The JSON document written once all buckets are sized.
+#[derive(Debug, Serialize)]
+struct JsonDocument<'a> {
+    /// Whether every bucket was sized, see `JsonStatus`.
+    status: JsonStatus,
+
+    /// Number of buckets that failed to be sized and were skipped.
+    error_count: usize,
+
+    /// ID of the AWS account that the buckets belong to, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    account_id: Option<&'a str>,
+
+    /// Mode that the buckets were sized in, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mode: Option<&'a str>,
+
+    /// Region that the buckets were sized in, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    region: Option<&'a str>,
+
+    /// All of the sized buckets.
+    buckets: &'a [JsonBucket],
+
+    /// Whether any of the bucket sizes, and so the total, is an estimate.
+    estimated: bool,
+
+    /// Total of all buckets.
+    total: JsonTotal,
+
+    /// Total number of objects in all buckets, if counting was requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    total_object_count: Option<Option<u64>>,
+
+    /// Total size of the buckets in each region, if requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    by_region: Option<&'a BTreeMap<String, u64>>,
+
+    /// Total size of each storage class, if requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    by_storage_class: Option<&'a BTreeMap<String, u64>>,
+}
+
+/// Renders bucket sizes as a single JSON document.
+///
+/// As the document can only be written once everything is known, buckets are
+/// collected until the total is rendered.
+pub struct JsonRenderer {
+    /// ID of the AWS account that the buckets belong to, if known.
+    account_id: Option<String>,
+
+    /// Buckets collected so far.
+    buckets: Vec<JsonBucket>,
+
+    /// Optional fields that will be included.
+    columns: Columns,
+
+    /// Mode that the buckets were sized in, if known.
+    mode: Option<String>,
+
+    /// The S3 object versions that were sized, if sizes came from S3.
+    object_versions: Option<String>,
+
+    /// Region that the buckets were sized in, if known.
+    region: Option<String>,
+
+    /// Unit that the `human` sizes will be displayed in.
+    unit: SizeUnit,
+}
+
+impl JsonRenderer {
+    /// Return a new `JsonRenderer` displaying `human` sizes in the given
+    /// `unit`, with any optional `columns`.
+    pub fn new(unit: SizeUnit, columns: Columns) -> Self {
+        Self {
+            columns,
+            unit,
+            account_id:      None,
+            buckets:         Vec::new(),
+            mode:            None,
+            object_versions: None,
+            region:          None,
+        }
+    }
+
+    /// Return the `JsonRenderer` including the given `account_id` in the
+    /// document.
+    pub fn account_id(self, account_id: Option<String>) -> Self {
+        Self {
+            account_id,
+            ..self
+        }
+    }
+
+    /// Return the `JsonRenderer` including the given `object_versions` in
+    /// each bucket.
+    pub fn object_versions(self, object_versions: Option<String>) -> Self {
+        Self {
+            object_versions,
+            ..self
+        }
+    }
+
+    /// Return the `JsonRenderer` including the `mode` and `region` that the
+    /// buckets were sized in, so that the document records how it was
+    /// produced.
+    pub fn query(self, mode: Option<String>, region: Option<String>) -> Self {
+        Self {
+            mode,
+            region,
+            ..self
+        }
+    }
+
+    /// Return the JSON document for the collected buckets and `total`.
+    fn document(&self, total: &Total) -> Result<String> {
+        let size = &total.size;
+
+        let json_total = JsonTotal {
+            bytes:        size.bytes,
+            human:        size.bytes.humansize(&self.unit),
+            unit:         self.unit.name(),
+            bucket_count: total.bucket_count,
+        };
+
+        let document = JsonDocument {
+            status:             JsonStatus::from(total),
+            error_count:        total.error_count,
+            account_id:         self.account_id.as_deref(),
+            mode:               self.mode.as_deref(),
+            region:             self.region.as_deref(),
+            buckets:            &self.buckets,
+            estimated:          size.estimated,
+            total:              json_total,
+            total_object_count: self.columns.count.then_some(size.count),
+            by_region:          total.by_region.as_ref(),
+            by_storage_class:   total.by_storage_class.as_ref(),
+        };
+
+        let document = serde_json::to_string_pretty(&document)?;
+
+        Ok(document)
+    }
+}
+
+impl Renderer for JsonRenderer {
+    /// Collect the bucket, nothing is output until the total is rendered.
+    fn bucket(
+        &mut self,
+        _out: &mut dyn Write,
+        bucket: &Bucket,
+        size: &BucketSize,
+    ) -> Result<()> {
+        let versions = size.versions.as_ref();
+        let split    = size.multipart_split.as_ref();
+
+        // Storage types are in discovery order, sort them so that output is
+        // stable between runs.
+        let storage_types = bucket.storage_types.clone()
+            .map(|mut storage_types| {
+                storage_types.sort();
+                storage_types
+            });
+
+        let created = bucket.created
+            .map(|created| DateTime::from(created).fmt(Format::DateTime))
+            .transpose()?;
+
+        let newest_object = size.newest_object
+            .map(|newest| DateTime::from(newest).fmt(Format::DateTime))
+            .transpose()?;
+
+        let datapoint_timestamp = size.datapoint_timestamp
+            .map(|timestamp| DateTime::from(timestamp).fmt(Format::DateTime))
+            .transpose()?;
+
+        let datapoints = size.datapoints
+            .as_ref()
+            .map(|datapoints| {
+                datapoints.iter()
+                    .map(|(storage_type, datapoint)| {
+                        let datapoint = JsonDatapoint::try_from(datapoint)?;
+
+                        Ok((storage_type.clone(), datapoint))
+                    })
+                    .collect::<Result<BTreeMap<_, _>>>()
+            })
+            .transpose()?;
+
+        let bucket = JsonBucket {
+            bucket:           bucket.name.clone(),
+            arn:              bucket.arn(),
+            created,
+            bytes:            size.bytes,
+            estimated:        size.estimated,
+            human:            size.bytes.humansize(&self.unit),
+            object_versions:  self.object_versions.clone(),
+            object_count:     self.columns.count.then_some(size.count),
+            bytes_per_object: self.columns.count.then_some(
+                size.count.and_then(|count| size.bytes.checked_div(count)),
+            ),
+            region:           bucket.region.as_ref().map(|r| r.name().into()),
+            storage_types,
+            storage_classes:  self.columns.storage_classes
+                .then(|| size.storage_classes.clone()),
+            datapoints,
+            versioned:        bucket.is_versioned,
+            current_bytes:    versions.map(|v| v.current),
+            noncurrent_bytes: versions.map(|v| v.noncurrent),
+            versions_bytes:   split.map(|s| s.versions),
+            multipart_bytes:  versions.map(|v| v.multipart)
+                .or_else(|| split.map(|s| s.multipart)),
+
+            oldest_multipart_age_days: self.columns.multipart_age
+                .then_some(size.multipart_age.map(age_days)),
+
+            newest_object,
+
+            datapoint_timestamp: self.columns.datapoint_timestamp
+                .then_some(datapoint_timestamp),
+        };
+
+        self.buckets.push(bucket);
+
+        Ok(())
+    }
+
+    fn total(&mut self, out: &mut dyn Write, total: &Total) -> Result<()> {
+        writeln!(out, "{}", self.document(total)?)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{
+        MultipartSplit,
+        Region,
+        VersionsBreakdown,
+    };
+    use pretty_assertions::assert_eq;
+    use serde_json::{
+        json,
+        Value,
+    };
+    use std::io;
+    use std::str::FromStr;
+    use std::time::{
+        Duration,
+        SystemTime,
+    };
+
+    #[test]
+    fn test_document_aggregates() {
+        let renderer = JsonRenderer::new(
+            SizeUnit::from_str("binary").unwrap(),
+            Columns::default(),
+        );
+
+        let region = Region::new().set_region("eu-west-1");
+
+        let buckets = vec![
+            (
+                Bucket {
+                    name:          "a-bucket".into(),
+                    region:        Some(region.clone()),
+                    storage_types: Some(vec!["StandardStorage".into()]),
+                    is_versioned:  None,
+                    created:       None,
+                },
+                BucketSize {
+                    bytes:           1024,
+                    estimated:       true,
+                    storage_classes: Some(BTreeMap::from([
+                        ("StandardStorage".into(), 1024),
+                    ])),
+                    ..Default::default()
+                },
+            ),
+            (
+                Bucket {
+                    name:          "b-bucket".into(),
+                    region:        Some(region),
+                    storage_types: Some(vec![
+                        "StandardIAStorage".into(),
+                        "StandardStorage".into(),
+                    ]),
+                    is_versioned:  None,
+                    created:       None,
+                },
+                BucketSize {
+                    bytes:           7,
+                    estimated:       true,
+                    storage_classes: Some(BTreeMap::from([
+                        ("StandardIAStorage".into(), 4),
+                        ("StandardStorage".into(),   3),
+                    ])),
+                    ..Default::default()
+                },
+            ),
+        ];
+
+        let mut total = Total::new(true, true);
+
+        for (bucket, size) in &buckets {
+            total.add(bucket, size);
+        }
+
+        let ret = renderer.document(&total).unwrap();
+        let ret: Value = serde_json::from_str(&ret).unwrap();
+
+        let expected = json!({
+            "status":      "ok",
+            "error_count": 0,
+            "buckets":   [],
+            "estimated": true,
+            "total":     {
+                "bytes":        1031,
+                "human":        "1.01KiB",
+                "unit":         "binary",
+                "bucket_count": 2,
+            },
+            "by_region": {
+                "eu-west-1": 1031,
+            },
+            "by_storage_class": {
+                "StandardIAStorage": 4,
+                "StandardStorage":   1027,
+            },
+        });
+
+        assert_eq!(ret, expected);
+    }
+
+    #[test]
+    fn test_document() {
+        let mut renderer = JsonRenderer::new(
+            SizeUnit::from_str("binary").unwrap(),
+            Columns::default(),
+        );
+
+        let buckets = vec![
+            (
+                Bucket {
+                    name:          "a-bucket".into(),
+                    region:        Some(Region::new().set_region("eu-west-1")),
+                    storage_types: None,
+                    is_versioned:  Some(true),
+                    created:       None,
+                },
+                BucketSize {
+                    bytes:    7,
+                    versions: Some(VersionsBreakdown {
+                        current:    1,
+                        multipart:  4,
+                        noncurrent: 2,
+                    }),
+                    ..Default::default()
+                },
+            ),
+            (
+                Bucket {
+                    name:          "b-bucket".into(),
+                    region:        None,
+                    storage_types: None,
+                    is_versioned:  None,
+                    created:       None,
+                },
+                BucketSize::from(1024),
+            ),
+        ];
+
+        let mut total = Total::default();
+
+        for (bucket, size) in &buckets {
+            total.add(bucket, size);
+            renderer.bucket(&mut io::sink(), bucket, size).unwrap();
+        }
+
+        let ret = renderer.document(&total).unwrap();
+        let ret: Value = serde_json::from_str(&ret).unwrap();
+
+        let expected = json!({
+            "status":      "ok",
+            "error_count": 0,
+            "buckets": [
+                {
+                    "bucket":           "a-bucket",
+                    "arn":              "arn:aws:s3:::a-bucket",
+                    "created":          null,
+                    "bytes":            7,
+                    "estimated":        false,
+                    "human":            "7B",
+                    "region":           "eu-west-1",
+                    "versioned":        true,
+                    "current_bytes":    1,
+                    "noncurrent_bytes": 2,
+                    "multipart_bytes":  4,
+                },
+                {
+                    "bucket":    "b-bucket",
+                    "arn":       "arn:aws:s3:::b-bucket",
+                    "created":   null,
+                    "bytes":     1024,
+                    "estimated": false,
+                    "human":     "1KiB",
+                },
+            ],
+            "estimated": false,
+            "total":     {
+                "bytes":        1031,
+                "human":        "1.01KiB",
+                "unit":         "binary",
+                "bucket_count": 2,
+            },
+        });
+
+        assert_eq!(ret, expected);
+    }
+
+    #[test]
+    fn test_document_multipart_split() {
+        let mut renderer = JsonRenderer::new(
+            SizeUnit::from_str("bytes").unwrap(),
+            Columns::default(),
+        );
+
+        let bucket = Bucket {
+            name:          "a-bucket".into(),
+            region:        None,
+            storage_types: None,
+            is_versioned:  None,
+            created:       None,
+        };
+
+        let size = BucketSize {
+            bytes:           7,
+            multipart_split: Some(MultipartSplit {
+                multipart: 4,
+                versions:  3,
+            }),
+            ..Default::default()
+        };
+
+        let mut total = Total::default();
+        total.add(&bucket, &size);
+        renderer.bucket(&mut io::sink(), &bucket, &size).unwrap();
+
+        let ret = renderer.document(&total).unwrap();
+        let ret: Value = serde_json::from_str(&ret).unwrap();
+
+        let bucket = &ret["buckets"][0];
+
+        assert_eq!(bucket["bytes"], json!(7));
+        assert_eq!(bucket["versions_bytes"], json!(3));
+        assert_eq!(bucket["multipart_bytes"], json!(4));
+    }
+
+    #[test]
+    fn test_document_count() {
+        let columns = Columns {
+            count: true,
+            ..Default::default()
+        };
+
+        let mut renderer = JsonRenderer::new(
+            SizeUnit::from_str("binary").unwrap(),
+            columns,
+        );
+
+        let buckets = vec![
+            (
+                Bucket {
+                    name:          "a-bucket".into(),
+                    region:        None,
+                    storage_types: Some(vec!["StandardStorage".into()]),
+                    is_versioned:  None,
+                    created:       None,
+                },
+                BucketSize {
+                    bytes:     0,
+                    count:     Some(0),
+                    estimated: true,
+                    ..Default::default()
+                },
+            ),
+            (
+                Bucket {
+                    name:          "b-bucket".into(),
+                    region:        None,
+                    storage_types: Some(vec![
+                        "StandardStorage".into(),
+                        "StandardIAStorage".into(),
+                    ]),
+                    is_versioned:  None,
+                    created:       None,
+                },
+                BucketSize {
+                    bytes:     1024,
+                    estimated: true,
+                    ..Default::default()
+                },
+            ),
+        ];
+
+        let mut total = Total::default();
+
+        for (bucket, size) in &buckets {
+            total.add(bucket, size);
+            renderer.bucket(&mut io::sink(), bucket, size).unwrap();
+        }
+
+        let ret = renderer.document(&total).unwrap();
+        let ret: Value = serde_json::from_str(&ret).unwrap();
+
+        let expected = json!({
+            "status":      "ok",
+            "error_count": 0,
+            "buckets": [
+                {
+                    "bucket":           "a-bucket",
+                    "arn":              "arn:aws:s3:::a-bucket",
+                    "created":          null,
+                    "bytes":            0,
+                    "estimated":        true,
+                    "human":            "0B",
+                    "object_count":     0,
+                    "bytes_per_object": null,
+                    "storage_types":    ["StandardStorage"],
+                },
+                {
+                    "bucket":           "b-bucket",
+                    "arn":              "arn:aws:s3:::b-bucket",
+                    "created":          null,
+                    "bytes":            1024,
+                    "estimated":        true,
+                    "human":            "1KiB",
+                    "object_count":     null,
+                    "bytes_per_object": null,
+                    "storage_types":    ["StandardIAStorage", "StandardStorage"],
+                },
+            ],
+            "estimated":          true,
+            "total":              {
+                "bytes":        1024,
+                "human":        "1KiB",
+                "unit":         "binary",
+                "bucket_count": 2,
+            },
+            "total_object_count": 0,
+        });
+
+        assert_eq!(ret, expected);
+    }
+
+    #[test]
+    fn test_document_bytes_per_object() {
+        let tests = vec![
+            (1024, Some(4), json!(256)),
+            (1000, Some(3), json!(333)),
+            (1024, Some(0), json!(null)),
+            (1024, None,    json!(null)),
+        ];
+
+        for test in tests {
+            let bytes    = test.0;
+            let count    = test.1;
+            let expected = test.2;
+
+            let columns = Columns {
+                count: true,
+                ..Default::default()
+            };
+
+            let mut renderer = JsonRenderer::new(
+                SizeUnit::from_str("bytes").unwrap(),
+                columns,
+            );
+
+            let bucket = Bucket {
+                name:          "a-bucket".into(),
+                region:        None,
+                storage_types: None,
+                is_versioned:  None,
+                created:       None,
+            };
+
+            let size = BucketSize {
+                bytes,
+                count,
+                ..Default::default()
+            };
+
+            let mut total = Total::default();
+            total.add(&bucket, &size);
+            renderer.bucket(&mut io::sink(), &bucket, &size).unwrap();
+
+            let ret = renderer.document(&total).unwrap();
+            let ret: Value = serde_json::from_str(&ret).unwrap();
+
+            assert_eq!(ret["buckets"][0]["bytes_per_object"], expected);
+        }
+    }
+
+    #[test]
+    fn test_document_storage_classes() {
+        let tests = vec![
+            (
+                false,
+                Some(BTreeMap::from([("STANDARD".into(), 7)])),
+                None,
+            ),
+            (
+                true,
+                Some(BTreeMap::from([
+                    ("GLACIER".into(),  4),
+                    ("STANDARD".into(), 3),
+                ])),
+                Some(json!({"GLACIER": 4, "STANDARD": 3})),
+            ),
+            (
+                true,
+                None,
+                Some(json!(null)),
+            ),
+        ];
+
+        for test in tests {
+            let breakdown       = test.0;
+            let storage_classes = test.1;
+            let expected        = test.2;
+
+            let columns = Columns {
+                storage_classes: breakdown,
+                ..Default::default()
+            };
+
+            let mut renderer = JsonRenderer::new(
+                SizeUnit::from_str("bytes").unwrap(),
+                columns,
+            );
+
+            let bucket = Bucket {
+                name:          "a-bucket".into(),
+                region:        None,
+                storage_types: None,
+                is_versioned:  None,
+                created:       None,
+            };
+
+            let size = BucketSize {
+                bytes: 7,
+                storage_classes,
+                ..Default::default()
+            };
+
+            let mut total = Total::default();
+            total.add(&bucket, &size);
+            renderer.bucket(&mut io::sink(), &bucket, &size).unwrap();
+
+            let ret = renderer.document(&total).unwrap();
+            let ret: Value = serde_json::from_str(&ret).unwrap();
+
+            let ret = ret["buckets"][0].get("storage_classes");
+
+            assert_eq!(ret, expected.as_ref());
+        }
+    }
+
+    #[test]
+    fn test_document_multipart_age() {
+        let columns = Columns {
+            multipart_age: true,
+            ..Default::default()
+        };
+
+        let mut renderer = JsonRenderer::new(
+            SizeUnit::from_str("binary").unwrap(),
+            columns,
+        );
+
+        let buckets = vec![
+            (
+                Bucket {
+                    name:          "a-bucket".into(),
+                    region:        None,
+                    storage_types: None,
+                    is_versioned:  None,
+                    created:       None,
+                },
+                BucketSize {
+                    bytes:         1024,
+                    multipart_age: Some(Duration::from_secs(10 * 86_400)),
+                    ..Default::default()
+                },
+            ),
+            (
+                Bucket {
+                    name:          "b-bucket".into(),
+                    region:        None,
+                    storage_types: None,
+                    is_versioned:  None,
+                    created:       None,
+                },
+                BucketSize::from(0),
+            ),
+        ];
+
+        let mut total = Total::default();
+
+        for (bucket, size) in &buckets {
+            total.add(bucket, size);
+            renderer.bucket(&mut io::sink(), bucket, size).unwrap();
+        }
+
+        let ret = renderer.document(&total).unwrap();
+        let ret: Value = serde_json::from_str(&ret).unwrap();
+
+        let expected = json!({
+            "status":      "ok",
+            "error_count": 0,
+            "buckets": [
+                {
+                    "bucket":                    "a-bucket",
+                    "arn":                       "arn:aws:s3:::a-bucket",
+                    "created":                   null,
+                    "bytes":                     1024,
+                    "estimated":                 false,
+                    "human":                     "1KiB",
+                    "oldest_multipart_age_days": 10,
+                },
+                {
+                    "bucket":                    "b-bucket",
+                    "arn":                       "arn:aws:s3:::b-bucket",
+                    "created":                   null,
+                    "bytes":                     0,
+                    "estimated":                 false,
+                    "human":                     "0B",
+                    "oldest_multipart_age_days": null,
+                },
+            ],
+            "estimated": false,
+            "total":     {
+                "bytes":        1024,
+                "human":        "1KiB",
+                "unit":         "binary",
+                "bucket_count": 2,
+            },
+        });
+
+        assert_eq!(ret, expected);
+    }
+
+    #[test]
+    fn test_document_newest_object() {
+        let mut renderer = JsonRenderer::new(
+            SizeUnit::from_str("bytes").unwrap(),
+            Columns::default(),
+        );
+
+        let bucket = Bucket {
+            name:          "a-bucket".into(),
+            region:        None,
+            storage_types: None,
+            is_versioned:  None,
+            created:       None,
+        };
+
+        let size = BucketSize {
+            bytes:         7,
+            newest_object: Some(
+                SystemTime::UNIX_EPOCH + Duration::from_secs(1_584_011_049),
+            ),
+            ..Default::default()
+        };
+
+        let mut total = Total::default();
+        total.add(&bucket, &size);
+        renderer.bucket(&mut io::sink(), &bucket, &size).unwrap();
+
+        let ret = renderer.document(&total).unwrap();
+        let ret: Value = serde_json::from_str(&ret).unwrap();
+
+        assert_eq!(
+            ret["buckets"][0]["newest_object"],
+            json!("2020-03-12T11:04:09Z"),
+        );
+    }
+
+    #[test]
+    fn test_document_datapoint_timestamp() {
+        let measured = BucketSize {
+            bytes:               7,
+            datapoint_timestamp: Some(
+                SystemTime::UNIX_EPOCH + Duration::from_secs(1_583_096_340),
+            ),
+            ..Default::default()
+        };
+
+        let tests = vec![
+            (false, measured.clone(),    None),
+            (true,  measured,            Some(json!("2020-03-01T20:59:00Z"))),
+            (true,  BucketSize::from(7), Some(Value::Null)),
+        ];
+
+        for test in tests {
+            let datapoint_timestamp = test.0;
+            let size                = test.1;
+            let expected            = test.2;
+
+            let columns = Columns {
+                datapoint_timestamp,
+                ..Default::default()
+            };
+
+            let mut renderer = JsonRenderer::new(
+                SizeUnit::from_str("bytes").unwrap(),
+                columns,
+            );
+
+            let bucket = Bucket {
+                name:          "a-bucket".into(),
+                region:        None,
+                storage_types: None,
+                is_versioned:  None,
+                created:       None,
+            };
+
+            let mut total = Total::default();
+            total.add(&bucket, &size);
+            renderer.bucket(&mut io::sink(), &bucket, &size).unwrap();
+
+            let ret = renderer.document(&total).unwrap();
+            let ret: Value = serde_json::from_str(&ret).unwrap();
+
+            let ret = ret["buckets"][0].get("datapoint_timestamp");
+
+            assert_eq!(ret, expected.as_ref());
+        }
+    }
+
+    #[test]
+    fn test_document_datapoints() {
+        let timestamp = SystemTime::UNIX_EPOCH
+            + Duration::from_secs(1_583_096_340);
+
+        let tests = vec![
+            (None, json!(null)),
+            (
+                Some(BTreeMap::from([
+                    ("StandardStorage".into(), MetricDatapoint {
+                        statistic: "average".into(),
+                        timestamp: Some(timestamp),
+                        unit:      Some("Bytes".into()),
+                        value:     123_456_789.0,
+                    }),
+                ])),
+                json!({
+                    "StandardStorage": {
+                        "statistic": "average",
+                        "value":     123_456_789.0,
+                        "timestamp": "2020-03-01T20:59:00Z",
+                        "unit":      "Bytes",
+                    },
+                }),
+            ),
+        ];
+
+        for test in tests {
+            let datapoints = test.0;
+            let expected   = test.1;
+
+            let mut renderer = JsonRenderer::new(
+                SizeUnit::from_str("bytes").unwrap(),
+                Columns::default(),
+            );
+
+            let bucket = Bucket {
+                name:          "a-bucket".into(),
+                region:        None,
+                storage_types: None,
+                is_versioned:  None,
+                created:       None,
+            };
+
+            let size = BucketSize {
+                bytes: 123_456_789,
+                datapoints,
+                ..Default::default()
+            };
+
+            let mut total = Total::default();
+            total.add(&bucket, &size);
+            renderer.bucket(&mut io::sink(), &bucket, &size).unwrap();
+
+            let ret = renderer.document(&total).unwrap();
+            let ret: Value = serde_json::from_str(&ret).unwrap();
+
+            assert_eq!(ret["buckets"][0]["datapoints"], expected);
+        }
+    }
+
+    #[test]
+    fn test_document_created() {
+        let tests = vec![
+            (None,                json!(null)),
+            (Some(1_584_010_652), json!("2020-03-12T10:57:32Z")),
+        ];
+
+        for test in tests {
+            let created  = test.0;
+            let expected = test.1;
+
+            let mut renderer = JsonRenderer::new(
+                SizeUnit::from_str("bytes").unwrap(),
+                Columns::default(),
+            );
+
+            let bucket = Bucket {
+                name:          "a-bucket".into(),
+                region:        None,
+                storage_types: None,
+                is_versioned:  None,
+                created:       created.map(|secs| {
+                    SystemTime::UNIX_EPOCH + Duration::from_secs(secs)
+                }),
+            };
+
+            let size = BucketSize::from(7);
+
+            let mut total = Total::default();
+            total.add(&bucket, &size);
+            renderer.bucket(&mut io::sink(), &bucket, &size).unwrap();
+
+            let ret = renderer.document(&total).unwrap();
+            let ret: Value = serde_json::from_str(&ret).unwrap();
+
+            assert_eq!(ret["buckets"][0]["created"], expected);
+        }
+    }
+
+    #[test]
+    fn test_document_account_id() {
+        let tests = vec![
+            (None,                        None),
+            (Some("123456789012".into()), Some(json!("123456789012"))),
+        ];
+
+        for test in tests {
+            let account_id = test.0;
+            let expected   = test.1;
+
+            let renderer = JsonRenderer::new(
+                SizeUnit::from_str("bytes").unwrap(),
+                Columns::default(),
+            )
+            .account_id(account_id);
+
+            let ret = renderer.document(&Total::default()).unwrap();
+            let ret: Value = serde_json::from_str(&ret).unwrap();
+
+            assert_eq!(ret.get("account_id"), expected.as_ref());
+        }
+    }
+
+    #[test]
+    fn test_document_query() {
+        let tests = vec![
+            (
+                None,
+                None,
+                None,
+                None,
+            ),
+            (
+                Some("s3".into()),
+                Some("eu-west-1".into()),
+                Some(json!("s3")),
+                Some(json!("eu-west-1")),
+            ),
+        ];
+
+        for test in tests {
+            let mode            = test.0;
+            let region          = test.1;
+            let expected_mode   = test.2;
+            let expected_region = test.3;
+
+            let renderer = JsonRenderer::new(
+                SizeUnit::from_str("bytes").unwrap(),
+                Columns::default(),
+            )
+            .query(mode, region);
+
+            let ret = renderer.document(&Total::default()).unwrap();
+            let ret: Value = serde_json::from_str(&ret).unwrap();
+
+            assert_eq!(ret.get("mode"), expected_mode.as_ref());
+            assert_eq!(ret.get("region"), expected_region.as_ref());
+        }
+    }
+
+    #[test]
+    fn test_document_object_versions() {
+        let tests = vec![
+            (None,                       None),
+            (Some("non-current".into()), Some(json!("non-current"))),
+        ];
+
+        for test in tests {
+            let object_versions = test.0;
+            let expected        = test.1;
+
+            let mut renderer = JsonRenderer::new(
+                SizeUnit::from_str("bytes").unwrap(),
+                Columns::default(),
+            )
+            .object_versions(object_versions);
+
+            let bucket = Bucket {
+                name:          "a-bucket".into(),
+                region:        None,
+                storage_types: None,
+                is_versioned:  None,
+                created:       None,
+            };
+
+            renderer
+                .bucket(&mut io::sink(), &bucket, &BucketSize::from(1))
+                .unwrap();
+
+            let ret = renderer.document(&Total::default()).unwrap();
+            let ret: Value = serde_json::from_str(&ret).unwrap();
+            let ret = ret["buckets"][0].get("object_versions");
+
+            assert_eq!(ret, expected.as_ref());
+        }
+    }
+
+    #[test]
+    fn test_document_status() {
+        let tests = vec![
+            (0, 0, "ok"),
+            (2, 0, "ok"),
+            (2, 1, "partial"),
+            (0, 1, "failed"),
+        ];
+
+        for test in tests {
+            let bucket_count = test.0;
+            let error_count  = test.1;
+            let expected     = test.2;
+
+            let renderer = JsonRenderer::new(
+                SizeUnit::from_str("bytes").unwrap(),
+                Columns::default(),
+            );
+
+            let total = Total {
+                bucket_count,
+                error_count,
+                ..Default::default()
+            };
+
+            let ret = renderer.document(&total).unwrap();
+            let ret: Value = serde_json::from_str(&ret).unwrap();
+
+            assert_eq!(ret["status"], json!(expected));
+            assert_eq!(ret["error_count"], json!(error_count));
+        }
+    }
+}
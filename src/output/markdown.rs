@@ -0,0 +1,249 @@
+// Markdown table output
+#![forbid(unsafe_code)]
+#![deny(missing_docs)]
+use anyhow::Result;
+use crate::common::{
+    Bucket,
+    BucketSize,
+    HumanSize,
+    SizeUnit,
+};
+use std::io::Write;
+use super::{
+    age_days,
+    or_dash,
+    timestamp_or_dash,
+    Columns,
+    Renderer,
+    Total,
+};
+
+/// Renders bucket sizes as a Markdown table.
+///
+/// The table header depends on whether any bucket has a version breakdown, so
+/// buckets are collected until the total is rendered.
+pub struct MarkdownRenderer {
+    /// Names and sizes of the buckets collected so far.
+    buckets: Vec<(String, BucketSize)>,
+
+    /// Optional columns that will be displayed.
+    columns: Columns,
+
+    /// Whether the final total row will be displayed.
+    total: bool,
+
+    /// Unit that sizes will be displayed in.
+    unit: SizeUnit,
+}
+
+/// Returns the given `cells` as a Markdown table row.
+fn row(cells: &[String]) -> String {
+    format!("| {} |", cells.join(" | "))
+}
+
+impl MarkdownRenderer {
+    /// Return a new `MarkdownRenderer` displaying sizes in the given `unit`,
+    /// with any optional `columns` and followed by a `total` row if
+    /// requested.
+    pub fn new(unit: SizeUnit, columns: Columns, total: bool) -> Self {
+        Self {
+            columns,
+            total,
+            unit,
+            buckets: Vec::new(),
+        }
+    }
+
+    /// Return the header and alignment rows, with a version breakdown if
+    /// `versions` is set.
+    fn header(&self, versions: bool) -> String {
+        let mut headers = vec!["Size"];
+
+        if self.columns.count {
+            headers.push("Objects");
+        }
+
+        if versions {
+            headers.extend(["Current", "Non-current", "Multipart"]);
+        }
+
+        if self.columns.multipart_age {
+            headers.push("Oldest multipart (days)");
+        }
+
+        if self.columns.datapoint_timestamp {
+            headers.push("Timestamp");
+        }
+
+        // The bucket name is left aligned, while the numbers are right
+        // aligned.
+        let mut names     = vec!["Bucket".to_string()];
+        let mut alignment = vec!["---".to_string()];
+
+        for header in headers {
+            names.push(header.to_string());
+            alignment.push("---:".to_string());
+        }
+
+        format!("{}\n{}", row(&names), row(&alignment))
+    }
+
+    /// Return the table row for `size` with the given `name`.
+    ///
+    /// Pipes in the `name` are escaped so that they don't end the cell.
+    fn line(&self, size: &BucketSize, name: &str) -> String {
+        let mut cells = vec![
+            name.replace('|', "\\|"),
+            size.bytes.humansize(&self.unit),
+        ];
+
+        if self.columns.count {
+            cells.push(or_dash(size.count));
+        }
+
+        if let Some(versions) = &size.versions {
+            cells.push(versions.current.humansize(&self.unit));
+            cells.push(versions.noncurrent.humansize(&self.unit));
+            cells.push(versions.multipart.humansize(&self.unit));
+        }
+
+        if self.columns.multipart_age {
+            cells.push(or_dash(size.multipart_age.map(age_days)));
+        }
+
+        if self.columns.datapoint_timestamp {
+            cells.push(timestamp_or_dash(size.datapoint_timestamp));
+        }
+
+        row(&cells)
+    }
+
+    /// Return the table for the collected buckets and `total`.
+    fn table(&self, total: &Total) -> String {
+        let size = &total.size;
+
+        let mut lines = vec![self.header(size.versions.is_some())];
+
+        for (name, size) in &self.buckets {
+            lines.push(self.line(size, name));
+        }
+
+        if self.total {
+            lines.push(self.line(size, "**Total**"));
+        }
+
+        lines.join("\n")
+    }
+}
+
+impl Renderer for MarkdownRenderer {
+    /// Collect the bucket, nothing is output until the total is rendered.
+    fn bucket(
+        &mut self,
+        _out: &mut dyn Write,
+        bucket: &Bucket,
+        size: &BucketSize,
+    ) -> Result<()> {
+        self.buckets.push((bucket.name.clone(), size.clone()));
+
+        Ok(())
+    }
+
+    /// Display the table, totals by region and storage class aren't
+    /// displayed.
+    fn total(&mut self, out: &mut dyn Write, total: &Total) -> Result<()> {
+        writeln!(out, "{}", self.table(total))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::VersionsBreakdown;
+    use pretty_assertions::assert_eq;
+    use std::io;
+    use std::str::FromStr;
+
+    fn bucket(name: &str) -> Bucket {
+        Bucket {
+            name:          name.into(),
+            region:        None,
+            storage_types: None,
+            is_versioned:  None,
+            created:       None,
+        }
+    }
+
+    #[test]
+    fn test_table() {
+        let mut renderer = MarkdownRenderer::new(
+            SizeUnit::from_str("bytes").unwrap(),
+            Columns::default(),
+            true,
+        );
+
+        let buckets = vec![
+            (bucket("a-bucket"), BucketSize::from(1024)),
+            (bucket("b|bucket"), BucketSize::from(7)),
+        ];
+
+        let mut total = Total::default();
+
+        for (bucket, size) in &buckets {
+            total.add(bucket, size);
+            renderer.bucket(&mut io::sink(), bucket, size).unwrap();
+        }
+
+        let expected = concat!(
+            "| Bucket | Size |\n",
+            "| --- | ---: |\n",
+            "| a-bucket | 1024 |\n",
+            "| b\\|bucket | 7 |\n",
+            "| **Total** | 1031 |",
+        );
+
+        assert_eq!(renderer.table(&total), expected);
+    }
+
+    #[test]
+    fn test_table_columns() {
+        let columns = Columns {
+            count:         true,
+            multipart_age: true,
+            ..Default::default()
+        };
+
+        let mut renderer = MarkdownRenderer::new(
+            SizeUnit::from_str("bytes").unwrap(),
+            columns,
+            false,
+        );
+
+        let size = BucketSize {
+            bytes:    7,
+            count:    Some(3),
+            versions: Some(VersionsBreakdown {
+                current:    1,
+                multipart:  4,
+                noncurrent: 2,
+            }),
+            ..Default::default()
+        };
+
+        let bucket = bucket("a-bucket");
+
+        let mut total = Total::default();
+        total.add(&bucket, &size);
+        renderer.bucket(&mut io::sink(), &bucket, &size).unwrap();
+
+        let expected = concat!(
+            "| Bucket | Size | Objects | Current | Non-current | Multipart | Oldest multipart (days) |\n",
+            "| --- | ---: | ---: | ---: | ---: | ---: | ---: |\n",
+            "| a-bucket | 7 | 3 | 1 | 2 | 4 | - |",
+        );
+
+        assert_eq!(renderer.table(&total), expected);
+    }
+}
@@ -0,0 +1,319 @@
+// Plain du(1) style output
+#![forbid(unsafe_code)]
+#![deny(missing_docs)]
+use anyhow::Result;
+use crate::common::{
+    Bucket,
+    BucketSize,
+    HumanSize,
+    SizeUnit,
+};
+use std::io::Write;
+use super::{
+    age_days,
+    or_dash,
+    timestamp_or_dash,
+    Columns,
+    Renderer,
+    Total,
+};
+
+/// Renders bucket sizes as tab separated lines in the style of `du(1)`.
+///
+/// Each line starts with the size and ends with the bucket name, any extra
+/// columns are placed between the two.
+pub struct PlainRenderer {
+    /// Optional columns that will be displayed.
+    columns: Columns,
+
+    /// Whether the final total line will be displayed.
+    total: bool,
+
+    /// Unit that sizes will be displayed in.
+    unit: SizeUnit,
+}
+
+impl PlainRenderer {
+    /// Return a new `PlainRenderer` displaying sizes in the given `unit`,
+    /// with any optional `columns` and followed by a `total` line if
+    /// requested.
+    pub fn new(unit: SizeUnit, columns: Columns, total: bool) -> Self {
+        Self {
+            columns,
+            total,
+            unit,
+        }
+    }
+
+    /// Return the output line for `size` with the given `name`.
+    fn line(&self, size: &BucketSize, name: &str) -> String {
+        let mut columns = vec![size.bytes.humansize(&self.unit)];
+
+        // Unknown counts are displayed as a `-` to keep the columns aligned.
+        if self.columns.count {
+            columns.push(or_dash(size.count));
+        }
+
+        if let Some(versions) = &size.versions {
+            columns.push(versions.current.humansize(&self.unit));
+            columns.push(versions.noncurrent.humansize(&self.unit));
+            columns.push(versions.multipart.humansize(&self.unit));
+        }
+
+        if self.columns.multipart_age {
+            columns.push(or_dash(size.multipart_age.map(age_days)));
+        }
+
+        if self.columns.datapoint_timestamp {
+            columns.push(timestamp_or_dash(size.datapoint_timestamp));
+        }
+
+        columns.push(name.to_string());
+
+        columns.join("\t")
+    }
+}
+
+impl Renderer for PlainRenderer {
+    fn bucket(
+        &mut self,
+        out: &mut dyn Write,
+        bucket: &Bucket,
+        size: &BucketSize,
+    ) -> Result<()> {
+        writeln!(out, "{}", self.line(size, &bucket.name))?;
+
+        // Each storage class is shown indented under its bucket.
+        if self.columns.breakdown {
+            let storage_classes = size.storage_classes.iter().flatten();
+
+            for (storage_class, bytes) in storage_classes {
+                writeln!(
+                    out,
+                    "  {}\t{}",
+                    bytes.humansize(&self.unit),
+                    storage_class,
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Display the total size the same way du(1) would, the total size
+    /// followed by a `.`.
+    ///
+    /// Totals by region and storage class aren't displayed.
+    fn total(&mut self, out: &mut dyn Write, total: &Total) -> Result<()> {
+        if self.total {
+            writeln!(out, "{}", self.line(&total.size, "."))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::VersionsBreakdown;
+    use pretty_assertions::assert_eq;
+    use std::collections::BTreeMap;
+    use std::str::FromStr;
+    use std::time::{
+        Duration,
+        SystemTime,
+    };
+
+    #[test]
+    fn test_line() {
+        let breakdown = BucketSize {
+            bytes:    7,
+            versions: Some(VersionsBreakdown {
+                current:    1,
+                multipart:  4,
+                noncurrent: 2,
+            }),
+            ..Default::default()
+        };
+
+        let tests = vec![
+            (BucketSize::from(1024), "1024\ttest-bucket"),
+            (breakdown,              "7\t1\t2\t4\ttest-bucket"),
+        ];
+
+        let renderer = PlainRenderer::new(
+            SizeUnit::from_str("bytes").unwrap(),
+            Columns::default(),
+            true,
+        );
+
+        for test in tests {
+            let size     = test.0;
+            let expected = test.1;
+
+            let ret = renderer.line(&size, "test-bucket");
+
+            assert_eq!(ret, expected);
+        }
+    }
+
+    #[test]
+    fn test_bucket_breakdown() {
+        let bucket = Bucket {
+            name:          "test-bucket".into(),
+            region:        None,
+            storage_types: None,
+            is_versioned:  None,
+            created:       None,
+        };
+
+        let size = BucketSize {
+            bytes:           3072,
+            storage_classes: Some(BTreeMap::from([
+                ("StandardIAStorage".into(), 1024),
+                ("StandardStorage".into(),   2048),
+            ])),
+            ..Default::default()
+        };
+
+        let tests = vec![
+            (
+                false,
+                "3KiB\ttest-bucket\n",
+            ),
+            (
+                true,
+                "3KiB\ttest-bucket\n  1KiB\tStandardIAStorage\n  2KiB\tStandardStorage\n",
+            ),
+        ];
+
+        for test in tests {
+            let breakdown = test.0;
+            let expected  = test.1;
+
+            let columns = Columns {
+                breakdown,
+                ..Default::default()
+            };
+
+            let mut renderer = PlainRenderer::new(
+                SizeUnit::from_str("binary").unwrap(),
+                columns,
+                true,
+            );
+
+            let mut out = Vec::new();
+
+            renderer.bucket(&mut out, &bucket, &size).unwrap();
+
+            let ret = String::from_utf8(out).unwrap();
+
+            assert_eq!(ret, expected);
+        }
+    }
+
+    #[test]
+    fn test_line_count() {
+        let counted = BucketSize {
+            bytes: 1024,
+            count: Some(42),
+            ..Default::default()
+        };
+
+        let tests = vec![
+            (counted,                "1024\t42\ttest-bucket"),
+            (BucketSize::from(1024), "1024\t-\ttest-bucket"),
+        ];
+
+        let columns = Columns {
+            count: true,
+            ..Default::default()
+        };
+
+        let renderer = PlainRenderer::new(
+            SizeUnit::from_str("bytes").unwrap(),
+            columns,
+            true,
+        );
+
+        for test in tests {
+            let size     = test.0;
+            let expected = test.1;
+
+            let ret = renderer.line(&size, "test-bucket");
+
+            assert_eq!(ret, expected);
+        }
+    }
+
+    #[test]
+    fn test_line_multipart_age() {
+        let aged = BucketSize {
+            bytes:         1024,
+            multipart_age: Some(Duration::from_secs(3 * 86_400 + 60)),
+            ..Default::default()
+        };
+
+        let tests = vec![
+            (aged,                   "1024\t3\ttest-bucket"),
+            (BucketSize::from(1024), "1024\t-\ttest-bucket"),
+        ];
+
+        let columns = Columns {
+            multipart_age: true,
+            ..Default::default()
+        };
+
+        let renderer = PlainRenderer::new(
+            SizeUnit::from_str("bytes").unwrap(),
+            columns,
+            true,
+        );
+
+        for test in tests {
+            let size     = test.0;
+            let expected = test.1;
+
+            let ret = renderer.line(&size, "test-bucket");
+
+            assert_eq!(ret, expected);
+        }
+    }
+
+    #[test]
+    fn test_line_datapoint_timestamp() {
+        let measured = BucketSize {
+            bytes:               1024,
+            datapoint_timestamp: Some(
+                SystemTime::UNIX_EPOCH + Duration::from_secs(1_583_096_340),
+            ),
+            ..Default::default()
+        };
+
+        let tests = vec![
+            (measured,               "1024\t2020-03-01T20:59:00Z\ttest-bucket"),
+            (BucketSize::from(1024), "1024\t-\ttest-bucket"),
+        ];
+
+        let columns = Columns {
+            datapoint_timestamp: true,
+            ..Default::default()
+        };
+
+        let renderer = PlainRenderer::new(
+            SizeUnit::from_str("bytes").unwrap(),
+            columns,
+            true,
+        );
+
+        for test in tests {
+            let size     = test.0;
+            let expected = test.1;
+
+            let ret = renderer.line(&size, "test-bucket");
+
+            assert_eq!(ret, expected);
+        }
+    }
+}
@@ -0,0 +1,183 @@
+// Prometheus text exposition format output
+#![forbid(unsafe_code)]
+#![deny(missing_docs)]
+use anyhow::Result;
+use crate::common::{
+    Bucket,
+    BucketSize,
+};
+use std::io::Write;
+use super::{
+    Renderer,
+    Total,
+};
+
+/// Name of the metric giving the size of each bucket.
+const BUCKET_SIZE_METRIC: &str = "s3du_bucket_size_bytes";
+
+/// Name of the metric giving the total size of all buckets.
+const TOTAL_SIZE_METRIC: &str = "s3du_bucket_size_bytes_total";
+
+/// Returns the `# HELP` and `# TYPE` header lines for a gauge with the given
+/// `name` and `help` text.
+fn header(name: &str, help: &str) -> String {
+    format!("# HELP {name} {help}\n# TYPE {name} gauge")
+}
+
+/// Returns the given label `value` escaped as required by the Prometheus text
+/// exposition format.
+fn escape_label_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"'  => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            _    => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// Renders bucket sizes in the Prometheus text exposition format, suitable for
+/// the `node_exporter` textfile collector.
+///
+/// Sizes are always output in bytes, whichever unit was selected.
+#[derive(Default)]
+pub struct PrometheusRenderer {
+    /// Whether the bucket size header has been output yet.
+    header: bool,
+}
+
+impl PrometheusRenderer {
+    /// Return a new `PrometheusRenderer`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return the sample line for the `size` of the given `bucket`.
+    fn sample(bucket: &Bucket, size: &BucketSize) -> String {
+        let region = bucket.region
+            .as_ref()
+            .map_or("", |region| region.name());
+
+        format!(
+            "{}{{bucket=\"{}\",region=\"{}\"}} {}",
+            BUCKET_SIZE_METRIC,
+            escape_label_value(&bucket.name),
+            escape_label_value(region),
+            size.bytes,
+        )
+    }
+}
+
+impl Renderer for PrometheusRenderer {
+    /// Output the sample for the bucket, preceded by the metric header if
+    /// this is the first bucket.
+    fn bucket(
+        &mut self,
+        out: &mut dyn Write,
+        bucket: &Bucket,
+        size: &BucketSize,
+    ) -> Result<()> {
+        if !self.header {
+            writeln!(
+                out,
+                "{}",
+                header(BUCKET_SIZE_METRIC, "Size of the S3 bucket in bytes."),
+            )?;
+
+            self.header = true;
+        }
+
+        writeln!(out, "{}", Self::sample(bucket, size))?;
+
+        Ok(())
+    }
+
+    /// Output the total size of all buckets as its own metric.
+    fn total(&mut self, out: &mut dyn Write, total: &Total) -> Result<()> {
+        writeln!(
+            out,
+            "{}",
+            header(TOTAL_SIZE_METRIC, "Total size of all S3 buckets in bytes."),
+        )?;
+
+        writeln!(out, "{} {}", TOTAL_SIZE_METRIC, total.size.bytes)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::Region;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_escape_label_value() {
+        let tests = vec![
+            ("a-bucket",     "a-bucket"),
+            ("back\\slash",  "back\\\\slash"),
+            ("\"quoted\"",   "\\\"quoted\\\""),
+            ("new\nline",    "new\\nline"),
+        ];
+
+        for test in tests {
+            let value    = test.0;
+            let expected = test.1;
+
+            let ret = escape_label_value(value);
+
+            assert_eq!(ret, expected);
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let buckets = vec![
+            ("a-bucket", Some("eu-west-1"), 1024),
+            ("b-bucket", None,              2048),
+        ];
+
+        let mut renderer = PrometheusRenderer::new();
+        let mut out      = Vec::new();
+        let mut total    = Total::default();
+
+        for (name, region, bytes) in buckets {
+            let bucket = Bucket {
+                name:          name.into(),
+                region:        region.map(|r| Region::new().set_region(r)),
+                storage_types: None,
+                is_versioned:  None,
+                created:       None,
+            };
+
+            let size = BucketSize::from(bytes);
+
+            total.add(&bucket, &size);
+
+            renderer.bucket(&mut out, &bucket, &size).unwrap();
+        }
+
+        renderer.total(&mut out, &total).unwrap();
+
+        let out = String::from_utf8(out).unwrap();
+        let ret: Vec<&str> = out.lines().collect();
+
+        let expected = vec![
+            "# HELP s3du_bucket_size_bytes Size of the S3 bucket in bytes.",
+            "# TYPE s3du_bucket_size_bytes gauge",
+            "s3du_bucket_size_bytes{bucket=\"a-bucket\",region=\"eu-west-1\"} 1024",
+            "s3du_bucket_size_bytes{bucket=\"b-bucket\",region=\"\"} 2048",
+            "# HELP s3du_bucket_size_bytes_total Total size of all S3 buckets in bytes.",
+            "# TYPE s3du_bucket_size_bytes_total gauge",
+            "s3du_bucket_size_bytes_total 3072",
+        ];
+
+        assert_eq!(ret, expected);
+    }
+}
@@ -0,0 +1,203 @@
+// Output using user supplied templates
+#![forbid(unsafe_code)]
+#![deny(missing_docs)]
+use anyhow::Result;
+use crate::common::{
+    Bucket,
+    BucketSize,
+    HumanSize,
+    Placeholder,
+    SizeUnit,
+    Template,
+};
+use std::io::Write;
+use super::{
+    or_dash,
+    Renderer,
+    Total,
+};
+
+/// Renders each bucket, and optionally the total, with user supplied
+/// templates.
+pub struct TemplateRenderer {
+    /// Template rendered for each bucket.
+    template: Template,
+
+    /// Template rendered for the total, if any.
+    total_template: Option<Template>,
+
+    /// Unit that `human` sizes will be displayed in.
+    unit: SizeUnit,
+}
+
+impl TemplateRenderer {
+    /// Return a new `TemplateRenderer` rendering each bucket with `template`
+    /// and the total with `total_template`, if given, displaying `human` sizes
+    /// in the given `unit`.
+    pub fn new(
+        unit: SizeUnit,
+        template: Template,
+        total_template: Option<Template>,
+    ) -> Self {
+        Self {
+            template,
+            total_template,
+            unit,
+        }
+    }
+
+    /// Return the output line for the `size` of `bucket`.
+    fn line(&self, bucket: &Bucket, size: &BucketSize) -> String {
+        self.template.render(|placeholder| match placeholder {
+            Placeholder::Bucket => bucket.name.clone(),
+            Placeholder::Bytes  => size.bytes.to_string(),
+            Placeholder::Count  => or_dash(size.count),
+            Placeholder::Human  => size.bytes.humansize(&self.unit),
+            Placeholder::Region => {
+                bucket.region
+                    .as_ref()
+                    .map_or_else(|| "-".into(), |r| r.name().into())
+            },
+
+            // Only valid in the total template, this is checked when the
+            // template is parsed.
+            Placeholder::Total  => unreachable!(),
+        })
+    }
+
+    /// Return the output line for the `total`, if there's a total template.
+    fn total_line(&self, total: &Total) -> Option<String> {
+        let template = self.total_template.as_ref()?;
+        let size     = &total.size;
+
+        let line = template.render(|placeholder| match placeholder {
+            Placeholder::Bytes  => size.bytes.to_string(),
+            Placeholder::Count  => or_dash(size.count),
+            Placeholder::Human  => size.bytes.humansize(&self.unit),
+            Placeholder::Total  => size.bytes.humansize(&self.unit),
+
+            // Only valid in the bucket template, this is checked when the
+            // template is parsed.
+            Placeholder::Bucket | Placeholder::Region => unreachable!(),
+        });
+
+        Some(line)
+    }
+}
+
+impl Renderer for TemplateRenderer {
+    fn bucket(
+        &mut self,
+        out: &mut dyn Write,
+        bucket: &Bucket,
+        size: &BucketSize,
+    ) -> Result<()> {
+        writeln!(out, "{}", self.line(bucket, size))?;
+
+        Ok(())
+    }
+
+    /// Display the total with the total template, nothing is displayed if
+    /// there isn't one.
+    fn total(&mut self, out: &mut dyn Write, total: &Total) -> Result<()> {
+        if let Some(line) = self.total_line(total) {
+            writeln!(out, "{line}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::Region;
+    use pretty_assertions::assert_eq;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_line() {
+        let template = Template::parse(
+            "{bucket}\\t{bytes}\\t{human}\\t{region}\\t{count}",
+            Placeholder::BUCKET,
+        ).unwrap();
+
+        let renderer = TemplateRenderer::new(
+            SizeUnit::from_str("binary").unwrap(),
+            template,
+            None,
+        );
+
+        let tests = vec![
+            (
+                Bucket {
+                    name:          "a-bucket".into(),
+                    region:        Some(Region::new().set_region("eu-west-1")),
+                    storage_types: None,
+                    is_versioned:  None,
+                    created:       None,
+                },
+                BucketSize {
+                    bytes: 2048,
+                    count: Some(3),
+                    ..Default::default()
+                },
+                "a-bucket\t2048\t2KiB\teu-west-1\t3",
+            ),
+            (
+                Bucket {
+                    name:          "b-bucket".into(),
+                    region:        None,
+                    storage_types: None,
+                    is_versioned:  None,
+                    created:       None,
+                },
+                BucketSize::from(7),
+                "b-bucket\t7\t7B\t-\t-",
+            ),
+        ];
+
+        for test in tests {
+            let bucket   = test.0;
+            let size     = test.1;
+            let expected = test.2;
+
+            assert_eq!(renderer.line(&bucket, &size), expected);
+        }
+    }
+
+    #[test]
+    fn test_total_line() {
+        let template = Template::parse("{bucket}", Placeholder::BUCKET)
+            .unwrap();
+
+        let total = Total {
+            size: BucketSize::from(2048),
+            ..Default::default()
+        };
+
+        let tests = vec![
+            (None,                          None),
+            (Some("total: {total}"),        Some("total: 2KiB")),
+            (Some("{bytes} {human} {count}"), Some("2048 2KiB -")),
+        ];
+
+        for test in tests {
+            let total_template = test.0.map(|t| {
+                Template::parse(t, Placeholder::TOTAL).unwrap()
+            });
+
+            let expected = test.1;
+
+            let renderer = TemplateRenderer::new(
+                SizeUnit::from_str("binary").unwrap(),
+                template.clone(),
+                total_template,
+            );
+
+            let ret = renderer.total_line(&total);
+
+            assert_eq!(ret.as_deref(), expected);
+        }
+    }
+}
@@ -0,0 +1,80 @@
+// prompt: Interactive confirmation prompts
+#![forbid(unsafe_code)]
+#![deny(missing_docs)]
+use anyhow::Result;
+use std::io::{
+    self,
+    BufRead,
+    IsTerminal,
+    Write,
+};
+
+/// Ask the user to confirm that more than `threshold` buckets should be
+/// sized.
+///
+/// If stdin isn't a terminal there is nobody to ask, so we proceed without
+/// prompting.
+pub fn confirm_buckets(threshold: usize) -> Result<bool> {
+    let stdin = io::stdin();
+
+    if !stdin.is_terminal() {
+        return Ok(true);
+    }
+
+    let prompt = format!(
+        "About to size more than {threshold} buckets in S3 mode, this may issue a large number of requests. Continue?",
+    );
+
+    confirm(stdin.lock(), io::stderr(), &prompt)
+}
+
+/// Write the `prompt` to `output` and return whether the answer read from
+/// `input` was yes.
+///
+/// Anything other than `y` or `yes` is taken to be no.
+fn confirm<R, W>(mut input: R, mut output: W, prompt: &str) -> Result<bool>
+where
+    R: BufRead,
+    W: Write,
+{
+    write!(output, "{prompt} [y/N] ")?;
+    output.flush()?;
+
+    let mut answer = String::new();
+    input.read_line(&mut answer)?;
+
+    let answer = answer.trim().to_lowercase();
+
+    Ok(matches!(answer.as_str(), "y" | "yes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_confirm() {
+        let tests = vec![
+            ("y\n",   true),
+            ("YES\n", true),
+            (" yes ", true),
+            ("n\n",   false),
+            ("\n",    false),
+            ("",      false),
+            ("maybe", false),
+        ];
+
+        for test in tests {
+            let input    = test.0.as_bytes();
+            let expected = test.1;
+
+            let mut output = Vec::new();
+
+            let ret = confirm(input, &mut output, "Continue?").unwrap();
+
+            assert_eq!(ret, expected, "answer {input:?}");
+            assert_eq!(output, b"Continue? [y/N] ");
+        }
+    }
+}
@@ -0,0 +1,88 @@
+// proxy: Connections via an HTTP proxy
+#![forbid(unsafe_code)]
+#![deny(missing_docs)]
+use hyper::client::HttpConnector;
+use hyper::Uri;
+use hyper_proxy::{
+    Intercept,
+    Proxy,
+    ProxyConnector,
+};
+use hyper_rustls::{
+    HttpsConnector,
+    HttpsConnectorBuilder,
+};
+
+/// Ensures that a given proxy URL is valid, where valid means:
+///   - Parses as a valid URL
+///   - Uses the `http` scheme
+///   - Has a host
+///
+/// Connections to the proxy itself are made in plain HTTP, HTTPS connections
+/// are then tunnelled through it.
+pub fn is_valid_proxy(s: &str) -> Result<String, String> {
+    let uri = s.parse::<Uri>()
+        .map_err(|e| format!("Could not parse proxy: {e}"))?;
+
+    match uri.scheme_str() {
+        Some("http") => Ok(()),
+        Some(scheme) => Err(format!("Proxy scheme must be http, found {scheme}")),
+        None         => Err("No proxy URI scheme found".into()),
+    }?;
+
+    if uri.host().is_none() {
+        return Err("No proxy host found".into());
+    }
+
+    Ok(s.to_string())
+}
+
+/// Return a connector that makes all connections via the proxy at the given
+/// `proxy` URL.
+pub fn connector(
+    proxy: &str,
+) -> HttpsConnector<ProxyConnector<HttpConnector>> {
+    // This should be safe, we validated this in the CLI parser.
+    let uri = proxy.parse::<Uri>()
+        .expect("proxy uri");
+
+    let proxy = Proxy::new(Intercept::All, uri);
+
+    let connector = ProxyConnector::from_proxy_unsecured(
+        HttpConnector::new(),
+        proxy,
+    );
+
+    // TLS to the destination happens over the tunnel through the proxy.
+    HttpsConnectorBuilder::new()
+        .with_native_roots()
+        .https_or_http()
+        .enable_http1()
+        .wrap_connector(connector)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_valid_proxy() {
+        let tests = vec![
+            ("http://proxy.example.com:3128",  true),
+            ("http://10.0.0.1:8080/",          true),
+            ("https://proxy.example.com:3128", false),
+            ("socks5://proxy.example.com",     false),
+            ("proxy.example.com:3128",         false),
+            ("",                               false),
+        ];
+
+        for test in tests {
+            let proxy = test.0;
+            let valid = test.1;
+
+            let ret = is_valid_proxy(proxy);
+
+            assert_eq!(ret.is_ok(), valid, "proxy {proxy:?}");
+        }
+    }
+}
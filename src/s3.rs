@@ -8,4 +8,11 @@ mod bucket_sizer;
 /// S3 `Client`.
 mod client;
 
+/// S3 `Client`s replaying canned responses, for tests and benchmarks.
+#[cfg(any(test, feature = "benchmark"))]
+mod replay;
+
+/// Retrying throttled S3 requests.
+mod retry;
+
 pub use client::*;
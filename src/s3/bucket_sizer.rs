@@ -4,72 +4,367 @@
 use anyhow::Result;
 use async_trait::async_trait;
 use crate::common::{
+    is_selected,
     Bucket,
     Buckets,
+    BucketSize,
     BucketSizer,
+    BucketStream,
+    ObjectVersions,
+    Region,
+};
+use futures::future;
+use futures::stream::{
+    self,
+    StreamExt,
+    TryStreamExt,
+};
+use super::client::{
+    BucketAccess,
+    Client,
+    ListedBuckets,
+};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{
+    AtomicUsize,
+    Ordering,
+};
+use std::time::SystemTime;
+use tracing::{
+    debug,
+    info,
+    warn,
 };
-use super::client::Client;
-use tracing::debug;
 
-#[async_trait]
-impl BucketSizer for Client {
-    /// Return `Buckets` discovered in S3.
-    ///
-    /// This list of buckets will also be filtered by the following:
-    ///   - The `bucket` argument provided on the command line
-    ///   - The `Region`, ensuring it's in our currently selected `--region`
-    async fn buckets(&self) -> Result<Buckets> {
-        debug!("buckets: Listing...");
+/// Returns a summary of how many of the `discovered` buckets `matched` our
+/// filters.
+fn filter_summary(discovered: usize, matched: usize) -> String {
+    let plural = |count: usize| if count == 1 { "" } else { "s" };
+
+    format!(
+        "Discovered {} bucket{}, {} matched filters",
+        discovered,
+        plural(discovered),
+        matched,
+    )
+}
 
-        let mut bucket_names = self.list_buckets().await?;
+/// A discovered bucket waiting to be filtered, as its name, the time that it
+/// was created and its `Region`, if that has already been looked up.
+type Discovered = (String, Option<SystemTime>, Option<Region>);
 
-        // If we were provided with a specific bucket name on the CLI, filter
+impl Client {
+    /// Return the buckets in a `page` of the bucket listing that match the
+    /// `bucket` arguments provided on the command line, if any, which may
+    /// contain wildcards, and don't
+    /// match any `--exclude` patterns.
+    ///
+    /// If `--prewarm` was given, the locations of these buckets are looked up
+    /// concurrently.
+    async fn filter_page(
+        &self,
+        mut page: ListedBuckets,
+    ) -> Result<Vec<Discovered>> {
+        // If we were provided with specific bucket names on the CLI, filter
         // out buckets that don't match.
-        if let Some(bucket_name) = self.bucket_name.as_ref() {
-            debug!("Filtering bucket list for '{}'", bucket_name);
+        if !self.bucket_names.is_empty() {
+            debug!("Filtering bucket list for {:?}", self.bucket_names);
+
+            page.retain(|(name, _)| is_selected(&self.bucket_names, name));
+        }
+
+        page.retain(|(name, _)| {
+            let excluded = self.exclude.iter()
+                .any(|pattern| pattern.matches(name));
+
+            if excluded {
+                debug!("Excluding '{}'", name);
+            }
+
+            !excluded
+        });
+
+        // Look up every location in the page up front if asked, rather than
+        // as each bucket is filtered.
+        let mut regions = if self.prewarm && !self.assume_region {
+            let names: Vec<String> = page.iter()
+                .map(|(name, _)| name.clone())
+                .collect();
+
+            self.bucket_regions(&names).await?
+        }
+        else {
+            HashMap::new()
+        };
 
-            bucket_names.retain(|b| b == bucket_name);
+        let discovered = page.into_iter()
+            .map(|(name, created)| {
+                let region = regions.remove(&name);
+
+                (name, created, region)
+            })
+            .collect();
+
+        Ok(discovered)
+    }
+
+    /// Return the `Bucket` for the discovered bucket `name`, or `None` if it
+    /// should be filtered out.
+    ///
+    /// The bucket's location is looked up if it isn't already known.
+    async fn filter_bucket(
+        &self,
+        name: String,
+        created: Option<SystemTime>,
+        region: Option<Region>,
+    ) -> Result<Option<Bucket>> {
+        let region = match region {
+            Some(region) => region,
+            None         => self.bucket_region(&name).await?,
+        };
+
+        // Unless we're sizing buckets in all regions, we can only ListBucket
+        // for the region our S3 client is in, so we filter for that region
+        // here.
+        if region != self.region
+            && !self.all_regions
+            && !self.is_custom_client_region()
+        {
+            return Ok(None);
         }
 
-        let mut buckets = Buckets::new();
+        let client = self.for_region(&region);
+        let access = client.head_bucket(&name).await;
+
+        // The bucket's location may be wrong, but when sizing all regions we
+        // can check our access again in the region that S3 says it's in.
+        let (client, region, access) = match access {
+            BucketAccess::WrongRegion(actual) if self.all_regions => {
+                debug!(
+                    "'{}' is in region '{}', checking access there",
+                    name,
+                    actual.name(),
+                );
+
+                let client = self.for_region(&actual);
+                let access = client.head_bucket(&name).await;
+
+                (client, actual, access)
+            },
+            access => (client, region, access),
+        };
+
+        // If we don't have access to the bucket, skip it.
+        match access {
+            BucketAccess::Allowed => {},
+            BucketAccess::Denied  => {
+                debug!("Access denied for '{}'", name);
+
+                return Ok(None);
+            },
+            // We can't size buckets outside of the region we checked, but we
+            // shouldn't silently drop them either.
+            BucketAccess::WrongRegion(actual) => {
+                warn!(
+                    "Skipping '{}', it is in region '{}' not '{}'",
+                    name,
+                    actual.name(),
+                    region.name(),
+                );
+
+                return Ok(None);
+            },
+        }
+
+        // If we're looking for buckets missing a non-current version
+        // expiration rule, skip any that have one.
+        if self.missing_lifecycle
+            && client.has_noncurrent_expiration(&name).await?
+        {
+            debug!("'{}' has a non-current expiration rule", name);
+
+            return Ok(None);
+        }
+
+        let is_versioned = if self.needs_versioning() {
+            client.is_versioned(&name).await
+        }
+        else {
+            None
+        };
+
+        let bucket = Bucket {
+            name,
+            created,
+            is_versioned,
+            region:        Some(region),
+            storage_types: None,
+        };
+
+        Ok(Some(bucket))
+    }
+}
 
-        for bucket in &bucket_names {
-            debug!("Retrieving location for '{}'", bucket);
+#[async_trait]
+impl BucketSizer for Client {
+    /// Return `Buckets` discovered in S3.
+    ///
+    /// This list of buckets will also be filtered by the following:
+    ///   - The `bucket` arguments provided on the command line
+    ///   - The `--exclude` patterns provided on the command line
+    ///   - The `Region`, ensuring it's in our currently selected `--region`,
+    ///     unless `--all-regions` was given
+    ///
+    /// If `--all-regions` was given and S3 says that a bucket is in a
+    /// different region to its location, the bucket is sized in the region
+    /// that S3 gave.
+    ///
+    /// If `--missing-lifecycle` was given, buckets with a lifecycle rule
+    /// expiring non-current object versions are also filtered out.
+    ///
+    /// If non-current object versions are being sized, or `--warn-versioned`
+    /// was given when sizing current objects, the versioning status of each
+    /// bucket is also looked up.
+    ///
+    /// If `--prewarm` was given, the locations of all buckets are looked up
+    /// concurrently before any are filtered.
+    ///
+    /// If `--stats` was given, the number of buckets discovered and the number
+    /// that matched the filters are logged.
+    ///
+    /// If `--assume-region` was given, every bucket is assumed to be in our
+    /// currently selected `--region` and its location isn't looked up.
+    /// Buckets in other regions will then fail the access check and be
+    /// skipped.
+    async fn buckets(&self) -> Result<Buckets> {
+        debug!("buckets: Listing...");
 
-            let region = self.get_bucket_location(bucket).await?;
+        self.bucket_stream().try_collect().await
+    }
 
-            // We can only ListBucket for the region our S3 client is in, so
-            // we filter for that region here.
-            if region == self.region || self.is_custom_client_region() {
-                // If we don't have access to the bucket, skip it.
-                if !self.head_bucket(bucket).await {
-                    debug!("Access denied for '{}'", bucket);
+    /// Return a stream of the `Buckets` discovered in S3, filtered in the
+    /// same way as `buckets`.
+    ///
+    /// Buckets are listed a page at a time and each bucket is yielded as soon
+    /// as it passes the filters, so only a page of bucket names is held in
+    /// memory. With `--prewarm`, locations are looked up for a page at a
+    /// time.
+    fn bucket_stream(&self) -> BucketStream<'_> {
+        // These are counted for the `--stats` summary, which is logged once
+        // the listing is complete.
+        let discovered = Arc::new(AtomicUsize::new(0));
+        let matched    = Arc::new(AtomicUsize::new(0));
+
+        let buckets = self.list_buckets()
+            .inspect_ok({
+                let discovered = Arc::clone(&discovered);
+
+                move |page| {
+                    discovered.fetch_add(page.len(), Ordering::Relaxed);
+                }
+            })
+            .and_then(move |page| self.filter_page(page))
+            .map_ok(|page| stream::iter(page).map(Ok))
+            .try_flatten()
+            .try_filter_map(move |(name, created, region)| {
+                self.filter_bucket(name, created, region)
+            })
+            .inspect_ok({
+                let matched = Arc::clone(&matched);
 
-                    continue;
+                move |_| {
+                    matched.fetch_add(1, Ordering::Relaxed);
                 }
+            });
 
-                let bucket = Bucket {
-                    name:          bucket.into(),
-                    region:        Some(region),
-                    storage_types: None,
-                };
+        let stats   = self.stats;
+        let summary = stream::once(async move {
+            if stats {
+                let discovered = discovered.load(Ordering::Relaxed);
+                let matched    = matched.load(Ordering::Relaxed);
 
-                buckets.push(bucket);
+                info!("{}", filter_summary(discovered, matched));
             }
-        }
+        })
+        .filter_map(|()| future::ready(None));
 
-        // Finally, we have a list of buckets that we should be able to get the
-        // size for.
-        Ok(buckets)
+        buckets.chain(summary).boxed()
     }
 
     /// Return the size of `bucket`.
-    async fn bucket_size(&self, bucket: &Bucket) -> Result<u64> {
+    ///
+    /// If a breakdown of `All` object versions was requested, the sizes of
+    /// each object version type will also be returned.
+    ///
+    /// If `Current` objects are being sized, the time that the newest object
+    /// was last modified will also be returned.
+    ///
+    /// If multipart upload ages were requested, the age of the oldest
+    /// in-progress multipart upload will also be returned.
+    ///
+    /// A warning is logged when sizing the non-current object versions of a
+    /// bucket that has never had versioning enabled, as its size will always
+    /// be 0.
+    ///
+    /// If `--warn-versioned` was given, a warning is also logged when sizing
+    /// the current objects of a bucket that has versioning enabled or
+    /// suspended, as its non-current versions may be far larger than the size
+    /// reported.
+    ///
+    /// If `--all-regions` was given, buckets in other regions are sized with
+    /// a client in the bucket's region.
+    async fn bucket_size(&self, bucket: &Bucket) -> Result<BucketSize> {
         debug!("bucket_size: Calculating size for '{}'", bucket.name);
 
-        let size = self.size_objects(&bucket.name).await?;
+        let client = bucket.region
+            .as_ref()
+            .map_or(Cow::Borrowed(self), |region| self.for_region(region));
+
+        if self.object_versions == ObjectVersions::NonCurrent
+            && bucket.is_versioned == Some(false)
+        {
+            warn!(
+                "'{}' has never had versioning enabled, it has no non-current versions to size",
+                bucket.name,
+            );
+        }
+
+        if self.object_versions == ObjectVersions::Current
+            && bucket.is_versioned == Some(true)
+        {
+            warn!(
+                "'{}' is versioned, non-current versions aren't counted, use --object-versions=all to include them",
+                bucket.name,
+            );
+        }
+
+        let mut size = if self.breakdown_versions
+            && self.object_versions == ObjectVersions::All
+        {
+            client.size_versions_breakdown(&bucket.name).await?
+        }
+        else if self.object_versions == ObjectVersions::Current {
+            client.size_current_objects(&bucket.name).await?
+        }
+        else {
+            client.size_objects(&bucket.name).await?
+        };
 
-        debug!("bucket_size: size for '{}' is '{}'", bucket.name, size);
+        if self.multipart_age {
+            let oldest = client.oldest_multipart_upload(&bucket.name).await?;
+
+            // An upload initiated in the future, due to clock skew, is
+            // treated as brand new.
+            size.multipart_age = oldest.map(|initiated| {
+                SystemTime::now()
+                    .duration_since(initiated)
+                    .unwrap_or_default()
+            });
+        }
+
+        debug!("bucket_size: size for '{}' is '{:?}'", bucket.name, size);
 
         Ok(size)
     }
@@ -78,24 +373,21 @@ impl BucketSizer for Client {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use aws_credential_types::Credentials;
-    use aws_sdk_s3::client::Client as S3Client;
-    use aws_sdk_s3::config::Config as S3Config;
     use aws_smithy_runtime::client::http::test_util::{
         ReplayEvent,
         StaticReplayClient,
     };
     use aws_smithy_types::body::SdkBody;
-    use crate::common::{
-        ObjectVersions,
-        Region,
-    };
+    use crate::common::VersionsBreakdown;
+    use glob::Pattern;
     use pretty_assertions::assert_eq;
     use std::fs;
     use std::path::Path;
+    use std::time::Duration;
 
     enum ResponseType<'a> {
         FromFile(&'a str),
+        FromFileWithStatus(&'a str, u16),
         WithStatus(u16),
     }
 
@@ -105,8 +397,7 @@ mod tests {
         responses: Vec<ResponseType<'a>>,
         versions:  ObjectVersions,
     ) -> Client {
-        // Get a vec of events based on the given data_files
-        let events = responses
+        let responses = responses
             .iter()
             .map(|r| {
                 match r {
@@ -114,51 +405,38 @@ mod tests {
                         let path = Path::new("test-data").join(file);
                         let data = fs::read_to_string(path).unwrap();
 
-                        ReplayEvent::new(
-                            http::Request::builder()
-                                .body(SdkBody::from("request body"))
-                                .unwrap(),
+                        (200, data)
+                    },
+                    ResponseType::FromFileWithStatus(file, status) => {
+                        let path = Path::new("test-data").join(file);
+                        let data = fs::read_to_string(path).unwrap();
 
-                            http::Response::builder()
-                                .status(200)
-                                .body(SdkBody::from(data))
-                                .unwrap(),
-                        )
+                        (*status, data)
                     },
                     ResponseType::WithStatus(status) => {
-                        ReplayEvent::new(
-                            http::Request::builder()
-                                .body(SdkBody::from("request body"))
-                                .unwrap(),
-
-                            http::Response::builder()
-                                .status(*status)
-                                .body(SdkBody::from(""))
-                                .unwrap(),
-                        )
+                        (*status, String::new())
                     },
                 }
             })
             .collect();
 
-        let http_client = StaticReplayClient::new(events);
-
-        let creds = Credentials::for_tests_with_session_token();
+        Client::replay(responses, versions)
+    }
 
-        let conf = S3Config::builder()
-            .behavior_version_latest()
-            .credentials_provider(creds)
-            .http_client(http_client)
-            .region(aws_sdk_s3::config::Region::new("eu-west-1"))
-            .build();
+    #[test]
+    fn test_filter_summary() {
+        let tests = vec![
+            (0,  0,  "Discovered 0 buckets, 0 matched filters"),
+            (1,  1,  "Discovered 1 bucket, 1 matched filters"),
+            (50, 12, "Discovered 50 buckets, 12 matched filters"),
+        ];
 
-        let client = S3Client::from_conf(conf);
+        for test in tests {
+            let discovered = test.0;
+            let matched    = test.1;
+            let expected   = test.2;
 
-        Client {
-            client:          client,
-            bucket_name:     None,
-            object_versions: versions,
-            region:          Region::new().set_region("eu-west-1"),
+            assert_eq!(filter_summary(discovered, matched), expected);
         }
     }
 
@@ -193,6 +471,428 @@ mod tests {
         assert_eq!(buckets, expected);
     }
 
+    #[tokio::test]
+    async fn test_buckets_created() {
+        // 2020-03-12T10:57:32Z and 2020-03-10T10:58:12Z.
+        let expected = vec![
+            ("a-bucket-name",       1_584_010_652),
+            ("another-bucket-name", 1_583_837_892),
+        ];
+
+        let responses = vec![
+            ResponseType::FromFile("s3-list-buckets.xml"),
+            ResponseType::FromFile("s3-get-bucket-location.xml"),
+            ResponseType::WithStatus(200),
+            ResponseType::FromFile("s3-get-bucket-location.xml"),
+            ResponseType::WithStatus(200),
+        ];
+
+        let client = mock_client(
+            responses,
+            ObjectVersions::Current,
+        ).await;
+
+        let buckets = client.buckets().await.unwrap();
+
+        let mut buckets: Vec<(&str, u64)> = buckets.iter()
+            .map(|b| {
+                let created = b.created
+                    .unwrap()
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs();
+
+                (b.name.as_str(), created)
+            })
+            .collect();
+
+        buckets.sort();
+
+        assert_eq!(buckets, expected);
+    }
+
+    #[tokio::test]
+    async fn test_buckets_wildcard() {
+        let expected = vec![
+            "another-bucket-name",
+        ];
+
+        // The bucket that doesn't match is skipped before its location is
+        // looked up.
+        let responses = vec![
+            ResponseType::FromFile("s3-list-buckets.xml"),
+            ResponseType::FromFile("s3-get-bucket-location.xml"),
+            ResponseType::WithStatus(200),
+        ];
+
+        let mut client = mock_client(
+            responses,
+            ObjectVersions::Current,
+        ).await;
+
+        client.bucket_names = vec![
+            "another-*".into(),
+        ];
+
+        let buckets = client.buckets().await.unwrap();
+
+        let buckets: Vec<String> = buckets.iter()
+            .map(|b| b.name.to_owned())
+            .collect();
+
+        assert_eq!(buckets, expected);
+    }
+
+    #[tokio::test]
+    async fn test_buckets_exclude() {
+        let expected = vec![
+            "a-bucket-name",
+        ];
+
+        // The excluded bucket is skipped before its location is looked up.
+        let responses = vec![
+            ResponseType::FromFile("s3-list-buckets.xml"),
+            ResponseType::FromFile("s3-get-bucket-location.xml"),
+            ResponseType::WithStatus(200),
+        ];
+
+        let mut client = mock_client(
+            responses,
+            ObjectVersions::Current,
+        ).await;
+
+        client.exclude = vec![
+            Pattern::new("another-*").unwrap(),
+        ];
+
+        let buckets = client.buckets().await.unwrap();
+
+        let buckets: Vec<String> = buckets.iter()
+            .map(|b| b.name.to_owned())
+            .collect();
+
+        assert_eq!(buckets, expected);
+    }
+
+    #[tokio::test]
+    async fn test_buckets_assume_region() {
+        let expected = vec![
+            "a-bucket-name",
+        ];
+
+        // No location lookups are performed, the second bucket is in another
+        // region and fails the access check.
+        let responses = vec![
+            ResponseType::FromFile("s3-list-buckets.xml"),
+            ResponseType::WithStatus(200),
+            ResponseType::WithStatus(301),
+        ];
+
+        let mut client = mock_client(
+            responses,
+            ObjectVersions::Current,
+        ).await;
+
+        client.assume_region = true;
+
+        let buckets = client.buckets().await.unwrap();
+
+        let buckets: Vec<String> = buckets.iter()
+            .map(|b| b.name.to_owned())
+            .collect();
+
+        assert_eq!(buckets, expected);
+    }
+
+    #[tokio::test]
+    async fn test_buckets_all_regions() {
+        let expected = vec![
+            ("a-bucket-name",       "eu-west-1"),
+            ("another-bucket-name", "us-east-1"),
+        ];
+
+        // The second bucket is in us-east-1, which would be skipped without
+        // all_regions.
+        let responses = vec![
+            ResponseType::FromFile("s3-list-buckets.xml"),
+            ResponseType::FromFile("s3-get-bucket-location.xml"),
+            ResponseType::WithStatus(200),
+            ResponseType::FromFile("s3-get-bucket-location-null.xml"),
+            ResponseType::WithStatus(200),
+        ];
+
+        let mut client = mock_client(
+            responses,
+            ObjectVersions::Current,
+        ).await;
+
+        client.all_regions = true;
+
+        let buckets = client.buckets().await.unwrap();
+
+        let buckets: Vec<(&str, &str)> = buckets.iter()
+            .map(|b| (b.name.as_str(), b.region.as_ref().unwrap().name()))
+            .collect();
+
+        assert_eq!(buckets, expected);
+        assert_eq!(client.regional_clients.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_buckets_all_regions_wrong_location() {
+        let expected = vec![
+            ("a-bucket-name",       "eu-west-1"),
+            ("another-bucket-name", "us-east-1"),
+        ];
+
+        let response = |status: u16, region: Option<&str>, file: Option<&str>| {
+            let body = file.map_or_else(String::new, |file| {
+                fs::read_to_string(Path::new("test-data").join(file)).unwrap()
+            });
+
+            let response = http::Response::builder().status(status);
+
+            let response = match region {
+                Some(region) => response.header("x-amz-bucket-region", region),
+                None         => response,
+            };
+
+            ReplayEvent::new(
+                http::Request::builder()
+                    .body(SdkBody::from("request body"))
+                    .unwrap(),
+
+                response
+                    .body(SdkBody::from(body))
+                    .unwrap(),
+            )
+        };
+
+        // Both buckets claim to be in eu-west-1, but the access check for
+        // the second says that it's in us-east-1, where it's checked again.
+        let events = vec![
+            response(200, None, Some("s3-list-buckets.xml")),
+            response(200, None, Some("s3-get-bucket-location.xml")),
+            response(200, None, None),
+            response(200, None, Some("s3-get-bucket-location.xml")),
+            response(301, Some("us-east-1"), None),
+            response(200, None, None),
+        ];
+
+        let mut client = mock_client(
+            Vec::new(),
+            ObjectVersions::Current,
+        ).await;
+
+        client.all_regions = true;
+        client.client      = aws_sdk_s3::Client::from_conf(
+            client.client.config()
+                .to_builder()
+                .http_client(StaticReplayClient::new(events))
+                .build(),
+        );
+
+        let buckets = client.buckets().await.unwrap();
+
+        let buckets: Vec<(&str, &str)> = buckets.iter()
+            .map(|b| (b.name.as_str(), b.region.as_ref().unwrap().name()))
+            .collect();
+
+        assert_eq!(buckets, expected);
+        assert_eq!(client.regional_clients.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_buckets_prewarm() {
+        let expected = vec![
+            "a-bucket-name",
+        ];
+
+        // Both locations are looked up before either access check, the
+        // second bucket is in us-east-1 and is filtered out without one.
+        let responses = vec![
+            ResponseType::FromFile("s3-list-buckets.xml"),
+            ResponseType::FromFile("s3-get-bucket-location.xml"),
+            ResponseType::FromFile("s3-get-bucket-location-null.xml"),
+            ResponseType::WithStatus(200),
+        ];
+
+        let mut client = mock_client(
+            responses,
+            ObjectVersions::Current,
+        ).await;
+
+        client.prewarm = true;
+
+        let buckets = client.buckets().await.unwrap();
+
+        let buckets: Vec<String> = buckets.iter()
+            .map(|b| b.name.to_owned())
+            .collect();
+
+        assert_eq!(buckets, expected);
+    }
+
+    #[tokio::test]
+    async fn test_buckets_is_versioned() {
+        let expected = vec![
+            ("a-bucket-name",       Some(true)),
+            ("another-bucket-name", None),
+        ];
+
+        // Versioning is looked up after each access check, the lookup for the
+        // second bucket fails.
+        let responses = vec![
+            ResponseType::FromFile("s3-list-buckets.xml"),
+            ResponseType::FromFile("s3-get-bucket-location.xml"),
+            ResponseType::WithStatus(200),
+            ResponseType::FromFile("s3-get-bucket-versioning-enabled.xml"),
+            ResponseType::FromFile("s3-get-bucket-location.xml"),
+            ResponseType::WithStatus(200),
+            ResponseType::FromFileWithStatus("s3-not-implemented.xml", 501),
+        ];
+
+        let client = mock_client(
+            responses,
+            ObjectVersions::NonCurrent,
+        ).await;
+
+        let buckets = client.buckets().await.unwrap();
+
+        let buckets: Vec<(&str, Option<bool>)> = buckets.iter()
+            .map(|b| (b.name.as_str(), b.is_versioned))
+            .collect();
+
+        assert_eq!(buckets, expected);
+    }
+
+    #[tokio::test]
+    async fn test_buckets_is_versioned_current() {
+        // Versioning is only looked up when sizing current objects if asked
+        // to warn about non-current versions that aren't counted.
+        let tests = vec![
+            (
+                false,
+                vec![
+                    ResponseType::FromFile("s3-list-buckets.xml"),
+                    ResponseType::FromFile("s3-get-bucket-location.xml"),
+                    ResponseType::WithStatus(200),
+                    ResponseType::FromFile("s3-get-bucket-location.xml"),
+                    ResponseType::WithStatus(200),
+                ],
+                vec![
+                    ("a-bucket-name",       None),
+                    ("another-bucket-name", None),
+                ],
+            ),
+            (
+                true,
+                vec![
+                    ResponseType::FromFile("s3-list-buckets.xml"),
+                    ResponseType::FromFile("s3-get-bucket-location.xml"),
+                    ResponseType::WithStatus(200),
+                    ResponseType::FromFile("s3-get-bucket-versioning-suspended.xml"),
+                    ResponseType::FromFile("s3-get-bucket-location.xml"),
+                    ResponseType::WithStatus(200),
+                    ResponseType::FromFile("s3-get-bucket-versioning-never.xml"),
+                ],
+                vec![
+                    ("a-bucket-name",       Some(true)),
+                    ("another-bucket-name", Some(false)),
+                ],
+            ),
+        ];
+
+        for test in tests {
+            let warn_versioned = test.0;
+            let responses      = test.1;
+            let expected       = test.2;
+
+            let mut client = mock_client(
+                responses,
+                ObjectVersions::Current,
+            ).await;
+
+            client.warn_versioned = warn_versioned;
+
+            let buckets = client.buckets().await.unwrap();
+
+            let buckets: Vec<(&str, Option<bool>)> = buckets.iter()
+                .map(|b| (b.name.as_str(), b.is_versioned))
+                .collect();
+
+            assert_eq!(buckets, expected, "{warn_versioned}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_buckets_missing_lifecycle() {
+        let expected = vec![
+            "another-bucket-name",
+        ];
+
+        // The first bucket expires non-current versions, the second has no
+        // lifecycle configuration at all.
+        let responses = vec![
+            ResponseType::FromFile("s3-list-buckets.xml"),
+            ResponseType::FromFile("s3-get-bucket-location.xml"),
+            ResponseType::WithStatus(200),
+            ResponseType::FromFile("s3-get-bucket-lifecycle-configuration.xml"),
+            ResponseType::FromFile("s3-get-bucket-location.xml"),
+            ResponseType::WithStatus(200),
+            ResponseType::FromFileWithStatus(
+                "s3-no-such-lifecycle-configuration.xml",
+                404,
+            ),
+        ];
+
+        let mut client = mock_client(
+            responses,
+            ObjectVersions::NonCurrent,
+        ).await;
+
+        client.missing_lifecycle = true;
+
+        let buckets = client.buckets().await.unwrap();
+
+        let buckets: Vec<String> = buckets.iter()
+            .map(|b| b.name.to_owned())
+            .collect();
+
+        assert_eq!(buckets, expected);
+    }
+
+    #[tokio::test]
+    async fn test_buckets_custom_endpoint_location_unsupported() {
+        let expected = vec![
+            "a-bucket-name",
+            "another-bucket-name",
+        ];
+
+        let responses = vec![
+            ResponseType::FromFile("s3-list-buckets.xml"),
+            ResponseType::WithStatus(501),
+            ResponseType::WithStatus(200),
+            ResponseType::WithStatus(501),
+            ResponseType::WithStatus(200),
+        ];
+
+        let mut client = mock_client(
+            responses,
+            ObjectVersions::Current,
+        ).await;
+
+        client.endpoint = Some("http://127.0.0.1:9000".into());
+
+        let buckets = client.buckets().await.unwrap();
+
+        let buckets: Vec<String> = buckets.iter()
+            .map(|b| b.name.to_owned())
+            .collect();
+
+        assert_eq!(buckets, expected);
+    }
+
     #[tokio::test]
     async fn test_bucket_size() {
         let client = mock_client(
@@ -204,11 +904,62 @@ mod tests {
             name:          "test-bucket".into(),
             region:        None,
             storage_types: None,
+            is_versioned:  None,
+            created:       None,
         };
 
         let ret = client.bucket_size(&bucket).await.unwrap();
 
-        let expected = 33792;
+        // The newest object in s3-list-objects.xml is from
+        // 2020-03-12T11:04:09Z.
+        let newest = SystemTime::UNIX_EPOCH
+            + Duration::from_secs(1_584_011_049);
+
+        let expected = BucketSize {
+            bytes:         33_792,
+            count:         Some(2),
+            newest_object: Some(newest),
+            ..Default::default()
+        };
+
+        assert_eq!(ret, expected);
+    }
+
+    #[tokio::test]
+    async fn test_bucket_size_breakdown_versions() {
+        let responses = vec![
+            ResponseType::FromFile("s3-list-object-versions.xml"),
+            ResponseType::FromFile("s3-list-multipart-uploads.xml"),
+            ResponseType::FromFile("s3-list-parts.xml"),
+        ];
+
+        let mut client = mock_client(
+            responses,
+            ObjectVersions::All,
+        ).await;
+
+        client.breakdown_versions = true;
+
+        let bucket = Bucket {
+            name:          "test-bucket".into(),
+            region:        None,
+            storage_types: None,
+            is_versioned:  None,
+            created:       None,
+        };
+
+        let ret = client.bucket_size(&bucket).await.unwrap();
+
+        let expected = BucketSize {
+            bytes:    805_532,
+            count:    Some(3),
+            versions: Some(VersionsBreakdown {
+                current:    434_234,
+                multipart:  204_800,
+                noncurrent: 166_498,
+            }),
+            ..Default::default()
+        };
 
         assert_eq!(ret, expected);
     }
@@ -2,37 +2,397 @@
 #![forbid(unsafe_code)]
 #![deny(missing_docs)]
 use anyhow::{
+    anyhow,
     Context,
     Result,
 };
 use aws_sdk_s3::client::Client as S3Client;
+use aws_sdk_s3::config::http::HttpResponse;
+use aws_sdk_s3::error::{
+    DisplayErrorContext,
+    ProvideErrorMetadata,
+    SdkError,
+};
+use aws_sdk_s3::operation::list_objects_v2::ListObjectsV2Output;
+use aws_sdk_s3::primitives::DateTime;
 use aws_sdk_s3::types::{
     BucketLocationConstraint,
+    BucketVersioningStatus,
+    ExpirationStatus,
+    MultipartUpload,
     Object,
+    ObjectStorageClass,
+    ObjectVersion,
+    ObjectVersionStorageClass,
     Part,
+    RequestPayer,
 };
+use aws_types::SdkConfig;
+use aws_types::service_config::ServiceConfigKey;
 use crate::common::{
-    BucketNames,
+    BucketSize,
     ClientConfig,
+    MultipartSplit,
     ObjectVersions,
     Region,
+    SizeAndCount,
+    VersionsBreakdown,
+};
+use crate::connection;
+use crate::credentials;
+use super::retry::Retry;
+use futures::stream::{
+    self,
+    Stream,
+    StreamExt,
+    TryStreamExt,
 };
+use glob::Pattern;
 use rayon::prelude::*;
-use tracing::debug;
+use std::borrow::Cow;
+use std::collections::{
+    BTreeMap,
+    HashMap,
+};
+use std::sync::{
+    Arc,
+    Mutex,
+};
+use std::time::{
+    Duration,
+    SystemTime,
+};
+use tracing::{
+    debug,
+    warn,
+};
+
+/// The maximum number of prefixes that will be listed concurrently when
+/// sizing current objects with `parallel_prefixes`.
+const PARALLEL_PREFIX_LIMIT: usize = 16;
+
+/// The maximum number of bucket locations that will be looked up
+/// concurrently with `prewarm`.
+const PREWARM_LIMIT: usize = 16;
+
+/// The maximum number of buckets requested in each page of `ListBuckets`.
+const LIST_BUCKETS_PAGE_SIZE: i32 = 1000;
+
+/// Convenience type for the size of each storage class, keyed by storage
+/// class.
+type StorageClasses = BTreeMap<String, u64>;
+
+/// Convenience type for the totals found by listing current objects, their
+/// size and count, the newest modification time and the size of each storage
+/// class, if storage classes are being totalled.
+type CurrentObjects = (SizeAndCount, Option<DateTime>, Option<StorageClasses>);
+
+/// Convenience type for the bucket names returned by `ListBuckets`, along
+/// with the time that each bucket was created, if known.
+pub type ListedBuckets = Vec<(String, Option<SystemTime>)>;
+
+/// Convenience type for the `S3Client`s created for other regions, keyed by
+/// region name and shared between every copy of a `Client`.
+pub type RegionalClients = Arc<Mutex<HashMap<String, S3Client>>>;
+
+/// Returns a `bool` indicating if the given `error` was caused by the
+/// operation not being implemented.
+///
+/// Some S3 compatible storage doesn't implement every S3 operation and will
+/// respond with one of these errors instead.
+fn is_unsupported_operation<E>(error: &SdkError<E, HttpResponse>) -> bool
+where
+    E: ProvideErrorMetadata,
+{
+    if matches!(error.code(), Some("MethodNotAllowed" | "NotImplemented")) {
+        return true;
+    }
+
+    error.raw_response()
+        .is_some_and(|response| {
+            matches!(response.status().as_u16(), 405 | 501)
+        })
+}
+
+/// Returns the region name for a bucket's location `constraint`, or `None`
+/// if the constraint doesn't name a region.
+///
+/// Location constraints for sufficiently old buckets in S3 may not quite meet
+/// expectations. These returns are badly documented and the assumptions here
+/// are based on what the web console does:
+///   - `EU` is the legacy alias of `eu-west-1`.
+///   - Buckets in `us-east-1` have no constraint. An empty constraint is
+///     parsed as an empty string, rather than as no constraint, so is
+///     treated the same way.
+///
+/// Every other constraint, including those unknown to the SDK, is already a
+/// region name.
+fn constraint_region(constraint: &BucketLocationConstraint) -> Option<&str> {
+    match constraint {
+        BucketLocationConstraint::Eu => Some("eu-west-1"),
+        constraint => {
+            Some(constraint.as_str())
+                .filter(|location| !location.is_empty())
+        },
+    }
+}
+
+/// Returns the S3 endpoint that the SDK resolved for the given `config`, if
+/// any.
+///
+/// This follows the SDK's own resolution order. An endpoint given on the
+/// command line wins, followed by an S3 specific endpoint from the environment
+/// or profile `services` section, followed by the global `endpoint_url` from
+/// the environment or profile.
+fn resolved_endpoint(config: &SdkConfig) -> Option<String> {
+    if config.get_origin("endpoint_url").is_client_config() {
+        return config.endpoint_url().map(ToOwned::to_owned);
+    }
+
+    let key = ServiceConfigKey::builder()
+        .service_id("s3")
+        .env("AWS_ENDPOINT_URL")
+        .profile("endpoint_url")
+        .build()
+        .expect("service config key");
+
+    config.service_config()
+        .and_then(|service_config| service_config.load_config(key))
+        .or_else(|| config.endpoint_url().map(ToOwned::to_owned))
+}
+
+/// Returns the continuation token for the page of a listing of `bucket` that
+/// follows `output`, or `None` if `output` was the last page.
+///
+/// S3 only returns a `NextContinuationToken` when the listing is truncated,
+/// but some S3 compatible storage omits `IsTruncated` or always sets it to
+/// `false`, so the presence of a token alone is used to decide whether to
+/// continue.
+///
+/// An empty token is treated as no token. A token that's the same as the
+/// `previous` one, which was used to request `output`, would list the same
+/// page forever, so the listing stops with a warning instead.
+fn next_continuation_token(
+    output: &ListObjectsV2Output,
+    previous: Option<&str>,
+    bucket: &str,
+) -> Option<String> {
+    let token = output.next_continuation_token()
+        .filter(|token| !token.is_empty());
+
+    if token.is_none() && output.is_truncated() == Some(true) {
+        warn!(
+            "Listing of '{}' is truncated without a continuation token, the size may be incomplete",
+            bucket,
+        );
+    }
+
+    if token.is_some() && token == previous {
+        warn!(
+            "Listing of '{}' returned the same continuation token twice, the size may be incomplete",
+            bucket,
+        );
+
+        return None;
+    }
+
+    token.map(ToOwned::to_owned)
+}
+
+/// Return the later of `newest` and the newest `last_modified` time of the
+/// `objects` in a page of a listing.
+fn newest_in_page(
+    newest: Option<DateTime>,
+    objects: &[Object],
+) -> Option<DateTime> {
+    let page_newest = objects.iter()
+        .filter_map(Object::last_modified)
+        .max()
+        .copied();
+
+    newest.max(page_newest)
+}
+
+/// Returns the storage class of `object`.
+///
+/// Objects without a storage class, which some S3 compatible storage doesn't
+/// report, are counted as `STANDARD`.
+fn object_storage_class(object: &Object) -> &str {
+    object.storage_class()
+        .map_or("STANDARD", ObjectStorageClass::as_str)
+}
+
+/// Returns a `bool` indicating if objects in the storage `class` pass the
+/// storage class `filter`.
+///
+/// Every storage class passes an empty `filter`.
+fn is_wanted_storage_class(filter: &[String], class: &str) -> bool {
+    filter.is_empty() || filter.iter().any(|wanted| wanted == class)
+}
+
+/// Add the sizes of the `objects` in a page of a listing to the running
+/// totals of each storage class in `classes`, if they're being totalled.
+///
+/// Only storage classes passing the storage class `filter` are totalled.
+/// Sizes that are invalid are left out, they're reported when the page's
+/// sizes are added to the total.
+fn add_storage_classes(
+    classes: Option<StorageClasses>,
+    objects: &[Object],
+    filter: &[String],
+) -> Option<StorageClasses> {
+    let mut classes = classes?;
+
+    for object in objects {
+        let class = object_storage_class(object);
+
+        if !is_wanted_storage_class(filter, class) {
+            continue;
+        }
+
+        let size = object.size()
+            .and_then(|size| u64::try_from(size).ok())
+            .unwrap_or_default();
+
+        let total = classes.entry(class.to_string()).or_insert(0);
+        *total = total.saturating_add(size);
+    }
+
+    Some(classes)
+}
+
+/// Return the sum of the storage class totals `a` and `b`.
+fn merge_storage_classes(
+    a: Option<StorageClasses>,
+    b: Option<StorageClasses>,
+) -> Option<StorageClasses> {
+    let mut a = a?;
+
+    for (class, size) in b.into_iter().flatten() {
+        let total = a.entry(class).or_insert(0);
+        *total = total.saturating_add(size);
+    }
+
+    Some(a)
+}
+
+/// Return a `BucketSize` for the `current` objects of a bucket.
+fn current_objects_size(current: CurrentObjects) -> Result<BucketSize> {
+    let (size, newest, storage_classes) = current;
+
+    let newest_object = newest
+        .map(SystemTime::try_from)
+        .transpose()
+        .context("object last modified")?;
+
+    let size = BucketSize {
+        newest_object,
+        storage_classes,
+        bytes: size.bytes,
+        count: Some(size.count),
+        ..Default::default()
+    };
+
+    Ok(size)
+}
+
+/// The result of checking our access to a bucket.
+#[derive(Debug, Eq, PartialEq)]
+pub enum BucketAccess {
+    /// The bucket exists and we have access to it.
+    Allowed,
+
+    /// The bucket doesn't exist or we don't have access to it.
+    Denied,
+
+    /// The bucket exists, but in the given region rather than ours.
+    WrongRegion(Region),
+}
 
 /// The S3 `Client`.
+#[derive(Clone)]
 pub struct Client {
     /// The AWS SDK `S3Client`.
     pub client: S3Client,
 
-    /// Selected bucket name, if any.
-    pub bucket_name: Option<String>,
+    /// Whether to size buckets in every region, not just our `region`.
+    pub all_regions: bool,
+
+    /// Whether to assume all buckets are in our `region`.
+    pub assume_region: bool,
+
+    /// Selected bucket names, if any.
+    pub bucket_names: Vec<String>,
+
+    /// Whether to size each object version type separately.
+    pub breakdown_versions: bool,
+
+    /// Patterns matching the names of buckets to skip.
+    pub exclude: Vec<Pattern>,
+
+    /// Whether to fall back to sizing current objects when listing object
+    /// versions is unsupported, even when not using a custom endpoint.
+    pub fallback_on_unsupported: bool,
+
+    /// Custom endpoint that the client is connected to, if any.
+    ///
+    /// This may have been given on the command line, or configured in the
+    /// environment or the selected profile.
+    pub endpoint: Option<String>,
+
+    /// Whether to only keep buckets missing a lifecycle rule expiring
+    /// non-current object versions.
+    pub missing_lifecycle: bool,
+
+    /// Whether to find the age of the oldest in-progress multipart upload.
+    pub multipart_age: bool,
+
+    /// Only size in-progress multipart uploads initiated longer ago than this,
+    /// if given.
+    pub multipart_older_than: Option<Duration>,
 
     /// Configuration for which objects to list in the bucket.
     pub object_versions: ObjectVersions,
 
+    /// Whether to list top level prefixes concurrently when sizing current
+    /// objects.
+    pub parallel_prefixes: bool,
+
+    /// Whether to sum the object sizes in each page of a listing in parallel.
+    pub parallel_sum: bool,
+
+    /// Only size objects with keys beginning with this prefix, if given.
+    pub prefix: Option<String>,
+
+    /// Whether to look up all bucket locations concurrently during discovery.
+    pub prewarm: bool,
+
     /// `Region` that we're listing buckets in.
     pub region: Region,
+
+    /// `S3Client`s for the other regions that buckets have been found in,
+    /// when sizing buckets in all regions.
+    pub regional_clients: RegionalClients,
+
+    /// Agreement to pay for requests to requester pays buckets, if given.
+    pub request_payer: Option<RequestPayer>,
+
+    /// How throttled listing requests are retried.
+    pub retry: Retry,
+
+    /// Whether to warn about potential problems found while sizing.
+    pub stats: bool,
+
+    /// Only size objects in these storage classes, or every storage class
+    /// if this is empty.
+    pub storage_class_filter: Vec<String>,
+
+    /// Whether to break down the size of current objects by storage class.
+    pub storage_classes: bool,
+
+    /// Whether to look up versioning when sizing current objects, to warn
+    /// about non-current versions that aren't counted.
+    pub warn_versioned: bool,
 }
 
 impl Client {
@@ -48,41 +408,250 @@ impl Client {
         let s3config = aws_config::from_env()
             .region(region.clone());
 
-        let s3config = if let Some(endpoint) = config.endpoint {
+        let s3config = if let Some(endpoint) = &config.endpoint {
             s3config.endpoint_url(endpoint)
         }
         else {
             s3config
         };
 
+        let s3config = if let Some(profile) = &config.profile {
+            s3config.profile_name(profile)
+        }
+        else {
+            s3config
+        };
+
+        let http_client = connection::http_client(
+            config.proxy.as_deref(),
+            config.max_connections,
+        );
+
+        let s3config = if let Some(http_client) = http_client {
+            s3config.http_client(http_client)
+        }
+        else {
+            s3config
+        };
+
         let s3config = s3config
             .load()
             .await;
 
-        let client = S3Client::new(&s3config);
+        let s3config = credentials::assume_role(
+            s3config,
+            config.role_arn.as_deref(),
+            config.external_id.as_deref(),
+        ).await;
+
+        // Throttled requests are counted so that we can suggest slowing
+        // down once sizing is done. Path style addressing is only allowed
+        // with a custom endpoint.
+        let client = S3Client::from_conf(
+            aws_sdk_s3::config::Builder::from(&s3config)
+                .force_path_style(config.path_style)
+                .interceptor(config.throttle_counter)
+                .build()
+        );
+
+        // The endpoint may have come from the profile or environment rather
+        // than the command line.
+        let endpoint = resolved_endpoint(&s3config);
+
+        debug!("new: Using endpoint {:?}", endpoint);
 
         Self {
             client,
+            endpoint,
             region,
-            bucket_name:     config.bucket_name,
-            object_versions: config.object_versions,
+            all_regions:             config.all_regions,
+            assume_region:           config.assume_region,
+            breakdown_versions:      config.breakdown_versions,
+            bucket_names:            config.bucket_names,
+            exclude:                 config.exclude,
+            fallback_on_unsupported: config.fallback_on_unsupported,
+            missing_lifecycle:       config.missing_lifecycle,
+            multipart_age:           config.multipart_age,
+            multipart_older_than:    config.multipart_older_than,
+            object_versions:         config.object_versions,
+            parallel_prefixes:       config.parallel_prefixes,
+            parallel_sum:            config.parallel_sum,
+            prefix:                  config.prefix,
+            prewarm:                 config.prewarm,
+            regional_clients:        RegionalClients::default(),
+            request_payer:           config.request_payer
+                .then_some(RequestPayer::Requester),
+            retry:                   Retry::new(
+                config.max_retries,
+                config.retry_base_delay,
+            ),
+            stats:                   config.stats,
+            storage_class_filter:    config.storage_class_filter,
+            storage_classes:         config.storage_classes,
+            warn_versioned:          config.warn_versioned,
+        }
+    }
+
+    /// Return a `Client` that can make requests to buckets in `region`.
+    ///
+    /// This is ourselves unless we're sizing buckets in all regions and
+    /// `region` isn't ours. In that case, a copy of ourselves using an
+    /// `S3Client` in `region` is returned. Each region's `S3Client` is only
+    /// created once, sharing our credentials, connections and settings.
+    ///
+    /// Custom endpoints have no regions to switch between, so are always
+    /// used as they are.
+    pub fn for_region(&self, region: &Region) -> Cow<'_, Self> {
+        if !self.all_regions
+            || *region == self.region
+            || self.is_custom_endpoint()
+        {
+            return Cow::Borrowed(self);
+        }
+
+        let client = self.regional_clients
+            .lock()
+            .expect("regional clients lock")
+            .entry(region.name().to_string())
+            .or_insert_with(|| {
+                debug!(
+                    "for_region: Creating S3Client in region '{}'",
+                    region.name(),
+                );
+
+                let config = self.client.config()
+                    .to_builder()
+                    .region(aws_sdk_s3::config::Region::new(
+                        region.name().to_string(),
+                    ))
+                    .build();
+
+                S3Client::from_conf(config)
+            })
+            .clone();
+
+        let mut regional = self.clone();
+        regional.client = client;
+        regional.region = region.clone();
+
+        Cow::Owned(regional)
+    }
+
+    /// Add the sizes of the `items` in `page` of a listing of `bucket` to the
+    /// running `total`, counting each size as one object.
+    ///
+    /// The sizes are summed in parallel with `rayon`, unless `parallel_sum`
+    /// has been disabled, in which case they're summed on the current thread.
+    ///
+    /// S3 never reports negative sizes or sizes that would overflow the total,
+    /// but non-conforming S3 compatible storage might, so these are reported
+    /// as an error rather than being trusted.
+    fn add_page_sizes<T, F>(
+        &self,
+        total: SizeAndCount,
+        items: &[T],
+        size: F,
+        bucket: &str,
+        page: usize,
+    ) -> Result<SizeAndCount>
+    where
+        T: Sync,
+        F: Fn(&T) -> Option<i64> + Send + Sync,
+    {
+        let to_size = |size: i64| u64::try_from(size).ok().map(SizeAndCount::from);
+
+        let page_size = if self.parallel_sum {
+            items.par_iter()
+                .filter_map(&size)
+                .map(to_size)
+                .try_fold(SizeAndCount::default, |sum, size| {
+                    sum.checked_add(size?)
+                })
+                .try_reduce(SizeAndCount::default, SizeAndCount::checked_add)
         }
+        else {
+            items.iter()
+                .filter_map(&size)
+                .map(to_size)
+                .try_fold(SizeAndCount::default(), |sum, size| {
+                    sum.checked_add(size?)
+                })
+        };
+
+        page_size
+            .and_then(|size| total.checked_add(size))
+            .ok_or_else(|| {
+                anyhow!(
+                    "Invalid object size in page {} of the listing for '{}'",
+                    page,
+                    bucket,
+                )
+            })
     }
 
-    /// Returns a list of bucket names.
-    pub async fn list_buckets(&self) -> Result<BucketNames> {
+    /// Returns a stream of pages of bucket names, along with the time that
+    /// each bucket was created, if known.
+    ///
+    /// Buckets are listed up to `LIST_BUCKETS_PAGE_SIZE` at a time, so that
+    /// accounts with huge numbers of buckets don't need to be listed in full
+    /// before the first bucket can be sized. Custom endpoints are asked for
+    /// every bucket at once, as not all S3 compatible storage paginates
+    /// `ListBuckets`.
+    pub fn list_buckets(
+        &self,
+    ) -> impl Stream<Item = Result<ListedBuckets>> + Send + '_ {
         debug!("list_buckets");
 
-        let output = self.client.list_buckets().send().await?;
+        let page_size = if self.is_custom_endpoint() {
+            None
+        }
+        else {
+            Some(LIST_BUCKETS_PAGE_SIZE)
+        };
+
+        // The state is the continuation token of the next page to list, the
+        // first page has none and the listing ends once there's no state.
+        stream::try_unfold(Some(None), move |token| async move {
+            let Some(token) = token else {
+                return Ok(None)
+            };
 
-        let bucket_names = output.buckets()
-            .par_iter()
-            .filter_map(|bucket| bucket.name.clone())
-            .collect();
+            let output = self.client.list_buckets()
+                .set_max_buckets(page_size)
+                .set_continuation_token(token.clone())
+                .send()
+                .await?;
+
+            let buckets: ListedBuckets = output.buckets()
+                .par_iter()
+                .filter_map(|bucket| {
+                    let created = bucket.creation_date
+                        .and_then(|created| SystemTime::try_from(created).ok());
+
+                    bucket.name.clone().map(|name| (name, created))
+                })
+                .collect();
 
-        debug!("Found buckets: {:?}", bucket_names);
+            debug!("Found buckets: {:?}", buckets);
 
-        Ok(bucket_names)
+            // As with object listings, an empty token is treated as no token
+            // and a repeated token stops the listing rather than listing the
+            // same page forever.
+            let mut next = output.continuation_token()
+                .filter(|next| !next.is_empty());
+
+            if next.is_some() && next == token.as_deref() {
+                warn!(
+                    "Listing buckets returned the same continuation token twice, some buckets may be missing",
+                );
+
+                next = None;
+            }
+
+            let next = next.map(|next| Some(next.to_owned()));
+
+            Ok(Some((buckets, next)))
+        })
     }
 
     /// Return the bucket location (`Region`) for the given `bucket`.
@@ -90,6 +659,10 @@ impl Client {
     /// This method will properly handle the case of the `null` (empty) and
     /// `EU` location constraints, by replacing them with `us-east-1` and
     /// `eu-west-1` respectively.
+    ///
+    /// When connected to a custom endpoint, a `null` location constraint is
+    /// replaced with the client `Region` instead, as `us-east-1` has no
+    /// meaning there.
     pub async fn get_bucket_location(&self, bucket: &str) -> Result<Region> {
         debug!("get_bucket_location for '{}'", bucket);
 
@@ -100,25 +673,178 @@ impl Client {
 
         debug!("GetBucketLocation API returned '{:?}'", output);
 
-        // Location constraints for sufficiently old buckets in S3 may not
-        // quite meet expectations. These returns are badly documented and the
-        // assumptions here are based on what the web console does.
-        let location = match output.location_constraint() {
-            Some(BucketLocationConstraint::Eu) => "eu-west-1".to_string(),
-            Some(location)                     => location.as_str().to_string(),
-            None                               => "us-east-1".to_string(),
+        let location = output.location_constraint()
+            .and_then(constraint_region);
+
+        let location = match location {
+            Some(location)                    => location,
+            None if self.is_custom_endpoint() => self.region.name(),
+            None                              => "us-east-1",
         };
 
-        let location = Region::new().set_region(&location);
+        let location = Region::new().set_region(location);
 
         debug!("Final location: {:?}", location);
 
         Ok(location)
     }
 
-    /// Returns a `bool` indicating if we have access to the given `bucket` or
-    /// not.
-    pub async fn head_bucket(&self, bucket: &str) -> bool {
+    /// Returns the `Region` that the given `bucket` is in.
+    ///
+    /// If `assume_region` is set, the bucket is assumed to be in our region
+    /// without looking up its location. Some S3 compatible storage doesn't
+    /// implement `GetBucketLocation`, so when using a custom endpoint a
+    /// failed lookup also results in our region.
+    pub async fn bucket_region(&self, bucket: &str) -> Result<Region> {
+        if self.assume_region {
+            debug!("Assuming '{}' is in our region", bucket);
+
+            return Ok(self.region.clone());
+        }
+
+        debug!("Retrieving location for '{}'", bucket);
+
+        match self.get_bucket_location(bucket).await {
+            Ok(region) => Ok(region),
+            Err(e) if self.is_custom_endpoint() => {
+                warn!(
+                    "Couldn't get location for '{}', assuming our region: {}",
+                    bucket,
+                    e,
+                );
+
+                Ok(self.region.clone())
+            },
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Returns the `Region` of each of the given `buckets`, keyed by bucket
+    /// name.
+    ///
+    /// Up to `PREWARM_LIMIT` locations are looked up concurrently.
+    pub async fn bucket_regions(
+        &self,
+        buckets: &[String],
+    ) -> Result<HashMap<String, Region>> {
+        debug!("bucket_regions: Prewarming {} locations", buckets.len());
+
+        stream::iter(buckets.iter().cloned())
+            .map(|bucket| async move {
+                let region = self.bucket_region(&bucket).await?;
+
+                Ok((bucket, region))
+            })
+            .buffer_unordered(PREWARM_LIMIT)
+            .try_collect()
+            .await
+    }
+
+    /// Returns a `bool` indicating if the given `bucket` has an enabled
+    /// lifecycle rule that expires non-current object versions.
+    ///
+    /// A bucket without any lifecycle configuration doesn't have such a rule.
+    pub async fn has_noncurrent_expiration(&self, bucket: &str) -> Result<bool> {
+        debug!("has_noncurrent_expiration for '{}'", bucket);
+
+        let output = self.client.get_bucket_lifecycle_configuration()
+            .bucket(bucket)
+            .send()
+            .await;
+
+        let output = match output {
+            Ok(output) => output,
+            Err(e) if e.code() == Some("NoSuchLifecycleConfiguration") => {
+                return Ok(false)
+            },
+            Err(e) => return Err(e.into()),
+        };
+
+        debug!("Lifecycle rules for '{}': {:?}", bucket, output.rules());
+
+        let has_expiration = output.rules()
+            .iter()
+            .any(|rule| {
+                *rule.status() == ExpirationStatus::Enabled
+                    && rule.noncurrent_version_expiration().is_some()
+            });
+
+        Ok(has_expiration)
+    }
+
+    /// Returns a `bool` indicating if the given `bucket` has versioning
+    /// enabled, or suspended, in which case it may hold non-current object
+    /// versions.
+    ///
+    /// This is only a hint, so if the versioning status can't be found a
+    /// warning is logged and `None` is returned.
+    pub async fn is_versioned(&self, bucket: &str) -> Option<bool> {
+        debug!("is_versioned for '{}'", bucket);
+
+        let output = self.client.get_bucket_versioning()
+            .bucket(bucket)
+            .send()
+            .await;
+
+        match output {
+            Ok(output) => {
+                debug!("Versioning for '{}': {:?}", bucket, output.status());
+
+                // Buckets that have never had versioning enabled don't return
+                // a status at all.
+                let is_versioned = matches!(
+                    output.status(),
+                    Some(BucketVersioningStatus::Enabled
+                        | BucketVersioningStatus::Suspended),
+                );
+
+                Some(is_versioned)
+            },
+            // When sizing current objects the status only decides whether
+            // to warn about uncounted versions, so failing to find it isn't
+            // worth a warning of its own.
+            Err(e) if self.object_versions == ObjectVersions::Current => {
+                debug!(
+                    "Couldn't get versioning status for '{}': {}",
+                    bucket,
+                    DisplayErrorContext(&e),
+                );
+
+                None
+            },
+            Err(e) => {
+                warn!(
+                    "Couldn't get versioning status for '{}': {}",
+                    bucket,
+                    DisplayErrorContext(&e),
+                );
+
+                None
+            },
+        }
+    }
+
+    /// Returns a `bool` indicating if the versioning status of each bucket
+    /// should be looked up during discovery.
+    ///
+    /// This costs an extra request per bucket, so is only done when the
+    /// status is used. When sizing current objects it's only used to warn
+    /// that non-current versions aren't counted, which must be asked for.
+    pub fn needs_versioning(&self) -> bool {
+        match self.object_versions {
+            ObjectVersions::Current    => self.warn_versioned,
+            ObjectVersions::NonCurrent => true,
+            _                          => false,
+        }
+    }
+
+    /// Returns a `BucketAccess` indicating if we have access to the given
+    /// `bucket` or not.
+    ///
+    /// When a bucket in another region is requested, S3 will fail the request
+    /// but return the region of the bucket in the `x-amz-bucket-region`
+    /// header. In this case the region is returned as a hint.
+    pub async fn head_bucket(&self, bucket: &str) -> BucketAccess {
         debug!("head_bucket for '{}'", bucket);
 
         let output = self.client.head_bucket()
@@ -128,7 +854,47 @@ impl Client {
 
         debug!("head_bucket output for '{}' -> '{:?}'", bucket, output);
 
-        output.is_ok()
+        let Err(error) = output else {
+            return BucketAccess::Allowed
+        };
+
+        // S3 may also return the header for buckets in our own region, such
+        // as when access is denied, so only differing regions are a hint.
+        let region_hint = error.raw_response()
+            .and_then(|response| response.headers().get("x-amz-bucket-region"))
+            .filter(|region| *region != self.region.name());
+
+        match region_hint {
+            Some(region) => {
+                BucketAccess::WrongRegion(Region::new().set_region(region))
+            },
+            None => BucketAccess::Denied,
+        }
+    }
+
+    /// Returns a bool indicating if the client is connected to a custom
+    /// endpoint.
+    pub fn is_custom_endpoint(&self) -> bool {
+        self.endpoint.is_some()
+    }
+
+    /// Return the size of the current `object`, or `None` if it has no size
+    /// or isn't in a storage class that we're sizing.
+    fn object_size(&self, object: &Object) -> Option<i64> {
+        let class = object_storage_class(object);
+
+        object.size()
+            .filter(|_| is_wanted_storage_class(&self.storage_class_filter, class))
+    }
+
+    /// Returns a bool indicating if unsupported operations should be
+    /// tolerated, by falling back to operations that are supported.
+    ///
+    /// This is always the case for custom endpoints, as S3 compatible storage
+    /// often doesn't implement every operation, and otherwise only if
+    /// `fallback_on_unsupported` was requested.
+    pub fn tolerates_unsupported(&self) -> bool {
+        self.is_custom_endpoint() || self.fallback_on_unsupported
     }
 
     /// Returns a bool indicating if the region is a custom region
@@ -138,27 +904,27 @@ impl Client {
             .contains(&self.region.name())
     }
 
-    /// List in-progress multipart uploads
-    async fn size_multipart_uploads(&self, bucket: &str) -> Result<u64> {
+    /// List in-progress multipart uploads, limited to those with keys under
+    /// our `prefix` if one was given.
+    async fn list_multipart_uploads(
+        &self,
+        bucket: &str,
+    ) -> Result<Vec<MultipartUpload>> {
         let mut key_marker       = None;
-        let mut size             = 0;
         let mut upload_id_marker = None;
+        let mut uploads          = Vec::new();
 
         loop {
-            let output = self.client.list_multipart_uploads()
+            let request = self.client.list_multipart_uploads()
                 .bucket(bucket)
                 .set_key_marker(key_marker)
-                .set_upload_id_marker(upload_id_marker)
-                .send()
-                .await?;
+                .set_prefix(self.prefix.clone())
+                .set_request_payer(self.request_payer.clone())
+                .set_upload_id_marker(upload_id_marker);
 
-            // No iterator here since we need to call an async method.
-            for upload in output.uploads() {
-                let key       = upload.key().expect("upload key");
-                let upload_id = upload.upload_id().expect("upload_id");
+            let output = self.retry.send(|| request.clone().send()).await?;
 
-                size += self.size_parts(bucket, key, upload_id).await?;
-            }
+            uploads.extend_from_slice(output.uploads());
 
             if matches!(output.is_truncated(), Some(true)) {
                 key_marker = output.next_key_marker()
@@ -172,66 +938,221 @@ impl Client {
             }
         }
 
+        Ok(uploads)
+    }
+
+    /// Return the time that the oldest in-progress multipart upload in the
+    /// `bucket` was initiated, if there are any uploads.
+    pub async fn oldest_multipart_upload(
+        &self,
+        bucket: &str,
+    ) -> Result<Option<SystemTime>> {
+        debug!("oldest_multipart_upload: '{}'", bucket);
+
+        let uploads = self.list_multipart_uploads(bucket).await?;
+
+        let mut oldest = None;
+
+        for upload in uploads {
+            let Some(initiated) = upload.initiated() else {
+                continue
+            };
+
+            let initiated = SystemTime::try_from(*initiated)
+                .context("multipart upload initiated")?;
+
+            oldest = Some(oldest.map_or(initiated, |o| initiated.min(o)));
+        }
+
+        Ok(oldest)
+    }
+
+    /// Returns a `bool` indicating if the `upload` was initiated longer ago
+    /// than `multipart_older_than`, or `true` if no threshold was given.
+    ///
+    /// Uploads without a known initiated time are never considered stale.
+    fn is_stale_upload(
+        &self,
+        upload: &MultipartUpload,
+        now: SystemTime,
+    ) -> Result<bool> {
+        let Some(older_than) = self.multipart_older_than else {
+            return Ok(true)
+        };
+
+        let Some(initiated) = upload.initiated() else {
+            return Ok(false)
+        };
+
+        let initiated = SystemTime::try_from(*initiated)
+            .context("multipart upload initiated")?;
+
+        let stale = now.duration_since(initiated)
+            .is_ok_and(|age| age > older_than);
+
+        Ok(stale)
+    }
+
+    /// Size in-progress multipart uploads, limited to those older than
+    /// `multipart_older_than` if it was given.
+    async fn size_multipart_uploads(&self, bucket: &str) -> Result<u64> {
+        let now      = SystemTime::now();
+        let mut size = 0;
+
+        // No iterator here since we need to call an async method.
+        for upload in self.list_multipart_uploads(bucket).await? {
+            let key       = upload.key().expect("upload key");
+            let upload_id = upload.upload_id().expect("upload_id");
+
+            if !self.is_stale_upload(&upload, now)? {
+                debug!(
+                    "size_multipart_uploads: Skipping recent upload '{}' of '{}'",
+                    upload_id,
+                    key,
+                );
+
+                continue;
+            }
+
+            let upload_size = self.size_parts(bucket, key, upload_id).await?;
+
+            // Uploads without any sized parts may be stuck, or their parts
+            // may be hidden from us by permissions.
+            if upload_size == 0 && self.stats {
+                warn!(
+                    "Multipart upload '{}' of '{}' in '{}' has no sized parts",
+                    upload_id,
+                    key,
+                    bucket,
+                );
+            }
+
+            size += upload_size;
+        }
+
         Ok(size)
     }
 
-    /// List object versions and filter according to `ObjectVersions`.
+    /// List object versions and filter according to the given `versions`,
+    /// returning their total size and how many there were.
     ///
     /// This will be used when the size of `All` or `NonCurrent` objects is
     /// requested.
-    async fn size_object_versions(&self, bucket: &str) -> Result<u64> {
-        debug!("size_object_versions for '{}'", bucket);
+    async fn size_object_versions(
+        &self,
+        bucket: &str,
+        versions: &ObjectVersions,
+    ) -> Result<SizeAndCount> {
+        debug!("size_object_versions for '{}' with {:?}", bucket, versions);
+
+        let (current, noncurrent) = self.size_versions_by_latest(
+            bucket,
+            versions,
+        ).await?;
+
+        let size = match versions {
+            ObjectVersions::All           => {
+                current.checked_add(noncurrent)
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "Size of object versions in '{}' overflowed",
+                            bucket,
+                        )
+                    })?
+            },
+            ObjectVersions::Current       => current,
+            ObjectVersions::DeleteMarkers => unreachable!(),
+            ObjectVersions::Multipart     => unreachable!(),
+            ObjectVersions::NonCurrent    => noncurrent,
+        };
+
+        Ok(size)
+    }
 
+    /// List object versions, returning the total size and count of the
+    /// current versions and of the non-current versions separately.
+    ///
+    /// Both come from the same listing, so an object that's overwritten while
+    /// the bucket is being listed is counted once, as either a current or a
+    /// non-current version.
+    ///
+    /// If listing object versions is unsupported and that's tolerated, the
+    /// objects wanted by `versions` are sized as current versions instead.
+    async fn size_versions_by_latest(
+        &self,
+        bucket: &str,
+        versions: &ObjectVersions,
+    ) -> Result<(SizeAndCount, SizeAndCount)> {
         let mut next_key_marker        = None;
         let mut next_version_id_marker = None;
-        let mut size                   = 0;
+        let mut current                = SizeAndCount::default();
+        let mut noncurrent             = SizeAndCount::default();
 
         // Loop until all object versions are processed
-        loop {
-            let output = self.client.list_object_versions()
+        for page in 1.. {
+            let request = self.client.list_object_versions()
                 .bucket(bucket)
                 .set_key_marker(next_key_marker)
-                .set_version_id_marker(next_version_id_marker)
-                .send()
-                .await?;
-
-            // Depending on which object versions we're paying attention to,
-            // we may or may not filter here.
-            let version_size = output.versions()
-                .par_iter()
-                .map(|v| {
-                    // Here we take our object version selection into
-                    // account.
-                    //
-                    // We return a size of 0 if we aren't interested in an
-                    // object version.
-                    //
-                    // Multipart isn't handled here.
-                    match self.object_versions {
-                        ObjectVersions::All     => v.size().unwrap_or(0),
-                        ObjectVersions::Current => {
-                            if v.is_latest() == Some(true) {
-                                v.size().unwrap_or(0)
-                            }
-                            else {
-                                0
-                            }
-                        },
-                        ObjectVersions::Multipart => unreachable!(),
-                        ObjectVersions::NonCurrent => {
-                            if v.is_latest() == Some(true) {
-                                0
-                            }
-                            else {
-                                v.size().unwrap_or(0)
-                            }
-                        },
-                    }
-                })
-                .sum::<i64>();
-
-            size += u64::try_from(version_size)
-                .context("version size")?;
+                .set_prefix(self.prefix.clone())
+                .set_request_payer(self.request_payer.clone())
+                .set_version_id_marker(next_version_id_marker);
+
+            let output = self.retry.send(|| request.clone().send()).await;
+
+            let output = match output {
+                Ok(output) => output,
+                Err(e) if self.tolerates_unsupported()
+                    && is_unsupported_operation(&e) =>
+                {
+                    warn!(
+                        "Listing object versions in '{}' is unsupported, \
+                         only current objects will be sized",
+                        bucket,
+                    );
+
+                    let current = self.size_unversioned_objects(
+                        bucket,
+                        versions,
+                    ).await?;
+
+                    return Ok((current, SizeAndCount::default()));
+                },
+                Err(e) => return Err(e.into()),
+            };
+
+            // Object versions are split by whether they're the latest
+            // version, only counting those in the wanted storage classes.
+            //
+            // Multipart isn't handled here.
+            let version_size = |v: &ObjectVersion, latest: bool| {
+                let is_latest = v.is_latest() == Some(true);
+
+                let class = v.storage_class()
+                    .map_or("STANDARD", ObjectVersionStorageClass::as_str);
+
+                let wanted = is_latest == latest && is_wanted_storage_class(
+                    &self.storage_class_filter,
+                    class,
+                );
+
+                v.size().filter(|_| wanted)
+            };
+
+            current = self.add_page_sizes(
+                current,
+                output.versions(),
+                |v| version_size(v, true),
+                bucket,
+                page,
+            )?;
+
+            noncurrent = self.add_page_sizes(
+                noncurrent,
+                output.versions(),
+                |v| version_size(v, false),
+                bucket,
+                page,
+            )?;
 
             // Check if we need to continue processing bucket output and store
             // the continuation tokens for the next loop if so.
@@ -247,40 +1168,55 @@ impl Client {
             }
         }
 
-        Ok(size)
+        Ok((current, noncurrent))
     }
 
-    /// Return the size of current object versions in the bucket.
+    /// Return the number of delete markers in the bucket, limited to those
+    /// with keys under our `prefix` if one was given.
     ///
-    /// This will be used when the size of `Current` objects is requested.
-    async fn size_current_objects(&self, bucket: &str) -> Result<u64> {
-        debug!("size_current_objects for '{}'", bucket);
+    /// This will be used when `DeleteMarkers` are requested. Buckets that
+    /// don't support listing object versions can't have delete markers, so
+    /// if unsupported operations are tolerated, 0 is returned with a warning.
+    async fn count_delete_markers(&self, bucket: &str) -> Result<u64> {
+        debug!("count_delete_markers for '{}'", bucket);
 
-        let mut continuation_token = None;
-        let mut size               = 0;
+        let mut count                  = 0;
+        let mut next_key_marker        = None;
+        let mut next_version_id_marker = None;
 
-        // Loop until all objects are processed.
         loop {
-            let output = self.client.list_objects_v2()
+            let request = self.client.list_object_versions()
                 .bucket(bucket)
-                .set_continuation_token(continuation_token)
-                .send()
-                .await?;
-
-            // Process the contents and add up the sizes
-            let object_size = output.contents()
-                .par_iter()
-                .filter_map(Object::size)
-                .sum::<i64>();
-
-            size += u64::try_from(object_size)
-                .context("object size")?;
+                .set_key_marker(next_key_marker)
+                .set_prefix(self.prefix.clone())
+                .set_request_payer(self.request_payer.clone())
+                .set_version_id_marker(next_version_id_marker);
+
+            let output = self.retry.send(|| request.clone().send()).await;
+
+            let output = match output {
+                Ok(output) => output,
+                Err(e) if self.tolerates_unsupported()
+                    && is_unsupported_operation(&e) =>
+                {
+                    warn!(
+                        "Listing object versions in '{}' is unsupported, \
+                         it has no delete markers",
+                        bucket,
+                    );
+
+                    return Ok(0);
+                },
+                Err(e) => return Err(e.into()),
+            };
+
+            count += output.delete_markers().len() as u64;
 
-            // If the output was truncated (Some(true)), we should have a
-            // next_continuation_token.
-            // If it wasn't, (Some(false) | None) we're done and can break.
             if matches!(output.is_truncated(), Some(true)) {
-                continuation_token = output.next_continuation_token()
+                next_key_marker = output.next_key_marker()
+                    .map(ToOwned::to_owned);
+
+                next_version_id_marker = output.next_version_id_marker()
                     .map(ToOwned::to_owned);
             }
             else {
@@ -288,35 +1224,315 @@ impl Client {
             }
         }
 
-        Ok(size)
+        Ok(count)
+    }
+
+    /// Size objects in a bucket that doesn't support listing object versions.
+    ///
+    /// Without object versions, every object is a current object, so there
+    /// are no non-current objects to size.
+    async fn size_unversioned_objects(
+        &self,
+        bucket: &str,
+        versions: &ObjectVersions,
+    ) -> Result<SizeAndCount> {
+        match versions {
+            ObjectVersions::NonCurrent => Ok(SizeAndCount::default()),
+            _                          => {
+                let size = self.size_current_objects(bucket).await?;
+
+                let size = SizeAndCount {
+                    bytes: size.bytes,
+                    count: size.count.unwrap_or_default(),
+                };
+
+                Ok(size)
+            },
+        }
+    }
+
+    /// Return the size and count of current object versions in the bucket,
+    /// along with the time that the newest object was last modified.
+    ///
+    /// This will be used when the size of `Current` objects is requested.
+    ///
+    /// Only objects under our `prefix` are sized, if one was given. If
+    /// `parallel_prefixes` is enabled, the top level prefixes of the bucket,
+    /// or of our `prefix`, will be listed concurrently.
+    pub async fn size_current_objects(
+        &self,
+        bucket: &str,
+    ) -> Result<BucketSize> {
+        debug!("size_current_objects for '{}'", bucket);
+
+        let current = if self.parallel_prefixes {
+            self.size_current_objects_parallel(bucket).await?
+        }
+        else {
+            self.size_current_objects_with_prefix(
+                bucket,
+                self.prefix.as_deref(),
+            ).await?
+        };
+
+        current_objects_size(current)
+    }
+
+    /// Return the size and count of current object versions in the bucket,
+    /// and the newest modification time, by listing each top level prefix
+    /// concurrently.
+    ///
+    /// Prefixes are discovered by listing the bucket, under our `prefix` if
+    /// one was given, with a `/` delimiter. Objects found at the top level
+    /// during discovery are sized directly.
+    /// Each prefix is then listed sequentially by continuation token, with up
+    /// to `PARALLEL_PREFIX_LIMIT` prefixes being listed at once.
+    async fn size_current_objects_parallel(
+        &self,
+        bucket: &str,
+    ) -> Result<CurrentObjects> {
+        debug!("size_current_objects_parallel for '{}'", bucket);
+
+        let mut classes            = self.storage_classes.then(BTreeMap::new);
+        let mut continuation_token = None;
+        let mut newest             = None;
+        let mut prefixes           = Vec::new();
+        let mut size               = SizeAndCount::default();
+
+        // Discover the top level prefixes.
+        for page in 1.. {
+            let request = self.client.list_objects_v2()
+                .bucket(bucket)
+                .delimiter("/")
+                .set_continuation_token(continuation_token.clone())
+                .set_prefix(self.prefix.clone())
+                .set_request_payer(self.request_payer.clone());
+
+            let output = self.retry.send(|| request.clone().send()).await?;
+
+            size = self.add_page_sizes(
+                size,
+                output.contents(),
+                |object| self.object_size(object),
+                bucket,
+                page,
+            )?;
+
+            newest  = newest_in_page(newest, output.contents());
+            classes = add_storage_classes(
+                classes,
+                output.contents(),
+                &self.storage_class_filter,
+            );
+
+            prefixes.extend(
+                output.common_prefixes()
+                    .iter()
+                    .filter_map(|prefix| prefix.prefix().map(ToOwned::to_owned))
+            );
+
+            let next = next_continuation_token(
+                &output,
+                continuation_token.as_deref(),
+                bucket,
+            );
+
+            let Some(token) = next else {
+                break
+            };
+
+            continuation_token = Some(token);
+        }
+
+        debug!("Listing {} prefixes in '{}'", prefixes.len(), bucket);
+
+        let prefixes_size = stream::iter(prefixes)
+            .map(|prefix| async move {
+                self.size_current_objects_with_prefix(bucket, Some(&prefix))
+                    .await
+            })
+            .buffer_unordered(PARALLEL_PREFIX_LIMIT)
+            .try_fold((size, newest, classes), |total, prefix| async move {
+                let size = total.0.checked_add(prefix.0)
+                    .ok_or_else(|| anyhow!("Size of '{}' is too large", bucket))?;
+
+                let classes = merge_storage_classes(total.2, prefix.2);
+
+                Ok((size, total.1.max(prefix.1), classes))
+            })
+            .await?;
+
+        Ok(prefixes_size)
+    }
+
+    /// Return the size and count of current object versions in the bucket,
+    /// optionally limited to those under the given `prefix`.
+    ///
+    /// The time that the newest object was last modified is taken from the
+    /// same listing, so this doesn't cost any extra requests.
+    async fn size_current_objects_with_prefix(
+        &self,
+        bucket: &str,
+        prefix: Option<&str>,
+    ) -> Result<CurrentObjects> {
+        let mut classes            = self.storage_classes.then(BTreeMap::new);
+        let mut continuation_token = None;
+        let mut newest             = None;
+        let mut size               = SizeAndCount::default();
+
+        // Loop until all objects are processed.
+        for page in 1.. {
+            let request = self.client.list_objects_v2()
+                .bucket(bucket)
+                .set_continuation_token(continuation_token.clone())
+                .set_prefix(prefix.map(ToOwned::to_owned))
+                .set_request_payer(self.request_payer.clone());
+
+            let output = self.retry.send(|| request.clone().send()).await?;
+
+            // Process the contents and add up the sizes
+            size = self.add_page_sizes(
+                size,
+                output.contents(),
+                |object| self.object_size(object),
+                bucket,
+                page,
+            )?;
+
+            newest  = newest_in_page(newest, output.contents());
+            classes = add_storage_classes(
+                classes,
+                output.contents(),
+                &self.storage_class_filter,
+            );
+
+            // If there's a next_continuation_token there's another page,
+            // otherwise we're done and can break.
+            let next = next_continuation_token(
+                &output,
+                continuation_token.as_deref(),
+                bucket,
+            );
+
+            let Some(token) = next else {
+                break
+            };
+
+            continuation_token = Some(token);
+        }
+
+        Ok((size, newest, classes))
     }
 
     /// A wrapper to call the appropriate bucket sizing function depending on
     /// the `ObjectVersions` configuration the `Client` was created with.
-    pub async fn size_objects(&self, bucket: &str) -> Result<u64> {
+    ///
+    /// When sizing `All` object versions, the split between object versions
+    /// and in-progress multipart uploads is also returned.
+    ///
+    /// The object versions that were sized are counted. In-progress multipart
+    /// uploads aren't objects yet, so they aren't counted, and the count is
+    /// unknown when only sizing `Multipart` uploads.
+    ///
+    /// Delete markers have no size, so when counting `DeleteMarkers` the size
+    /// is always 0 and the number of delete markers is the count.
+    pub async fn size_objects(&self, bucket: &str) -> Result<BucketSize> {
         debug!("size_objects: '{}' with {:?}", bucket, self.object_versions);
 
         match self.object_versions {
             ObjectVersions::All => {
-                let mut size = 0;
+                let multipart = self.size_multipart_uploads(bucket).await?;
+
+                let versions = self.size_object_versions(
+                    bucket,
+                    &ObjectVersions::All,
+                ).await?;
 
-                size += self.size_multipart_uploads(bucket).await?;
-                size += self.size_object_versions(bucket).await?;
+                let split = MultipartSplit {
+                    multipart,
+                    versions: versions.bytes,
+                };
+
+                let size = BucketSize {
+                    bytes:           split.multipart + split.versions,
+                    count:           Some(versions.count),
+                    multipart_split: Some(split),
+                    ..Default::default()
+                };
 
                 Ok(size)
             },
             ObjectVersions::Current => {
                 self.size_current_objects(bucket).await
             },
+            ObjectVersions::DeleteMarkers => {
+                let size = BucketSize {
+                    bytes: 0,
+                    count: Some(self.count_delete_markers(bucket).await?),
+                    ..Default::default()
+                };
+
+                Ok(size)
+            },
             ObjectVersions::Multipart => {
-                self.size_multipart_uploads(bucket).await
+                Ok(self.size_multipart_uploads(bucket).await?.into())
             },
             ObjectVersions::NonCurrent => {
-                self.size_object_versions(bucket).await
+                let size = self.size_object_versions(
+                    bucket,
+                    &ObjectVersions::NonCurrent,
+                ).await?;
+
+                let size = BucketSize {
+                    bytes: size.bytes,
+                    count: Some(size.count),
+                    ..Default::default()
+                };
+
+                Ok(size)
             },
         }
     }
 
+    /// Return the sizes of current objects, non-current objects and
+    /// in-progress multipart uploads in the bucket separately, along with
+    /// their total.
+    ///
+    /// As with `size_objects`, the total includes in-progress multipart
+    /// uploads, but only current and non-current object versions are counted.
+    /// Both are sized from the same listing of object versions, so that they
+    /// agree with each other.
+    ///
+    /// This will be used when a breakdown of `All` objects is requested.
+    pub async fn size_versions_breakdown(
+        &self,
+        bucket: &str,
+    ) -> Result<BucketSize> {
+        debug!("size_versions_breakdown: '{}'", bucket);
+
+        let (current, noncurrent) = self.size_versions_by_latest(
+            bucket,
+            &ObjectVersions::All,
+        ).await?;
+
+        let multipart = self.size_multipart_uploads(bucket).await?;
+
+        let breakdown = VersionsBreakdown {
+            current:    current.bytes,
+            multipart,
+            noncurrent: noncurrent.bytes,
+        };
+
+        let size = BucketSize {
+            bytes:    breakdown.total(),
+            count:    Some(current.count + noncurrent.count),
+            versions: Some(breakdown),
+            ..Default::default()
+        };
+
+        Ok(size)
+    }
+
     /// List parts of an in-progress multipart upload
     async fn size_parts(
         &self,
@@ -325,24 +1541,25 @@ impl Client {
         upload_id: &str,
     ) -> Result<u64> {
         let mut part_number_marker = None;
-        let mut size               = 0;
+        let mut size               = SizeAndCount::default();
 
-        loop {
-            let output = self.client.list_parts()
+        for page in 1.. {
+            let request = self.client.list_parts()
                 .bucket(bucket)
                 .key(key)
                 .set_part_number_marker(part_number_marker)
-                .upload_id(upload_id)
-                .send()
-                .await?;
+                .set_request_payer(self.request_payer.clone())
+                .upload_id(upload_id);
 
-            let part_sizes = output.parts()
-                .par_iter()
-                .filter_map(Part::size)
-                .sum::<i64>();
+            let output = self.retry.send(|| request.clone().send()).await?;
 
-            size += u64::try_from(part_sizes)
-                .context("part sizes")?;
+            size = self.add_page_sizes(
+                size,
+                output.parts(),
+                Part::size,
+                bucket,
+                page,
+            )?;
 
             if output.is_truncated() == Some(true) {
                 part_number_marker = output.next_part_number_marker()
@@ -353,16 +1570,22 @@ impl Client {
             }
         }
 
-        Ok(size)
+        Ok(size.bytes)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use aws_config::BehaviorVersion;
     use aws_credential_types::Credentials;
+    use aws_runtime::env_config::file::{
+        EnvConfigFileKind,
+        EnvConfigFiles,
+    };
     use aws_sdk_s3::config::Config as S3Config;
     use aws_smithy_runtime::client::http::test_util::{
+        capture_request,
         ReplayEvent,
         StaticReplayClient,
     };
@@ -370,6 +1593,10 @@ mod tests {
     use pretty_assertions::assert_eq;
     use std::fs;
     use std::path::Path;
+    use std::time::{
+        Duration,
+        UNIX_EPOCH,
+    };
 
     // Create a mock S3 client, returning the data from the specified
     // data_file.
@@ -377,53 +1604,47 @@ mod tests {
         data_file: Vec<&str>,
         versions:  ObjectVersions,
     ) -> Client {
-        // Get a vec of events based on the given data_files
-        let events = data_file
-            .iter()
-            .map(|d| {
+        let responses = data_file
+            .into_iter()
+            .map(|d| (200, d))
+            .collect();
+
+        mock_client_with_responses(responses, versions).await
+    }
+
+    // Create a mock S3 client, returning the status code and data from the
+    // specified data_file for each response.
+    async fn mock_client_with_responses(
+        responses: Vec<(u16, &str)>,
+        versions:  ObjectVersions,
+    ) -> Client {
+        let responses = responses
+            .into_iter()
+            .map(|(status, d)| {
                 let path = Path::new("test-data").join(d);
                 let data = fs::read_to_string(path).unwrap();
 
-                // Events
-                ReplayEvent::new(
-                    // Request
-                    http::Request::builder()
-                        .body(SdkBody::from("request body"))
-                        .unwrap(),
-
-                    // Response
-                    http::Response::builder()
-                        .status(200)
-                        .body(SdkBody::from(data))
-                        .unwrap(),
-                )
+                (status, data)
             })
             .collect();
 
-        let http_client = StaticReplayClient::new(events);
-
-        let creds = Credentials::for_tests_with_session_token();
-
-        let conf = S3Config::builder()
-            .behavior_version_latest()
-            .credentials_provider(creds)
-            .http_client(http_client)
-            .region(aws_sdk_s3::config::Region::new("eu-west-1"))
-            .build();
-
-        let client = S3Client::from_conf(conf);
-
-        Client {
-            client:          client,
-            bucket_name:     None,
-            object_versions: versions,
-            region:          Region::new().set_region("eu-west-1"),
-        }
+        Client::replay(responses, versions)
     }
 
     // Create a mock client that returns a specific status code and empty
-    // response body.
-    async fn mock_client_with_status(status: u16) -> Client {
+    // response body, with an optional x-amz-bucket-region header.
+    async fn mock_client_with_region_hint(
+        status:      u16,
+        region_hint: Option<&str>,
+    ) -> Client {
+        let response = http::Response::builder()
+            .status(status);
+
+        let response = match region_hint {
+            Some(region) => response.header("x-amz-bucket-region", region),
+            None         => response,
+        };
+
         let http_client = StaticReplayClient::new(vec![
             ReplayEvent::new(
                 // Request
@@ -432,8 +1653,7 @@ mod tests {
                     .unwrap(),
 
                 // Response
-                http::Response::builder()
-                    .status(status)
+                response
                     .body(SdkBody::from("response body"))
                     .unwrap(),
             ),
@@ -451,27 +1671,337 @@ mod tests {
         let client = S3Client::from_conf(conf);
 
         Client {
-            client:          client,
-            bucket_name:     None,
-            object_versions: ObjectVersions::Current,
-            region:          Region::new().set_region("eu-west-1"),
+            client,
+            all_regions:             false,
+            assume_region:           false,
+            breakdown_versions:      false,
+            bucket_names:            Vec::new(),
+            endpoint:                None,
+            exclude:                 Vec::new(),
+            fallback_on_unsupported: false,
+            missing_lifecycle:       false,
+            multipart_age:           false,
+            multipart_older_than:    None,
+            object_versions:         ObjectVersions::Current,
+            parallel_prefixes:       false,
+            parallel_sum:            true,
+            prefix:                  None,
+            prewarm:                 false,
+            regional_clients:        RegionalClients::default(),
+            request_payer:           None,
+            retry:                   Retry::default(),
+            stats:                   false,
+            storage_class_filter:    Vec::new(),
+            storage_classes:         false,
+            warn_versioned:          false,
+            region:                  Region::new().set_region("eu-west-1"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resolved_endpoint() {
+        let config_file = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("test-data")
+            .join("aws-config");
+
+        let tests = vec![
+            ("default",  None,                     None),
+            ("endpoint", None,                     Some("http://127.0.0.1:9000")),
+            ("services", None,                     Some("http://127.0.0.1:9001")),
+            ("endpoint", Some("http://cli:9002"),  Some("http://cli:9002")),
+            ("services", Some("http://cli:9002"),  Some("http://cli:9002")),
+        ];
+
+        for test in tests {
+            let profile  = test.0;
+            let endpoint = test.1;
+            let expected = test.2;
+
+            let profile_files = EnvConfigFiles::builder()
+                .with_file(EnvConfigFileKind::Config, &config_file)
+                .build();
+
+            let config = aws_config::defaults(BehaviorVersion::latest())
+                .profile_files(profile_files)
+                .profile_name(profile)
+                .region(Region::new().set_region("eu-west-1"));
+
+            let config = if let Some(endpoint) = endpoint {
+                config.endpoint_url(endpoint)
+            }
+            else {
+                config
+            };
+
+            let config = config.load().await;
+
+            let ret = resolved_endpoint(&config);
+
+            assert_eq!(ret.as_deref(), expected, "profile {profile}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_endpoint_request_uri() {
+        let tests = vec![
+            ("http://127.0.0.1:9000",      false, "http://127.0.0.1:9000/test-bucket/?list-type=2"),
+            ("http://[::1]:9000",          false, "http://[::1]:9000/test-bucket/?list-type=2"),
+            ("https://[2001:db8::1]:9443", false, "https://[2001:db8::1]:9443/test-bucket/?list-type=2"),
+            ("http://minio.local:9000",    false, "http://test-bucket.minio.local:9000/?list-type=2"),
+            ("http://minio.local:9000",    true,  "http://minio.local:9000/test-bucket/?list-type=2"),
+        ];
+
+        for test in tests {
+            let endpoint   = test.0;
+            let path_style = test.1;
+            let expected   = test.2;
+
+            let (http_client, request) = capture_request(None);
+
+            let config = aws_config::defaults(BehaviorVersion::latest())
+                .credentials_provider(Credentials::for_tests())
+                .endpoint_url(endpoint)
+                .http_client(http_client)
+                .region(Region::new().set_region("eu-west-1"))
+                .load()
+                .await;
+
+            let client = S3Client::from_conf(
+                aws_sdk_s3::config::Builder::from(&config)
+                    .force_path_style(path_style)
+                    .build()
+            );
+
+            // We only care about the request, not the response.
+            let _ = client.list_objects_v2()
+                .bucket("test-bucket")
+                .send()
+                .await;
+
+            let request = request.expect_request();
+
+            assert_eq!(request.uri(), expected, "endpoint {endpoint}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_prefix_request_uri() {
+        let tests = vec![
+            (ObjectVersions::Current,    false, None,           "list-type=2"),
+            (ObjectVersions::Current,    false, Some("team-a/"), "prefix=team-a%2F"),
+            (ObjectVersions::Current,    true,  Some("team-a/"), "prefix=team-a%2F"),
+            (ObjectVersions::NonCurrent, false, Some("team-a/"), "prefix=team-a%2F"),
+            (ObjectVersions::Multipart,  false, Some("team-a/"), "prefix=team-a%2F"),
+        ];
+
+        for test in tests {
+            let versions          = test.0;
+            let parallel_prefixes = test.1;
+            let prefix            = test.2;
+            let expected          = test.3;
+
+            let (http_client, request) = capture_request(None);
+
+            let conf = S3Config::builder()
+                .behavior_version_latest()
+                .credentials_provider(Credentials::for_tests())
+                .http_client(http_client)
+                .region(aws_sdk_s3::config::Region::new("eu-west-1"))
+                .build();
+
+            let mut client = Client::replay(Vec::new(), versions);
+
+            client.client            = S3Client::from_conf(conf);
+            client.parallel_prefixes = parallel_prefixes;
+            client.prefix            = prefix.map(ToOwned::to_owned);
+
+            // We only care about the first request, not the response.
+            let _ = client.size_objects("test-bucket").await;
+
+            let request = request.expect_request();
+            let uri     = request.uri();
+
+            assert!(uri.contains(expected), "{versions:?} {uri}");
+            assert_eq!(uri.contains("prefix="), prefix.is_some(), "{uri}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_request_payer_header() {
+        let tests = vec![
+            (ObjectVersions::Current,       false, None),
+            (ObjectVersions::Current,       true,  Some("requester")),
+            (ObjectVersions::DeleteMarkers, true,  Some("requester")),
+            (ObjectVersions::NonCurrent,    true,  Some("requester")),
+            (ObjectVersions::Multipart,     true,  Some("requester")),
+        ];
+
+        for test in tests {
+            let versions      = test.0;
+            let request_payer = test.1;
+            let expected      = test.2;
+
+            let (http_client, request) = capture_request(None);
+
+            let conf = S3Config::builder()
+                .behavior_version_latest()
+                .credentials_provider(Credentials::for_tests())
+                .http_client(http_client)
+                .region(aws_sdk_s3::config::Region::new("eu-west-1"))
+                .build();
+
+            let mut client = Client::replay(Vec::new(), versions);
+
+            client.client        = S3Client::from_conf(conf);
+            client.request_payer = request_payer
+                .then_some(RequestPayer::Requester);
+
+            // We only care about the first request, not the response.
+            let _ = client.size_objects("test-bucket").await;
+
+            let request = request.expect_request();
+            let header  = request.headers().get("x-amz-request-payer");
+
+            assert_eq!(header, expected, "{versions:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_has_noncurrent_expiration() {
+        let tests = vec![
+            (200, "s3-get-bucket-lifecycle-configuration.xml",          true),
+            (200, "s3-get-bucket-lifecycle-configuration-current.xml",  false),
+            (200, "s3-get-bucket-lifecycle-configuration-disabled.xml", false),
+            (404, "s3-no-such-lifecycle-configuration.xml",             false),
+        ];
+
+        for test in tests {
+            let status    = test.0;
+            let data_file = test.1;
+            let expected  = test.2;
+
+            let client = mock_client_with_responses(
+                vec![(status, data_file)],
+                ObjectVersions::NonCurrent,
+            ).await;
+
+            let ret = client.has_noncurrent_expiration("test-bucket")
+                .await
+                .unwrap();
+
+            assert_eq!(ret, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_has_noncurrent_expiration_err() {
+        let client = mock_client_with_responses(
+            vec![(403, "s3-not-implemented.xml")],
+            ObjectVersions::NonCurrent,
+        ).await;
+
+        let ret = client.has_noncurrent_expiration("test-bucket").await;
+
+        assert!(ret.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_is_versioned() {
+        let tests = vec![
+            (200, "s3-get-bucket-versioning-enabled.xml",   Some(true)),
+            (200, "s3-get-bucket-versioning-suspended.xml", Some(true)),
+            (200, "s3-get-bucket-versioning-never.xml",     Some(false)),
+            (501, "s3-not-implemented.xml",                 None),
+        ];
+
+        for test in tests {
+            let status    = test.0;
+            let data_file = test.1;
+            let expected  = test.2;
+
+            let client = mock_client_with_responses(
+                vec![(status, data_file)],
+                ObjectVersions::NonCurrent,
+            ).await;
+
+            let ret = client.is_versioned("test-bucket").await;
+
+            assert_eq!(ret, expected, "{data_file}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_for_region() {
+        let mut client = mock_client(vec![], ObjectVersions::Current).await;
+
+        let other = Region::new().set_region("us-west-2");
+
+        // Without all_regions, we're always used as we are.
+        let ret = client.for_region(&other);
+        assert!(matches!(ret, Cow::Borrowed(_)));
+
+        client.all_regions = true;
+
+        // Our own region needs no other client.
+        let ret = client.for_region(&client.region);
+        assert!(matches!(ret, Cow::Borrowed(_)));
+
+        // Other regions get their own client, created only once.
+        for _ in 0..2 {
+            let ret = client.for_region(&other);
+
+            assert!(matches!(ret, Cow::Owned(_)));
+            assert_eq!(ret.region, other);
+            assert_eq!(
+                ret.client.config().region().map(ToString::to_string),
+                Some("us-west-2".into()),
+            );
         }
+
+        assert_eq!(client.regional_clients.lock().unwrap().len(), 1);
+
+        // Custom endpoints have no other regions.
+        client.endpoint = Some("http://localhost:9000".into());
+
+        let ret = client.for_region(&Region::new().set_region("ap-south-1"));
+        assert!(matches!(ret, Cow::Borrowed(_)));
     }
 
     #[tokio::test]
     async fn test_head_bucket() {
         let tests = vec![
-            (200, true),
-            (403, false),
-            (404, false),
+            (200, None,              BucketAccess::Allowed),
+            (403, None,              BucketAccess::Denied),
+            (403, Some("eu-west-1"), BucketAccess::Denied),
+            (404, None,              BucketAccess::Denied),
+            (301, None,              BucketAccess::Denied),
+            (
+                301,
+                Some("us-west-2"),
+                BucketAccess::WrongRegion(
+                    Region::new().set_region("us-west-2"),
+                ),
+            ),
+            (
+                400,
+                Some("ap-east-1"),
+                BucketAccess::WrongRegion(
+                    Region::new().set_region("ap-east-1"),
+                ),
+            ),
         ];
 
         for test in tests {
             let status_code: u16 = test.0;
-            let expected         = test.1;
+            let region_hint      = test.1;
+            let expected         = test.2;
 
-            let client = mock_client_with_status(status_code).await;
-            let ret    = client.head_bucket("test-bucket").await;
+            let client = mock_client_with_region_hint(
+                status_code,
+                region_hint,
+            ).await;
+
+            let ret = client.head_bucket("test-bucket").await;
 
             assert_eq!(ret, expected);
         }
@@ -490,6 +2020,54 @@ mod tests {
     //    assert!(ret.is_err());
     //}
 
+    #[test]
+    fn test_constraint_region() {
+        let tests = vec![
+            ("",              None),
+            ("EU",            Some("eu-west-1")),
+            ("eu-west-2",     Some("eu-west-2")),
+            ("us-gov-west-1", Some("us-gov-west-1")),
+            ("xx-example-1",  Some("xx-example-1")),
+        ];
+
+        for test in tests {
+            let constraint = BucketLocationConstraint::from(test.0);
+            let expected   = test.1;
+
+            assert_eq!(constraint_region(&constraint), expected);
+        }
+    }
+
+    #[test]
+    fn test_constraint_region_known_constraints() {
+        // Every constraint that the SDK knows about must give a region that
+        // a client can be created in, which is never an alias such as `EU`.
+        for value in BucketLocationConstraint::values() {
+            let constraint = BucketLocationConstraint::from(*value);
+
+            let region = constraint_region(&constraint)
+                .unwrap_or_else(|| panic!("no region for '{}'", value));
+
+            let parts: Vec<&str> = region.split('-').collect();
+
+            assert!(parts.len() >= 3, "'{}' -> '{}'", value, region);
+            assert!(
+                region.chars().all(|c| {
+                    c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-'
+                }),
+                "'{}' -> '{}'",
+                value,
+                region,
+            );
+            assert!(
+                parts[parts.len() - 1].parse::<u8>().is_ok(),
+                "'{}' -> '{}'",
+                value,
+                region,
+            );
+        }
+    }
+
     #[tokio::test]
     async fn test_get_bucket_location_ok() {
         let client = mock_client(
@@ -533,29 +2111,114 @@ mod tests {
             .await
             .unwrap();
 
-        let expected = Region::new().set_region("");
+        let expected = Region::new().set_region("us-east-1");
 
         assert_eq!(ret, expected);
     }
 
     #[tokio::test]
-    async fn test_list_buckets() {
-        let client = mock_client(
-            vec!["s3-list-buckets.xml"],
+    async fn test_get_bucket_location_ok_null_custom_endpoint() {
+        let mut client = mock_client(
+            vec!["s3-get-bucket-location-null.xml"],
             ObjectVersions::Current,
         ).await;
 
-        let mut ret = client.list_buckets().await.unwrap();
+        client.endpoint = Some("http://127.0.0.1:9000".into());
+
+        let ret = client.get_bucket_location("test-bucket")
+            .await
+            .unwrap();
+
+        let expected = Region::new().set_region("eu-west-1");
+
+        assert_eq!(ret, expected);
+    }
+
+    #[tokio::test]
+    async fn test_list_buckets() {
+        let client = mock_client(
+            vec!["s3-list-buckets.xml"],
+            ObjectVersions::Current,
+        ).await;
+
+        let pages: Vec<ListedBuckets> = client.list_buckets()
+            .try_collect()
+            .await
+            .unwrap();
+
+        let mut ret = pages.concat();
         ret.sort();
 
-        let expected: Vec<String> = vec![
-            "a-bucket-name".into(),
-            "another-bucket-name".into(),
+        let created = |secs| Some(UNIX_EPOCH + Duration::from_secs(secs));
+
+        // 2020-03-12T10:57:32Z and 2020-03-10T10:58:12Z.
+        let expected: ListedBuckets = vec![
+            ("a-bucket-name".into(),       created(1_584_010_652)),
+            ("another-bucket-name".into(), created(1_583_837_892)),
         ];
 
         assert_eq!(ret, expected);
     }
 
+    #[tokio::test]
+    async fn test_list_buckets_pages() {
+        let tests = vec![
+            (
+                vec![
+                    vec!["first-page-bucket".to_string()],
+                    vec!["a-bucket-name".into(), "another-bucket-name".into()],
+                ],
+                vec![
+                    "s3-list-buckets-page.xml",
+                    "s3-list-buckets.xml",
+                ],
+            ),
+            // An empty continuation token is the same as no token.
+            (
+                vec![
+                    vec!["first-page-bucket".to_string()],
+                ],
+                vec![
+                    "s3-list-buckets-empty-token.xml",
+                    "s3-list-buckets.xml",
+                ],
+            ),
+            // A continuation token that comes back again stops the listing,
+            // rather than listing the same page forever.
+            (
+                vec![
+                    vec!["first-page-bucket".to_string()],
+                    vec!["repeated-page-bucket".into()],
+                ],
+                vec![
+                    "s3-list-buckets-page.xml",
+                    "s3-list-buckets-repeated-token.xml",
+                    "s3-list-buckets.xml",
+                ],
+            ),
+        ];
+
+        for test in tests {
+            let expected   = test.0;
+            let data_files = test.1;
+
+            let client = mock_client(
+                data_files,
+                ObjectVersions::Current,
+            ).await;
+
+            let pages: Vec<Vec<String>> = client.list_buckets()
+                .map_ok(|page| {
+                    page.into_iter().map(|(name, _)| name).collect()
+                })
+                .try_collect()
+                .await
+                .unwrap();
+
+            assert_eq!(pages, expected);
+        }
+    }
+
     #[tokio::test]
     async fn test_size_multipart_uploads() {
         let expected = 204_800;
@@ -575,12 +2238,372 @@ mod tests {
         assert_eq!(size, expected);
     }
 
+    #[tokio::test]
+    async fn test_size_multipart_uploads_older_than() {
+        // The upload in the test data was initiated in 2015.
+        let tests = vec![
+            (
+                None,
+                204_800,
+                vec!["s3-list-multipart-uploads.xml", "s3-list-parts.xml"],
+            ),
+            (
+                Some(1),
+                204_800,
+                vec!["s3-list-multipart-uploads.xml", "s3-list-parts.xml"],
+            ),
+            (
+                Some(100 * 365),
+                0,
+                vec!["s3-list-multipart-uploads.xml"],
+            ),
+        ];
+
+        for test in tests {
+            let days       = test.0;
+            let expected   = test.1;
+            let data_files = test.2;
+
+            let mut client = mock_client(
+                data_files,
+                ObjectVersions::Multipart,
+            ).await;
+
+            client.multipart_older_than = days
+                .map(|days| Duration::from_secs(days * 86_400));
+
+            let size = client.size_multipart_uploads("test-bucket")
+                .await
+                .unwrap();
+
+            assert_eq!(size, expected, "{days:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_size_multipart_uploads_empty_parts() {
+        let data_files = vec![
+            "s3-list-multipart-uploads.xml",
+            "s3-list-parts-empty.xml",
+        ];
+
+        let mut client = mock_client(
+            data_files,
+            ObjectVersions::Current,
+        ).await;
+
+        client.stats = true;
+
+        let size = client.size_multipart_uploads("test-bucket").await.unwrap();
+
+        assert_eq!(size, 0);
+    }
+
+    #[tokio::test]
+    async fn test_oldest_multipart_upload() {
+        let tests = vec![
+            ("s3-list-multipart-uploads.xml",          1_441_746_120),
+            ("s3-list-multipart-uploads-multiple.xml", 1_438_421_400),
+        ];
+
+        for test in tests {
+            let data_file = test.0;
+            let expected  = UNIX_EPOCH + Duration::from_secs(test.1);
+
+            let client = mock_client(
+                vec![data_file],
+                ObjectVersions::Multipart,
+            ).await;
+
+            let ret = client.oldest_multipart_upload("test-bucket")
+                .await
+                .unwrap();
+
+            assert_eq!(ret, Some(expected));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_size_current_objects() {
+        // 2020-03-12T11:04:09Z, the newest object in s3-list-objects.xml.
+        let newest = UNIX_EPOCH + Duration::from_secs(1_584_011_049);
+
+        let tests = vec![
+            (
+                0,
+                0,
+                None,
+                vec![
+                    "s3-list-objects-empty.xml",
+                ],
+            ),
+            (
+                33_792,
+                2,
+                Some(newest),
+                vec![
+                    "s3-list-objects.xml",
+                ],
+            ),
+            // A truncated page containing no objects must still be followed
+            // to the next page.
+            (
+                33_792,
+                2,
+                Some(newest),
+                vec![
+                    "s3-list-objects-empty-truncated.xml",
+                    "s3-list-objects.xml",
+                ],
+            ),
+            // Some S3 compatible storage omits IsTruncated, or sets it to
+            // false, while still returning a continuation token.
+            (
+                35_840,
+                3,
+                Some(newest),
+                vec![
+                    "s3-list-objects-no-is-truncated.xml",
+                    "s3-list-objects.xml",
+                ],
+            ),
+            (
+                33_792,
+                2,
+                Some(newest),
+                vec![
+                    "s3-list-objects-false-is-truncated.xml",
+                    "s3-list-objects.xml",
+                ],
+            ),
+            // An empty continuation token is the same as no token.
+            (
+                2_048,
+                1,
+                Some(UNIX_EPOCH + Duration::from_secs(1_583_060_649)),
+                vec![
+                    "s3-list-objects-empty-token.xml",
+                ],
+            ),
+            // A continuation token that comes back again stops the listing,
+            // rather than listing the same page forever.
+            (
+                6_144,
+                2,
+                Some(UNIX_EPOCH + Duration::from_secs(1_583_060_649)),
+                vec![
+                    "s3-list-objects-no-is-truncated.xml",
+                    "s3-list-objects-repeated-token.xml",
+                ],
+            ),
+        ];
+
+        for test in tests {
+            let expected_size   = test.0;
+            let expected_count  = test.1;
+            let expected_newest = test.2;
+            let data_files      = test.3;
+
+            let client = mock_client(
+                data_files,
+                ObjectVersions::Current,
+            ).await;
+
+            let ret = client.size_current_objects("test-bucket")
+                .await
+                .unwrap();
+
+            assert_eq!(ret.bytes, expected_size);
+            assert_eq!(ret.count, Some(expected_count));
+            assert_eq!(ret.newest_object, expected_newest);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_size_current_objects_storage_classes() {
+        // Objects without a storage class are counted as STANDARD.
+        let tests = vec![
+            (false, None),
+            (
+                true,
+                Some(BTreeMap::from([
+                    ("GLACIER".into(),     36_864),
+                    ("STANDARD".into(),    1_536),
+                    ("STANDARD_IA".into(), 2_048),
+                ])),
+            ),
+        ];
+
+        for test in tests {
+            let storage_classes = test.0;
+            let expected        = test.1;
+
+            let mut client = mock_client(
+                vec!["s3-list-objects-storage-classes.xml"],
+                ObjectVersions::Current,
+            ).await;
+
+            client.storage_classes = storage_classes;
+
+            let ret = client.size_current_objects("test-bucket")
+                .await
+                .unwrap();
+
+            assert_eq!(ret.bytes, 40_448);
+            assert_eq!(ret.storage_classes, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_size_current_objects_storage_class_filter() {
+        // Objects without a storage class are counted as STANDARD.
+        let tests = vec![
+            (vec![],                        40_448, 5),
+            (vec!["GLACIER"],               36_864, 2),
+            (vec!["STANDARD"],              1_536,  2),
+            (vec!["GLACIER", "STANDARD_IA"], 38_912, 3),
+            (vec!["DEEP_ARCHIVE"],          0,      0),
+        ];
+
+        for test in tests {
+            let filter         = test.0;
+            let expected_size  = test.1;
+            let expected_count = test.2;
+
+            let mut client = mock_client(
+                vec!["s3-list-objects-storage-classes.xml"],
+                ObjectVersions::Current,
+            ).await;
+
+            client.storage_classes      = true;
+            client.storage_class_filter = filter.iter()
+                .map(ToString::to_string)
+                .collect();
+
+            let ret = client.size_current_objects("test-bucket")
+                .await
+                .unwrap();
+
+            assert_eq!(ret.bytes, expected_size, "{filter:?}");
+            assert_eq!(ret.count, Some(expected_count), "{filter:?}");
+
+            // Only the wanted storage classes appear in the breakdown.
+            let classes = ret.storage_classes.unwrap();
+
+            assert!(
+                classes.keys().all(|class| {
+                    is_wanted_storage_class(&client.storage_class_filter, class)
+                }),
+                "{filter:?}",
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_size_current_objects_invalid_size() {
+        let tests = vec![
+            (
+                "Invalid object size in page 1 of the listing for 'test-bucket'",
+                vec![
+                    "s3-list-objects-negative-size.xml",
+                ],
+            ),
+            (
+                "Invalid object size in page 1 of the listing for 'test-bucket'",
+                vec![
+                    "s3-list-objects-huge-size.xml",
+                ],
+            ),
+            (
+                "Invalid object size in page 2 of the listing for 'test-bucket'",
+                vec![
+                    "s3-list-objects-empty-truncated.xml",
+                    "s3-list-objects-negative-size.xml",
+                ],
+            ),
+        ];
+
+        for test in tests {
+            let expected   = test.0;
+            let data_files = test.1;
+
+            for parallel_sum in [true, false] {
+                let mut client = mock_client(
+                    data_files.clone(),
+                    ObjectVersions::Current,
+                ).await;
+
+                client.parallel_sum = parallel_sum;
+
+                let ret = client.size_current_objects("test-bucket").await;
+
+                assert_eq!(ret.unwrap_err().to_string(), expected);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_size_current_objects_parallel() {
+        let tests = vec![
+            // Only top level objects, no prefixes to list.
+            (
+                0,
+                0,
+                vec![
+                    "s3-list-objects-empty.xml",
+                ],
+            ),
+            // A top level object and two prefixes.
+            (
+                68_096,
+                5,
+                vec![
+                    "s3-list-objects-delimiter.xml",
+                    "s3-list-objects.xml",
+                    "s3-list-objects.xml",
+                ],
+            ),
+            // A truncated discovery page must still be followed.
+            (
+                68_096,
+                5,
+                vec![
+                    "s3-list-objects-empty-truncated.xml",
+                    "s3-list-objects-delimiter.xml",
+                    "s3-list-objects.xml",
+                    "s3-list-objects.xml",
+                ],
+            ),
+        ];
+
+        for test in tests {
+            let expected_size  = test.0;
+            let expected_count = test.1;
+            let data_files     = test.2;
+
+            let mut client = mock_client(
+                data_files,
+                ObjectVersions::Current,
+            ).await;
+
+            client.parallel_prefixes = true;
+
+            let ret = client.size_current_objects("test-bucket")
+                .await
+                .unwrap();
+
+            assert_eq!(ret.bytes, expected_size);
+            assert_eq!(ret.count, Some(expected_count));
+        }
+    }
+
     #[tokio::test]
     async fn test_size_objects() {
         let tests = vec![
             (
                 ObjectVersions::All,
                 805_532,
+                Some(3),
                 vec![
                     "s3-list-multipart-uploads.xml",
                     "s3-list-parts.xml",
@@ -590,13 +2613,23 @@ mod tests {
             (
                 ObjectVersions::Current,
                 33_792,
+                Some(2),
                 vec![
                     "s3-list-objects.xml",
                 ],
             ),
+            (
+                ObjectVersions::DeleteMarkers,
+                0,
+                Some(2),
+                vec![
+                    "s3-list-object-versions.xml",
+                ],
+            ),
             (
                 ObjectVersions::Multipart,
                 204_800,
+                None,
                 vec![
                     "s3-list-multipart-uploads.xml",
                     "s3-list-parts.xml",
@@ -605,6 +2638,7 @@ mod tests {
             (
                 ObjectVersions::NonCurrent,
                 166_498,
+                Some(2),
                 vec![
                     "s3-list-object-versions.xml",
                 ],
@@ -612,20 +2646,167 @@ mod tests {
         ];
 
         for test in tests {
-            let versions      = test.0;
-            let expected_size = test.1;
-            let data_files    = test.2;
+            let versions       = test.0;
+            let expected_size  = test.1;
+            let expected_count = test.2;
+            let data_files     = test.3;
+
+            // Sequential summing must give the same result as parallel.
+            for parallel_sum in [true, false] {
+                let mut client = mock_client(
+                    data_files.clone(),
+                    versions,
+                ).await;
+
+                client.parallel_sum = parallel_sum;
+
+                let ret = client.size_objects("test-bucket")
+                    .await
+                    .unwrap();
+
+                assert_eq!(
+                    ret.bytes,
+                    expected_size,
+                    "parallel_sum {parallel_sum}",
+                );
+
+                assert_eq!(ret.count, expected_count, "{versions:?}");
+            }
+        }
+    }
 
-            let client = mock_client(
-                data_files,
-                versions,
+    #[tokio::test]
+    async fn test_size_objects_multipart_split() {
+        let client = mock_client(
+            vec![
+                "s3-list-multipart-uploads.xml",
+                "s3-list-parts.xml",
+                "s3-list-object-versions.xml",
+            ],
+            ObjectVersions::All,
+        ).await;
+
+        let ret = client.size_objects("test-bucket")
+            .await
+            .unwrap();
+
+        let expected = MultipartSplit {
+            multipart: 204_800,
+            versions:  600_732,
+        };
+
+        let split = ret.multipart_split.unwrap();
+
+        assert_eq!(split, expected);
+        assert_eq!(split.multipart + split.versions, ret.bytes);
+    }
+
+    #[tokio::test]
+    async fn test_size_versions_breakdown() {
+        let data_files = vec![
+            "s3-list-object-versions.xml",
+            "s3-list-multipart-uploads.xml",
+            "s3-list-parts.xml",
+        ];
+
+        let client = mock_client(
+            data_files,
+            ObjectVersions::All,
+        ).await;
+
+        let ret = client.size_versions_breakdown("test-bucket")
+            .await
+            .unwrap();
+
+        let expected = VersionsBreakdown {
+            current:    434_234,
+            multipart:  204_800,
+            noncurrent: 166_498,
+        };
+
+        assert_eq!(ret.versions, Some(expected));
+        assert_eq!(ret.bytes, 805_532);
+        assert_eq!(ret.count, Some(3));
+    }
+
+    #[tokio::test]
+    async fn test_size_object_versions_unsupported() {
+        let tests = vec![
+            (ObjectVersions::All,        SizeAndCount { bytes: 33_792, count: 2 }),
+            (ObjectVersions::NonCurrent, SizeAndCount::default()),
+        ];
+
+        for test in tests {
+            let versions = test.0;
+            let expected = test.1;
+
+            let responses = vec![
+                (501, "s3-not-implemented.xml"),
+                (200, "s3-list-objects.xml"),
+            ];
+
+            let mut client = mock_client_with_responses(
+                responses,
+                ObjectVersions::Current,
             ).await;
 
-            let ret = client.size_objects("test-bucket")
+            client.endpoint = Some("http://127.0.0.1:9000".into());
+
+            let ret = client.size_object_versions("test-bucket", &versions)
                 .await
                 .unwrap();
 
-            assert_eq!(ret, expected_size);
+            assert_eq!(ret, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_size_object_versions_unsupported_aws() {
+        // Against AWS, an unsupported operation is still an error.
+        let responses = vec![
+            (501, "s3-not-implemented.xml"),
+        ];
+
+        let client = mock_client_with_responses(
+            responses,
+            ObjectVersions::Current,
+        ).await;
+
+        let ret = client.size_object_versions(
+            "test-bucket",
+            &ObjectVersions::All,
+        ).await;
+
+        assert!(ret.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_size_object_versions_unsupported_fallback() {
+        // Unless falling back was requested.
+        let tests = vec![
+            (405, "s3-not-implemented.xml"),
+            (501, "s3-not-implemented.xml"),
+        ];
+
+        for test in tests {
+            let responses = vec![
+                test,
+                (200, "s3-list-objects.xml"),
+            ];
+
+            let mut client = mock_client_with_responses(
+                responses,
+                ObjectVersions::Current,
+            ).await;
+
+            client.fallback_on_unsupported = true;
+
+            let ret = client.size_object_versions(
+                "test-bucket",
+                &ObjectVersions::All,
+            ).await.unwrap();
+
+            assert_eq!(ret.bytes, 33_792);
         }
     }
 
@@ -0,0 +1,99 @@
+// Implements S3 Clients replaying canned responses
+#![forbid(unsafe_code)]
+#![deny(missing_docs)]
+use aws_sdk_s3::client::Client as S3Client;
+use aws_sdk_s3::config::{
+    Config as S3Config,
+    Credentials,
+};
+use aws_smithy_runtime::client::http::test_util::{
+    ReplayEvent,
+    StaticReplayClient,
+};
+use aws_smithy_types::body::SdkBody;
+use crate::common::{
+    ObjectVersions,
+    Region,
+};
+use super::client::{
+    Client,
+    RegionalClients,
+};
+use super::retry::Retry;
+
+/// Region that replaying clients are created in.
+const REPLAY_REGION: &str = "eu-west-1";
+
+/// Return an `S3Client` that answers each request with the next of the given
+/// `responses`, as a status code and body, without making any network
+/// requests.
+pub fn s3_client(responses: Vec<(u16, String)>) -> S3Client {
+    let events = responses
+        .into_iter()
+        .map(|(status, body)| {
+            ReplayEvent::new(
+                // Request
+                http::Request::builder()
+                    .body(SdkBody::from("request body"))
+                    .unwrap(),
+
+                // Response
+                http::Response::builder()
+                    .status(status)
+                    .body(SdkBody::from(body))
+                    .unwrap(),
+            )
+        })
+        .collect();
+
+    let http_client = StaticReplayClient::new(events);
+
+    let creds = Credentials::new("replay", "replay", None, None, "replay");
+
+    let conf = S3Config::builder()
+        .behavior_version_latest()
+        .credentials_provider(creds)
+        .http_client(http_client)
+        .region(aws_sdk_s3::config::Region::new(REPLAY_REGION))
+        .build();
+
+    S3Client::from_conf(conf)
+}
+
+impl Client {
+    /// Return an S3 `Client` sizing the given object `versions`, which
+    /// answers each request with the next of the given `responses`.
+    ///
+    /// Every other setting is left at its default.
+    pub fn replay(
+        responses: Vec<(u16, String)>,
+        versions:  ObjectVersions,
+    ) -> Self {
+        Self {
+            client:                  s3_client(responses),
+            all_regions:             false,
+            assume_region:           false,
+            breakdown_versions:      false,
+            bucket_names:            Vec::new(),
+            endpoint:                None,
+            exclude:                 Vec::new(),
+            fallback_on_unsupported: false,
+            missing_lifecycle:       false,
+            multipart_age:           false,
+            multipart_older_than:    None,
+            object_versions:         versions,
+            parallel_prefixes:       false,
+            parallel_sum:            true,
+            prefix:                  None,
+            prewarm:                 false,
+            regional_clients:        RegionalClients::default(),
+            request_payer:           None,
+            retry:                   Retry::default(),
+            stats:                   false,
+            storage_class_filter:    Vec::new(),
+            storage_classes:         false,
+            warn_versioned:          false,
+            region:                  Region::new().set_region(REPLAY_REGION),
+        }
+    }
+}
@@ -0,0 +1,226 @@
+// retry: Retries S3 requests that were throttled or failed on the server
+#![forbid(unsafe_code)]
+#![deny(missing_docs)]
+use aws_sdk_s3::config::http::HttpResponse;
+use aws_sdk_s3::error::SdkError;
+use std::future::Future;
+use std::time::Duration;
+use tracing::debug;
+
+/// Longest delay before retrying a request, however many times it's been
+/// retried.
+const MAX_DELAY: Duration = Duration::from_secs(20);
+
+/// HTTP status used by some services for throttling.
+const TOO_MANY_REQUESTS: u16 = 429;
+
+/// HTTP statuses of server errors that are worth retrying.
+///
+/// `501 Not Implemented` is missing, as S3 compatible storage uses it for
+/// operations that it doesn't support, which will never succeed.
+const RETRYABLE_SERVER_ERRORS: &[u16] = &[
+    500,
+    502,
+    503,
+    504,
+];
+
+/// Returns a `bool` indicating if the request that failed with `error` is
+/// worth retrying, which is only the case if it was throttled or failed with
+/// a server error.
+///
+/// Errors without a response, and client errors such as `403 Forbidden` or
+/// `404 Not Found`, are never retried.
+fn is_retryable<E>(error: &SdkError<E, HttpResponse>) -> bool {
+    let Some(response) = error.raw_response() else {
+        return false
+    };
+
+    let status = response.status().as_u16();
+
+    status == TOO_MANY_REQUESTS || RETRYABLE_SERVER_ERRORS.contains(&status)
+}
+
+/// Retries S3 requests with exponential backoff and jitter.
+///
+/// This is on top of the retries that the AWS SDK makes itself, for requests
+/// that are still throttled once the SDK has given up. The default doesn't
+/// retry at all.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Retry {
+    /// Maximum number of times that a single request is retried.
+    pub max_retries: u32,
+
+    /// Delay before the first retry, which doubles for each further retry.
+    pub base_delay: Duration,
+}
+
+impl Retry {
+    /// Return a new `Retry`, retrying each request up to `max_retries` times,
+    /// with a `base_delay` before the first retry.
+    pub fn new(max_retries: u32, base_delay: Duration) -> Self {
+        Self {
+            max_retries,
+            base_delay,
+        }
+    }
+
+    /// Returns the delay before the given retry `attempt`, counting from 0.
+    ///
+    /// This is a random delay of up to `base_delay` doubled for each
+    /// previous attempt, so that concurrent requests don't retry in step,
+    /// but never more than `MAX_DELAY`.
+    fn delay(&self, attempt: u32) -> Duration {
+        let max = self.base_delay
+            .saturating_mul(2_u32.saturating_pow(attempt))
+            .min(MAX_DELAY);
+
+        max.mul_f64(fastrand::f64())
+    }
+
+    /// Send a request with `send`, calling it again after a delay each time
+    /// that the request fails with a retryable error, up to `max_retries`
+    /// times.
+    ///
+    /// The final error is returned if every attempt fails.
+    pub async fn send<T, E, F, Fut>(
+        &self,
+        send: F,
+    ) -> Result<T, SdkError<E, HttpResponse>>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T, SdkError<E, HttpResponse>>>,
+    {
+        for attempt in 0.. {
+            match send().await {
+                Err(e) if attempt < self.max_retries && is_retryable(&e) => {
+                    let delay = self.delay(attempt);
+
+                    debug!(
+                        "send: Retry {} of {} in {:?}",
+                        attempt + 1,
+                        self.max_retries,
+                        delay,
+                    );
+
+                    tokio::time::sleep(delay).await;
+                },
+                ret => return ret,
+            }
+        }
+
+        unreachable!("retries are bounded by max_retries")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_sdk_s3::client::Client as S3Client;
+    use aws_sdk_s3::config::retry::RetryConfig;
+    use aws_sdk_s3::config::{
+        Config as S3Config,
+        Credentials,
+        Region,
+    };
+    use aws_smithy_runtime::client::http::test_util::{
+        ReplayEvent,
+        StaticReplayClient,
+    };
+    use aws_smithy_types::body::SdkBody;
+    use pretty_assertions::assert_eq;
+    use std::fs;
+    use std::path::Path;
+
+    // Return an S3Client that doesn't retry by itself, answering each request
+    // with the next of the given statuses. Successful responses list objects.
+    fn mock_client(statuses: &[u16]) -> (S3Client, StaticReplayClient) {
+        let path    = Path::new("test-data").join("s3-list-objects.xml");
+        let objects = fs::read_to_string(path).unwrap();
+
+        let events = statuses.iter()
+            .map(|&status| {
+                let body = if status == 200 {
+                    objects.clone()
+                }
+                else {
+                    "<Error><Code>SlowDown</Code></Error>".into()
+                };
+
+                ReplayEvent::new(
+                    http::Request::builder()
+                        .body(SdkBody::from("request body"))
+                        .unwrap(),
+
+                    http::Response::builder()
+                        .status(status)
+                        .body(SdkBody::from(body))
+                        .unwrap(),
+                )
+            })
+            .collect();
+
+        let http_client = StaticReplayClient::new(events);
+
+        let conf = S3Config::builder()
+            .behavior_version_latest()
+            .credentials_provider(Credentials::for_tests())
+            .http_client(http_client.clone())
+            .region(Region::new("eu-west-1"))
+            .retry_config(RetryConfig::disabled())
+            .build();
+
+        (S3Client::from_conf(conf), http_client)
+    }
+
+    #[test]
+    fn test_delay() {
+        let retry = Retry::new(3, Duration::from_millis(100));
+
+        for attempt in 0..4 {
+            let max = Duration::from_millis(100 * 2_u64.pow(attempt));
+
+            assert!(retry.delay(attempt) <= max, "attempt {attempt}");
+        }
+
+        // Huge numbers of attempts are capped rather than overflowing.
+        assert!(retry.delay(u32::MAX) <= MAX_DELAY);
+    }
+
+    #[tokio::test]
+    async fn test_send() {
+        let tests = vec![
+            (0, vec![200],           true,  1),
+            (2, vec![503, 200],      true,  2),
+            (2, vec![429, 500, 200], true,  3),
+            (1, vec![503, 503],      false, 2),
+            (0, vec![503],           false, 1),
+            (2, vec![403],           false, 1),
+            (2, vec![404],           false, 1),
+            (2, vec![501],           false, 1),
+        ];
+
+        for test in tests {
+            let max_retries = test.0;
+            let statuses    = test.1;
+            let expected    = test.2;
+            let requests    = test.3;
+
+            let (client, http_client) = mock_client(&statuses);
+
+            let retry = Retry::new(max_retries, Duration::from_millis(1));
+
+            let request = client.list_objects_v2()
+                .bucket("test-bucket");
+
+            let ret = retry.send(|| request.clone().send()).await;
+
+            assert_eq!(ret.is_ok(), expected, "{statuses:?}");
+            assert_eq!(
+                http_client.actual_requests().count(),
+                requests,
+                "{statuses:?}",
+            );
+        }
+    }
+}
@@ -0,0 +1,14 @@
+// Imports all of the components needed for storage_lens::client
+#![forbid(unsafe_code)]
+#![deny(missing_docs)]
+
+/// Implementation of the `BucketSizer` trait for our Storage Lens `Client`.
+mod bucket_sizer;
+
+/// Storage Lens `Client`.
+mod client;
+
+/// Parsing of Storage Lens metrics exports.
+mod report;
+
+pub use client::*;
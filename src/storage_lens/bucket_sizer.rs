@@ -0,0 +1,239 @@
+// Implements the BucketSizer trait for the Storage Lens Client
+#![forbid(unsafe_code)]
+#![deny(missing_docs)]
+use anyhow::{
+    anyhow,
+    Result,
+};
+use async_trait::async_trait;
+use crate::common::{
+    is_selected,
+    Bucket,
+    Buckets,
+    BucketSize,
+    BucketSizer,
+    Region,
+};
+use super::client::Client;
+use tracing::debug;
+
+#[async_trait]
+impl BucketSizer for Client {
+    /// Return the list of buckets found in the Storage Lens export.
+    ///
+    /// Storage Lens exports cover every region of the account, so buckets
+    /// aren't filtered by our `Region`, each bucket is given the region that
+    /// the export reports for it.
+    async fn buckets(&self) -> Result<Buckets> {
+        debug!("buckets: Listing...");
+
+        let report = self.report().await?;
+
+        let buckets = report.0.iter()
+            .filter(|(name, _)| {
+                is_selected(&self.bucket_names, name)
+            })
+            .map(|(name, bucket)| {
+                Bucket {
+                    name:          name.clone(),
+                    region:        Some(Region::new().set_region(&bucket.region)),
+                    storage_types: None,
+                    is_versioned:  None,
+                    created:       None,
+                }
+            })
+            .collect();
+
+        Ok(buckets)
+    }
+
+    /// Return the size of `bucket` from the Storage Lens export.
+    ///
+    /// Storage Lens metrics are collected daily, so are always an estimate.
+    async fn bucket_size(&self, bucket: &Bucket) -> Result<BucketSize> {
+        debug!("bucket_size: Calculating size for '{}'", bucket.name);
+
+        let report = self.report().await?;
+
+        let metrics = report.0.get(&bucket.name)
+            .ok_or_else(|| {
+                anyhow!("'{}' isn't in the Storage Lens export", bucket.name)
+            })?;
+
+        let count = if self.count {
+            metrics.count
+        }
+        else {
+            None
+        };
+
+        let size = BucketSize {
+            count,
+            bytes:           metrics.bytes,
+            estimated:       true,
+            storage_classes: Some(metrics.storage_classes.clone()),
+            ..Default::default()
+        };
+
+        debug!("bucket_size: size for '{}' is '{:?}'", bucket.name, size);
+
+        Ok(size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_credential_types::Credentials;
+    use aws_sdk_s3::client::Client as S3Client;
+    use aws_sdk_s3::config::Config as S3Config;
+    use aws_smithy_runtime::client::http::test_util::{
+        ReplayEvent,
+        StaticReplayClient,
+    };
+    use aws_smithy_types::body::SdkBody;
+    use once_cell::sync::OnceCell;
+    use pretty_assertions::assert_eq;
+    use std::collections::BTreeMap;
+    use std::fs;
+    use std::path::Path;
+
+    // Create a mock Storage Lens client reading the export at `export_key`,
+    // returning the data from each of the specified data_files.
+    fn mock_client(
+        data_files:   Vec<&str>,
+        export_key:   &str,
+        bucket_names: Vec<&str>,
+    ) -> Client {
+        let events = data_files
+            .iter()
+            .map(|file| {
+                let path = Path::new("test-data").join(file);
+                let data = fs::read_to_string(path).unwrap();
+
+                ReplayEvent::new(
+                    http::Request::builder()
+                        .body(SdkBody::from("request body"))
+                        .unwrap(),
+
+                    http::Response::builder()
+                        .status(200)
+                        .body(SdkBody::from(data))
+                        .unwrap(),
+                )
+            })
+            .collect();
+
+        let http_client = StaticReplayClient::new(events);
+
+        let creds = Credentials::for_tests_with_session_token();
+
+        let conf = S3Config::builder()
+            .behavior_version_latest()
+            .credentials_provider(creds)
+            .http_client(http_client)
+            .region(aws_sdk_s3::config::Region::new("eu-west-1"))
+            .build();
+
+        let client = S3Client::from_conf(conf);
+
+        Client {
+            client,
+            bucket_names:  bucket_names.into_iter().map(String::from).collect(),
+            count:         true,
+            export_bucket: "lens-destination".into(),
+            export_key:    export_key.into(),
+            report:        OnceCell::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_buckets() {
+        let tests = vec![
+            (vec![],                       vec!["a-bucket", "b-bucket"]),
+            (vec!["b-bucket"],             vec!["b-bucket"]),
+            (vec!["c-bucket"],             vec![]),
+            (vec!["a-bucket", "b-bucket"], vec!["a-bucket", "b-bucket"]),
+        ];
+
+        for test in tests {
+            let bucket_names = test.0;
+            let expected     = test.1;
+
+            let client = mock_client(
+                vec![
+                    "storage-lens-manifest.json",
+                    "storage-lens-report.csv",
+                ],
+                "lens/dt=2024-01-02/manifest.json",
+                bucket_names,
+            );
+
+            let buckets = client.buckets().await.unwrap();
+
+            let names: Vec<&str> = buckets.iter()
+                .map(|bucket| bucket.name.as_str())
+                .collect();
+
+            assert_eq!(names, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_buckets_latest_manifest() {
+        // The manifest is found again when the report is loaded.
+        let client = mock_client(
+            vec![
+                "storage-lens-list-objects.xml",
+                "storage-lens-list-objects.xml",
+                "storage-lens-manifest.json",
+                "storage-lens-report.csv",
+            ],
+            "lens/",
+            vec![],
+        );
+
+        let manifest_key = client.manifest_key().await.unwrap();
+
+        assert_eq!(
+            manifest_key,
+            "lens/StorageLens/123456789012/lens-config/V_1/manifests/dt=2024-01-02/manifest.json",
+        );
+
+        let buckets = client.buckets().await.unwrap();
+
+        let regions: Vec<&str> = buckets.iter()
+            .map(|bucket| bucket.region.as_ref().unwrap().name())
+            .collect();
+
+        assert_eq!(regions, vec!["eu-west-1", "us-east-1"]);
+    }
+
+    #[tokio::test]
+    async fn test_bucket_size() {
+        let client = mock_client(
+            vec![
+                "storage-lens-manifest.json",
+                "storage-lens-report.csv",
+            ],
+            "lens/dt=2024-01-02/manifest.json",
+            vec![],
+        );
+
+        let buckets = client.buckets().await.unwrap();
+        let size    = client.bucket_size(&buckets[0]).await.unwrap();
+
+        let expected = BucketSize {
+            bytes:           3072,
+            count:           Some(3),
+            estimated:       true,
+            storage_classes: Some(BTreeMap::from([
+                ("STANDARD".into(),    1024),
+                ("STANDARD_IA".into(), 2048),
+            ])),
+            ..Default::default()
+        };
+
+        assert_eq!(size, expected);
+    }
+}
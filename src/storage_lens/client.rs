@@ -0,0 +1,252 @@
+// Implements the Storage Lens Client
+#![forbid(unsafe_code)]
+#![deny(missing_docs)]
+use anyhow::{
+    anyhow,
+    Context,
+    Result,
+};
+use aws_sdk_s3::client::Client as S3Client;
+use crate::common::ClientConfig;
+use crate::s3;
+use once_cell::sync::OnceCell;
+use super::report::{
+    Manifest,
+    Report,
+};
+use tracing::debug;
+
+/// The name of the manifest file written with each Storage Lens export.
+const MANIFEST: &str = "manifest.json";
+
+/// Return the date of the export that the manifest at `key` belongs to.
+///
+/// Storage Lens writes each day's manifest under a `dt=YYYY-MM-DD` prefix,
+/// an empty date is returned if there isn't one.
+fn manifest_date(key: &str) -> &str {
+    key.split('/')
+        .find_map(|part| part.strip_prefix("dt="))
+        .unwrap_or_default()
+}
+
+/// Split an S3 URI, such as `s3://bucket/prefix`, into its bucket and key.
+///
+/// The key may be empty, but the bucket may not.
+pub fn parse_s3_uri(s: &str) -> Option<(&str, &str)> {
+    let path = s.strip_prefix("s3://")?;
+
+    let (bucket, key) = path.split_once('/')
+        .unwrap_or((path, ""));
+
+    if bucket.is_empty() {
+        return None;
+    }
+
+    Some((bucket, key))
+}
+
+/// The Storage Lens `Client`.
+///
+/// This reads bucket sizes from an S3 Storage Lens metrics export, rather than
+/// from `CloudWatch` or by listing objects.
+pub struct Client {
+    /// The AWS SDK `S3Client` used to read the export.
+    pub client: S3Client,
+
+    /// Selected bucket names, if any.
+    pub bucket_names: Vec<String>,
+
+    /// Whether the number of objects in each bucket should be reported.
+    pub count: bool,
+
+    /// Bucket that the export was written to.
+    pub export_bucket: String,
+
+    /// Key of the export's `manifest.json`, or a prefix to find the latest
+    /// manifest under.
+    pub export_key: String,
+
+    /// The report read from the export, once it has been loaded.
+    pub report: OnceCell<Report>,
+}
+
+impl Client {
+    /// Return a new Storage Lens `Client` with the given `ClientConfig`.
+    pub async fn new(config: ClientConfig) -> Self {
+        let bucket_names = config.bucket_names.clone();
+        let count        = config.count;
+
+        // This should be safe, we validated this in the CLI parser.
+        let export = config.storage_lens_export.clone()
+            .expect("storage lens export");
+
+        let (export_bucket, export_key) = parse_s3_uri(&export)
+            .expect("storage lens export");
+
+        debug!(
+            "new: Reading Storage Lens export from '{}' in '{}'",
+            export_key,
+            export_bucket,
+        );
+
+        // The export is read with a regular S3 client, configured in the same
+        // way as for S3 mode.
+        let client = s3::Client::new(config).await.client;
+
+        Self {
+            bucket_names,
+            client,
+            count,
+            export_bucket: export_bucket.into(),
+            export_key:    export_key.into(),
+            report:        OnceCell::new(),
+        }
+    }
+
+    /// Return the contents of the object at `key` in `bucket`.
+    async fn get_object(&self, bucket: &str, key: &str) -> Result<String> {
+        debug!("get_object: Reading '{}' from '{}'", key, bucket);
+
+        let output = self.client.get_object()
+            .bucket(bucket)
+            .key(key)
+            .send()
+            .await
+            .with_context(|| format!("Couldn't get '{key}' from '{bucket}'"))?;
+
+        let bytes = output.body
+            .collect()
+            .await
+            .with_context(|| format!("Couldn't read '{key}' from '{bucket}'"))?
+            .into_bytes();
+
+        let contents = String::from_utf8(bytes.to_vec())
+            .with_context(|| format!("'{key}' in '{bucket}' isn't UTF-8"))?;
+
+        Ok(contents)
+    }
+
+    /// Return the key of the manifest to read.
+    ///
+    /// If the export key is a manifest it is used as is, otherwise the
+    /// manifest of the latest export under the export key is found.
+    pub async fn manifest_key(&self) -> Result<String> {
+        if self.export_key.ends_with(MANIFEST) {
+            return Ok(self.export_key.clone());
+        }
+
+        debug!(
+            "manifest_key: Finding latest manifest under '{}'",
+            self.export_key,
+        );
+
+        let mut pages = self.client.list_objects_v2()
+            .bucket(&self.export_bucket)
+            .prefix(&self.export_key)
+            .into_paginator()
+            .send();
+
+        let mut latest: Option<String> = None;
+
+        while let Some(page) = pages.next().await {
+            let page = page?;
+
+            let keys = page.contents()
+                .iter()
+                .filter_map(|object| object.key())
+                .filter(|key| key.ends_with(&format!("/{MANIFEST}")));
+
+            for key in keys {
+                let is_later = latest.as_deref().map_or(true, |latest| {
+                    (manifest_date(key), key) > (manifest_date(latest), latest)
+                });
+
+                if is_later {
+                    latest = Some(key.to_string());
+                }
+            }
+        }
+
+        latest.ok_or_else(|| {
+            anyhow!(
+                "No Storage Lens manifest found under '{}' in '{}'",
+                self.export_key,
+                self.export_bucket,
+            )
+        })
+    }
+
+    /// Return the Storage Lens `Report`, loading it from the export on first
+    /// use.
+    pub async fn report(&self) -> Result<&Report> {
+        if let Some(report) = self.report.get() {
+            return Ok(report);
+        }
+
+        let manifest_key = self.manifest_key().await?;
+        let manifest     = self.get_object(&self.export_bucket, &manifest_key)
+            .await?;
+
+        let manifest = Manifest::parse(&manifest)
+            .with_context(|| format!("Couldn't parse '{manifest_key}'"))?;
+
+        let mut report = Report::default();
+
+        for file in &manifest.report_files {
+            let data = self.get_object(
+                manifest.destination_bucket_name(),
+                &file.key,
+            ).await?;
+
+            report.add_csv(&manifest.report_schema, &data)
+                .with_context(|| format!("Couldn't parse '{}'", file.key))?;
+        }
+
+        debug!("report: Found {} buckets", report.0.len());
+
+        Ok(self.report.get_or_init(|| report))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_manifest_date() {
+        let tests = vec![
+            ("lens/V_1/manifests/dt=2024-01-02/manifest.json", "2024-01-02"),
+            ("dt=2024-01-02/manifest.json",                    "2024-01-02"),
+            ("lens/manifest.json",                             ""),
+        ];
+
+        for test in tests {
+            let key      = test.0;
+            let expected = test.1;
+
+            assert_eq!(manifest_date(key), expected, "key {key}");
+        }
+    }
+
+    #[test]
+    fn test_parse_s3_uri() {
+        let tests = vec![
+            ("s3://bucket",                    Some(("bucket", ""))),
+            ("s3://bucket/",                   Some(("bucket", ""))),
+            ("s3://bucket/prefix/",            Some(("bucket", "prefix/"))),
+            ("s3://bucket/dt=1/manifest.json", Some(("bucket", "dt=1/manifest.json"))),
+            ("s3://",                          None),
+            ("s3:///prefix",                   None),
+            ("https://bucket/prefix",          None),
+            ("bucket/prefix",                  None),
+        ];
+
+        for test in tests {
+            let uri      = test.0;
+            let expected = test.1;
+
+            assert_eq!(parse_s3_uri(uri), expected, "uri {uri}");
+        }
+    }
+}
@@ -0,0 +1,354 @@
+// Parses S3 Storage Lens metrics exports
+#![forbid(unsafe_code)]
+#![deny(missing_docs)]
+use anyhow::{
+    anyhow,
+    Result,
+};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// The only report format that we can parse.
+const CSV_FORMAT: &str = "CSV";
+
+/// A single report file listed in a `Manifest`.
+#[derive(Debug, Deserialize, Eq, PartialEq)]
+pub struct ReportFile {
+    /// Key of the report file within the destination bucket.
+    pub key: String,
+}
+
+/// The `manifest.json` written alongside each Storage Lens export.
+#[derive(Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Manifest {
+    /// ARN of the bucket that the report files were written to.
+    pub destination_bucket: String,
+
+    /// Format of the report files, either `CSV` or `Parquet`.
+    pub report_format: String,
+
+    /// Comma separated names of the columns in the report files.
+    pub report_schema: String,
+
+    /// The report files making up the export.
+    pub report_files: Vec<ReportFile>,
+}
+
+impl Manifest {
+    /// Parse a `Manifest` from the JSON in `s`.
+    ///
+    /// Only CSV exports can be read, an error is returned for any other
+    /// format.
+    pub fn parse(s: &str) -> Result<Self> {
+        let manifest: Self = serde_json::from_str(s)?;
+
+        if manifest.report_format != CSV_FORMAT {
+            return Err(anyhow!(
+                "Unsupported Storage Lens report format '{}', only CSV exports can be read",
+                manifest.report_format,
+            ));
+        }
+
+        Ok(manifest)
+    }
+
+    /// Returns the name of the bucket that the report files were written to.
+    ///
+    /// The manifest gives this as an ARN, such as `arn:aws:s3:::bucket`.
+    pub fn destination_bucket_name(&self) -> &str {
+        self.destination_bucket
+            .rsplit(':')
+            .next()
+            .unwrap_or_default()
+    }
+}
+
+/// The storage metrics of a single bucket in a `Report`.
+#[derive(Debug, Default, Eq, PartialEq)]
+pub struct ReportBucket {
+    /// Region that the bucket is in.
+    pub region: String,
+
+    /// Total size of the bucket in bytes.
+    pub bytes: u64,
+
+    /// Number of objects in the bucket, if reported.
+    pub count: Option<u64>,
+
+    /// Size of each storage class used by the bucket in bytes.
+    pub storage_classes: BTreeMap<String, u64>,
+}
+
+/// The positions of the columns that we use within each report row.
+struct Columns {
+    /// Position of `aws_region`.
+    region: usize,
+
+    /// Position of `storage_class`.
+    storage_class: usize,
+
+    /// Position of `record_type`.
+    record_type: usize,
+
+    /// Position of `bucket_name`.
+    bucket_name: usize,
+
+    /// Position of `metric_name`.
+    metric_name: usize,
+
+    /// Position of `metric_value`.
+    metric_value: usize,
+}
+
+impl Columns {
+    /// Find the position of each of the columns that we use in `schema`.
+    fn new(schema: &[String]) -> Result<Self> {
+        let position = |name: &str| {
+            schema.iter()
+                .position(|column| column == name)
+                .ok_or_else(|| {
+                    anyhow!("Storage Lens report is missing the '{}' column", name)
+                })
+        };
+
+        let columns = Self {
+            region:        position("aws_region")?,
+            storage_class: position("storage_class")?,
+            record_type:   position("record_type")?,
+            bucket_name:   position("bucket_name")?,
+            metric_name:   position("metric_name")?,
+            metric_value:  position("metric_value")?,
+        };
+
+        Ok(columns)
+    }
+}
+
+/// The bucket level storage metrics from a Storage Lens export, keyed by
+/// bucket name.
+#[derive(Debug, Default, Eq, PartialEq)]
+pub struct Report(pub BTreeMap<String, ReportBucket>);
+
+impl Report {
+    /// Add the rows of the CSV report file `data` to the `Report`, using the
+    /// column names in the comma separated `schema`.
+    ///
+    /// Only `BUCKET` records of the `StorageBytes` and `ObjectCount` metrics
+    /// are used, a bucket's metrics are reported once per storage class and
+    /// are summed here.
+    pub fn add_csv(&mut self, schema: &str, data: &str) -> Result<()> {
+        let schema  = csv_fields(schema);
+        let columns = Columns::new(&schema)?;
+
+        for (i, line) in data.lines().enumerate() {
+            let fields = csv_fields(line);
+
+            // Report files may repeat the schema as a header row.
+            if line.trim().is_empty() || fields == schema {
+                continue;
+            }
+
+            let field = |position: usize| {
+                fields.get(position)
+                    .map(String::as_str)
+                    .ok_or_else(|| {
+                        anyhow!("Storage Lens report line {} is too short", i + 1)
+                    })
+            };
+
+            if field(columns.record_type)? != "BUCKET" {
+                continue;
+            }
+
+            let metric_name = field(columns.metric_name)?;
+
+            if !matches!(metric_name, "StorageBytes" | "ObjectCount") {
+                continue;
+            }
+
+            let value = field(columns.metric_value)?;
+            let value = parse_metric_value(value).ok_or_else(|| {
+                anyhow!(
+                    "Invalid metric value '{}' on Storage Lens report line {}",
+                    value,
+                    i + 1,
+                )
+            })?;
+
+            let bucket = self.0
+                .entry(field(columns.bucket_name)?.to_string())
+                .or_insert_with(|| ReportBucket {
+                    region: field(columns.region)
+                        .unwrap_or_default()
+                        .to_string(),
+                    ..Default::default()
+                });
+
+            if metric_name == "StorageBytes" {
+                let storage_class = field(columns.storage_class)?;
+
+                bucket.bytes += value;
+
+                *bucket.storage_classes
+                    .entry(storage_class.to_string())
+                    .or_insert(0) += value;
+            }
+            else {
+                *bucket.count.get_or_insert(0) += value;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse a metric value, which should be a whole number but may be written
+/// as a float, such as `1.0E9`.
+#[allow(clippy::cast_possible_truncation)]
+#[allow(clippy::cast_sign_loss)]
+fn parse_metric_value(value: &str) -> Option<u64> {
+    if let Ok(value) = value.parse::<u64>() {
+        return Some(value);
+    }
+
+    value.parse::<f64>()
+        .ok()
+        .filter(|value| value.is_finite() && *value >= 0.0)
+        .map(|value| value.round() as u64)
+}
+
+/// Split a CSV `line` into its fields.
+///
+/// Fields may be quoted, in which case they can contain commas and a quote is
+/// written as `""`.
+fn csv_fields(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field  = String::new();
+    let mut quoted = false;
+    let mut chars  = line.trim_end_matches(['\r', '\n']).chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.next_if_eq(&'"').is_some() => field.push('"'),
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(field.split_off(0)),
+            c => field.push(c),
+        }
+    }
+
+    fields.push(field);
+
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::fs;
+    use std::path::Path;
+
+    fn read_test_data(data_file: &str) -> String {
+        let path = Path::new("test-data").join(data_file);
+
+        fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn test_csv_fields() {
+        let tests = vec![
+            ("a,b,c",            vec!["a", "b", "c"]),
+            ("a,,c\r\n",         vec!["a", "", "c"]),
+            ("\"a,b\",c",        vec!["a,b", "c"]),
+            ("\"a \"\"b\"\"\",c", vec!["a \"b\"", "c"]),
+            ("",                 vec![""]),
+        ];
+
+        for test in tests {
+            let line     = test.0;
+            let expected = test.1;
+
+            assert_eq!(csv_fields(line), expected, "line {line:?}");
+        }
+    }
+
+    #[test]
+    fn test_manifest_parse() {
+        let manifest = read_test_data("storage-lens-manifest.json");
+        let manifest = Manifest::parse(&manifest).unwrap();
+
+        assert_eq!(manifest.destination_bucket_name(), "lens-destination");
+        assert_eq!(manifest.report_files, vec![
+            ReportFile {
+                key: "lens/StorageLens/123456789012/lens-config/V_1/reports/dt=2024-01-02/report.csv".into(),
+            },
+        ]);
+    }
+
+    #[test]
+    fn test_manifest_parse_parquet() {
+        let manifest = read_test_data("storage-lens-manifest.json")
+            .replace("\"CSV\"", "\"Parquet\"");
+
+        let ret = Manifest::parse(&manifest);
+
+        assert!(ret.is_err());
+    }
+
+    #[test]
+    fn test_report_add_csv() {
+        let manifest = read_test_data("storage-lens-manifest.json");
+        let manifest = Manifest::parse(&manifest).unwrap();
+        let data     = read_test_data("storage-lens-report.csv");
+
+        let mut report = Report::default();
+        report.add_csv(&manifest.report_schema, &data).unwrap();
+
+        let expected = Report(BTreeMap::from([
+            ("a-bucket".into(), ReportBucket {
+                region:          "eu-west-1".into(),
+                bytes:           3072,
+                count:           Some(3),
+                storage_classes: BTreeMap::from([
+                    ("STANDARD".into(),    1024),
+                    ("STANDARD_IA".into(), 2048),
+                ]),
+            }),
+            ("b-bucket".into(), ReportBucket {
+                region:          "us-east-1".into(),
+                bytes:           1_000_000_000,
+                count:           None,
+                storage_classes: BTreeMap::from([
+                    ("STANDARD".into(), 1_000_000_000),
+                ]),
+            }),
+        ]));
+
+        assert_eq!(report, expected);
+    }
+
+    #[test]
+    fn test_report_add_csv_err() {
+        let schema = "record_type,bucket_name,metric_name,metric_value";
+
+        let tests = vec![
+            // Missing columns in the schema.
+            ("BUCKET,a-bucket,StorageBytes,1", "bucket_name,metric_value"),
+            // Invalid metric value.
+            ("BUCKET,a-bucket,StorageBytes,lots", schema),
+            // Short row.
+            ("BUCKET,a-bucket", schema),
+        ];
+
+        for test in tests {
+            let data   = test.0;
+            let schema = test.1;
+
+            let mut report = Report::default();
+            let ret = report.add_csv(schema, data);
+
+            assert!(ret.is_err(), "{data}");
+        }
+    }
+}
@@ -0,0 +1,251 @@
+// sts: Looks up the AWS account that our credentials belong to
+#![forbid(unsafe_code)]
+#![deny(missing_docs)]
+use aws_sdk_sts::client::Client as StsClient;
+use aws_sdk_sts::error::DisplayErrorContext;
+use crate::common::{
+    ClientConfig,
+    Region,
+};
+use crate::connection;
+use crate::credentials;
+use tracing::{
+    debug,
+    warn,
+};
+
+/// The settings needed to create an STS `Client`.
+///
+/// These are taken from the `ClientConfig` up front, so that the `Client` is
+/// only created, and its credentials only loaded, if it's needed.
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// External ID to use when assuming `role_arn`.
+    external_id: Option<String>,
+
+    /// Maximum number of connections to keep open.
+    max_connections: Option<usize>,
+
+    /// AWS profile to load credentials from.
+    profile: Option<String>,
+
+    /// HTTP proxy to connect through.
+    proxy: Option<String>,
+
+    /// Region to create the client in.
+    region: Region,
+
+    /// IAM role to assume.
+    role_arn: Option<String>,
+}
+
+impl From<&ClientConfig> for Config {
+    fn from(config: &ClientConfig) -> Self {
+        Self {
+            external_id:     config.external_id.clone(),
+            max_connections: config.max_connections,
+            profile:         config.profile.clone(),
+            proxy:           config.proxy.clone(),
+            region:          config.region.clone(),
+            role_arn:        config.role_arn.clone(),
+        }
+    }
+}
+
+/// The STS `Client`.
+pub struct Client {
+    /// The AWS SDK `StsClient`.
+    client: StsClient,
+}
+
+impl Client {
+    /// Return a new STS `Client` using the region, profile, role, proxy and
+    /// connection settings from the given `Config`.
+    ///
+    /// No requests are made until the account ID is asked for.
+    pub async fn new(config: &Config) -> Self {
+        let sts_config = aws_config::from_env()
+            .region(config.region.clone());
+
+        let sts_config = if let Some(profile) = &config.profile {
+            sts_config.profile_name(profile)
+        }
+        else {
+            sts_config
+        };
+
+        let http_client = connection::http_client(
+            config.proxy.as_deref(),
+            config.max_connections,
+        );
+
+        let sts_config = if let Some(http_client) = http_client {
+            sts_config.http_client(http_client)
+        }
+        else {
+            sts_config
+        };
+
+        let sts_config = sts_config
+            .load()
+            .await;
+
+        let sts_config = credentials::assume_role(
+            sts_config,
+            config.role_arn.as_deref(),
+            config.external_id.as_deref(),
+        ).await;
+
+        let client = StsClient::new(&sts_config);
+
+        Self {
+            client,
+        }
+    }
+
+    /// Return the ID of the AWS account that our credentials belong to.
+    ///
+    /// The account ID only enriches the output, so if it can't be found, for
+    /// example because access to STS is denied, a warning is logged and `None`
+    /// is returned.
+    pub async fn account_id(&self) -> Option<String> {
+        debug!("account_id: Calling GetCallerIdentity");
+
+        match self.client.get_caller_identity().send().await {
+            Ok(output) => output.account().map(ToOwned::to_owned),
+            Err(e)     => {
+                warn!(
+                    "Unable to find the AWS account ID, it will be omitted: {}",
+                    DisplayErrorContext(&e),
+                );
+
+                None
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_credential_types::Credentials;
+    use aws_sdk_sts::config::Config as StsConfig;
+    use aws_smithy_runtime::client::http::test_util::{
+        ReplayEvent,
+        StaticReplayClient,
+    };
+    use aws_smithy_types::body::SdkBody;
+    use aws_config::BehaviorVersion;
+    use aws_credential_types::provider::ProvideCredentials;
+    use aws_runtime::env_config::file::{
+        EnvConfigFileKind,
+        EnvConfigFiles,
+    };
+    use pretty_assertions::assert_eq;
+    use std::env;
+    use std::fs;
+    use std::path::Path;
+    use std::process;
+
+    // An IAM Identity Center profile, using an `sso_session`.
+    const SSO_SESSION_CONFIG: &str = "\
+[profile s3du-sso]
+sso_session = s3du-test-session
+sso_account_id = 123456789012
+sso_role_name = ReadOnly
+region = eu-west-1
+
+[sso-session s3du-test-session]
+sso_region = eu-west-1
+sso_start_url = https://example.awsapps.com/start
+";
+
+    // Create a mock STS client, responding with the given status and the data
+    // from the specified data_file.
+    fn mock_client(status: u16, data_file: &str) -> Client {
+        let path = Path::new("test-data").join(data_file);
+        let data = fs::read_to_string(path).unwrap();
+
+        let http_client = StaticReplayClient::new(vec![
+            ReplayEvent::new(
+                http::Request::builder()
+                    .body(SdkBody::from("request body"))
+                    .unwrap(),
+
+                http::Response::builder()
+                    .status(status)
+                    .body(SdkBody::from(data))
+                    .unwrap(),
+            ),
+        ]);
+
+        let creds = Credentials::for_tests_with_session_token();
+
+        let conf = StsConfig::builder()
+            .behavior_version_latest()
+            .credentials_provider(creds)
+            .http_client(http_client)
+            .region(aws_sdk_sts::config::Region::new("eu-west-1"))
+            .build();
+
+        Client {
+            client: StsClient::from_conf(conf),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_account_id() {
+        let tests = vec![
+            (200, "sts-get-caller-identity.xml", Some("123456789012")),
+            (403, "sts-access-denied.xml",       None),
+        ];
+
+        for test in tests {
+            let status    = test.0;
+            let data_file = test.1;
+            let expected  = test.2;
+
+            let client = mock_client(status, data_file);
+
+            let ret = client.account_id().await;
+
+            assert_eq!(ret.as_deref(), expected);
+        }
+    }
+
+    // Profiles using an `sso_session` get their credentials from the SSO
+    // token cache, which is where resolution fails when there's no cached
+    // token, rather than failing to find static credentials.
+    #[tokio::test]
+    async fn test_sso_session_profile() {
+        let dir = env::temp_dir()
+            .join(format!("s3du-test-sso-{}", process::id()));
+
+        fs::create_dir_all(&dir).unwrap();
+
+        let config = dir.join("config");
+
+        fs::write(&config, SSO_SESSION_CONFIG).unwrap();
+
+        let profile_files = EnvConfigFiles::builder()
+            .with_file(EnvConfigFileKind::Config, &config)
+            .build();
+
+        let sdk_config = aws_config::defaults(BehaviorVersion::latest())
+            .profile_files(profile_files)
+            .profile_name("s3du-sso")
+            .load()
+            .await;
+
+        let ret = sdk_config.credentials_provider()
+            .unwrap()
+            .provide_credentials()
+            .await;
+
+        fs::remove_dir_all(&dir).unwrap();
+
+        let error = DisplayErrorContext(ret.unwrap_err()).to_string();
+
+        assert!(error.contains("failed to load the cached SSO token"), "{error}");
+    }
+}